@@ -0,0 +1,57 @@
+-- Tables use IF NOT EXISTS so that databases created from the former db/init.sql
+-- can be brought under the migration system as is.
+
+CREATE TABLE IF NOT EXISTS member (
+  id CHAR(6) PRIMARY KEY,
+  phone VARCHAR(15) UNIQUE NOT NULL, -- E.164 format, digits only
+  password VARCHAR(127) NOT NULL,
+  email VARCHAR(255) UNIQUE,
+  first_name VARCHAR(63),
+  last_name VARCHAR(63),
+  is_admin BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE IF NOT EXISTS reservation (
+  id CHAR(6) PRIMARY KEY,
+  member_id CHAR(6) NOT NULL REFERENCES member(id) ON DELETE CASCADE,
+  court_number SMALLINT NOT NULL CHECK (court_number BETWEEN 1 AND 4),
+  reservation_date DATE NOT NULL,
+  reservation_time SMALLINT NOT NULL CHECK (reservation_time BETWEEN 0 AND 23), -- 24h format
+  CONSTRAINT unique_court_date_time UNIQUE (court_number, reservation_date, reservation_time),
+  CONSTRAINT one_reservation_per_day UNIQUE (member_id, reservation_date)
+);
+
+CREATE TABLE IF NOT EXISTS password_reset_token (
+    token UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    member_id CHAR(6) NOT NULL REFERENCES member(id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL DEFAULT NOW() + INTERVAL '1 hour'
+);
+
+CREATE TABLE IF NOT EXISTS session (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    member_id CHAR(6) NOT NULL REFERENCES member(id) ON DELETE CASCADE,
+    refresh_token_hash CHAR(64) UNIQUE NOT NULL, -- SHA-256, hex encoded
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP NOT NULL,
+    revoked_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS session_member_id_idx ON session (member_id);
+
+CREATE TABLE IF NOT EXISTS login_attempt (
+    id BIGSERIAL PRIMARY KEY,
+    phone VARCHAR(15) NOT NULL,
+    ip_address VARCHAR(45) NOT NULL,
+    succeeded BOOLEAN NOT NULL,
+    attempted_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS login_attempt_phone_idx ON login_attempt (phone, attempted_at);
+CREATE INDEX IF NOT EXISTS login_attempt_ip_address_idx ON login_attempt (ip_address, attempted_at);
+
+CREATE TABLE IF NOT EXISTS login_lockout (
+    phone VARCHAR(15) PRIMARY KEY,
+    lockout_count INTEGER NOT NULL DEFAULT 1,
+    locked_until TIMESTAMP NOT NULL
+);
@@ -0,0 +1,29 @@
+-- History of the jobs run by the in-process scheduler, which replaces pg_cron.
+
+CREATE TABLE job_run (
+  id BIGSERIAL PRIMARY KEY,
+  job_name VARCHAR(63) NOT NULL,
+  started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  finished_at TIMESTAMPTZ,
+  succeeded BOOLEAN,
+  rows_affected BIGINT,
+  error TEXT
+);
+
+CREATE INDEX idx_job_run_job_name_started_at ON job_run(job_name, started_at DESC);
+
+-- Databases created from the former db/init.sql still have the pg_cron jobs scheduled
+DO $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_cron') THEN
+    PERFORM cron.unschedule(jobname)
+    FROM cron.job
+    WHERE jobname IN (
+      'cleanup_old_reservations',
+      'cleanup_expired_tokens',
+      'cleanup_expired_sessions',
+      'cleanup_old_login_attempts'
+    );
+  END IF;
+END
+$$;
@@ -0,0 +1,22 @@
+-- Courts become data: reservations reference them instead of a hard-coded range.
+
+CREATE TABLE court (
+  number SMALLINT PRIMARY KEY CHECK (number > 0),
+  name VARCHAR(63) NOT NULL,
+  surface VARCHAR(31) NOT NULL DEFAULT 'sand',
+  is_lit BOOLEAN NOT NULL DEFAULT FALSE,
+  is_active BOOLEAN NOT NULL DEFAULT TRUE,
+  display_order SMALLINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO court (number, name, is_lit, display_order) VALUES
+  (1, 'Terrain 1', TRUE, 1),
+  (2, 'Terrain 2', TRUE, 2),
+  (3, 'Terrain 3', TRUE, 3),
+  (4, 'Terrain 4', TRUE, 4);
+
+ALTER TABLE reservation DROP CONSTRAINT IF EXISTS reservation_court_number_check;
+
+ALTER TABLE reservation
+  ADD CONSTRAINT reservation_court_number_fkey
+  FOREIGN KEY (court_number) REFERENCES court(number) ON UPDATE CASCADE;
@@ -0,0 +1,22 @@
+-- Opening hours per weekday, for every court or a single one, possibly limited to a season.
+
+CREATE TABLE opening_hours (
+  id SERIAL PRIMARY KEY,
+  court_number SMALLINT REFERENCES court(number) ON UPDATE CASCADE ON DELETE CASCADE, -- NULL for every court
+  weekday SMALLINT NOT NULL CHECK (weekday BETWEEN 1 AND 7), -- ISO 8601, Monday is 1
+  opens_at TIME,
+  closes_at TIME,
+  slot_minutes SMALLINT NOT NULL DEFAULT 60 CHECK (slot_minutes > 0),
+  valid_from DATE, -- NULL for no start
+  valid_until DATE, -- NULL for no end
+  is_closed BOOLEAN NOT NULL DEFAULT FALSE,
+  CHECK (is_closed OR (opens_at IS NOT NULL AND closes_at IS NOT NULL AND opens_at < closes_at)),
+  CHECK (valid_from IS NULL OR valid_until IS NULL OR valid_from <= valid_until)
+);
+
+CREATE INDEX idx_opening_hours_weekday ON opening_hours(weekday);
+
+-- Tuesdays and Thursdays from 16:00 to 21:00, in one-hour slots
+INSERT INTO opening_hours (weekday, opens_at, closes_at, slot_minutes) VALUES
+  (2, '16:00', '21:00', 60),
+  (4, '16:00', '21:00', 60);
@@ -0,0 +1,27 @@
+-- Reservations get a start time and a duration instead of a start hour, and overlapping
+-- reservations of a court are rejected by an exclusion constraint.
+
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+ALTER TABLE reservation ADD COLUMN start_time TIME;
+UPDATE reservation SET start_time = make_time(reservation_time, 0, 0);
+ALTER TABLE reservation ALTER COLUMN start_time SET NOT NULL;
+
+ALTER TABLE reservation
+  ADD COLUMN duration_minutes SMALLINT NOT NULL DEFAULT 60
+  CHECK (duration_minutes IN (30, 60, 90, 120));
+
+ALTER TABLE reservation
+  ADD COLUMN period TSRANGE NOT NULL GENERATED ALWAYS AS (
+    tsrange(
+      reservation_date + start_time,
+      reservation_date + start_time + duration_minutes * INTERVAL '1 minute'
+    )
+  ) STORED;
+
+ALTER TABLE reservation DROP CONSTRAINT unique_court_date_time;
+ALTER TABLE reservation DROP COLUMN reservation_time;
+
+ALTER TABLE reservation
+  ADD CONSTRAINT no_overlapping_reservations
+  EXCLUDE USING gist (court_number WITH =, period WITH &&);
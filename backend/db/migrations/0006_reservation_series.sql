@@ -0,0 +1,20 @@
+-- Recurring reservations: a series materialises one reservation per occurrence.
+
+CREATE TABLE reservation_series (
+  id CHAR(6) PRIMARY KEY,
+  member_id CHAR(6) NOT NULL REFERENCES member(id) ON DELETE CASCADE,
+  court_number SMALLINT NOT NULL REFERENCES court(number) ON UPDATE CASCADE,
+  start_time TIME NOT NULL,
+  duration_minutes SMALLINT NOT NULL CHECK (duration_minutes IN (30, 60, 90, 120)),
+  first_date DATE NOT NULL,
+  interval_weeks SMALLINT NOT NULL CHECK (interval_weeks IN (1, 2)),
+  until_date DATE,
+  occurrence_count SMALLINT CHECK (occurrence_count > 0),
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  CHECK ((until_date IS NULL) <> (occurrence_count IS NULL))
+);
+
+ALTER TABLE reservation
+  ADD COLUMN series_id CHAR(6) REFERENCES reservation_series(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_reservation_series_id ON reservation(series_id);
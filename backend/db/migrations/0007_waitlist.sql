@@ -0,0 +1,19 @@
+-- Members waiting for a booked slot, promoted in order when it is freed.
+
+CREATE TABLE waitlist_entry (
+  id SERIAL PRIMARY KEY,
+  member_id CHAR(6) NOT NULL REFERENCES member(id) ON DELETE CASCADE,
+  court_number SMALLINT REFERENCES court(number) ON UPDATE CASCADE ON DELETE CASCADE, -- NULL for any court
+  reservation_date DATE NOT NULL,
+  start_time TIME NOT NULL,
+  duration_minutes SMALLINT NOT NULL CHECK (duration_minutes IN (30, 60, 90, 120)),
+  auto_book BOOLEAN NOT NULL DEFAULT FALSE, -- book the freed court instead of sending a claim link
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  claim_token UUID UNIQUE,
+  claim_court_number SMALLINT REFERENCES court(number) ON UPDATE CASCADE ON DELETE CASCADE,
+  claim_expires_at TIMESTAMPTZ,
+  CHECK ((claim_token IS NULL) = (claim_expires_at IS NULL)),
+  UNIQUE NULLS NOT DISTINCT (member_id, court_number, reservation_date, start_time)
+);
+
+CREATE INDEX idx_waitlist_entry_date ON waitlist_entry(reservation_date, start_time);
@@ -0,0 +1,22 @@
+-- Booking quotas checked by the backend, replacing the one reservation per day constraint.
+-- A single row holds the rules, NULL disabling a rule.
+
+CREATE TABLE booking_rules (
+  id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+  max_per_day SMALLINT CHECK (max_per_day > 0),
+  max_per_week SMALLINT CHECK (max_per_week > 0), -- ISO weeks, from Monday to Sunday
+  max_days_in_advance SMALLINT CHECK (max_days_in_advance >= 0),
+  min_notice_minutes INTEGER CHECK (min_notice_minutes >= 0),
+  peak_starts_at TIME,
+  peak_ends_at TIME,
+  max_peak_per_week SMALLINT CHECK (max_peak_per_week > 0),
+  CHECK ((peak_starts_at IS NULL) = (peak_ends_at IS NULL)),
+  CHECK (peak_starts_at IS NULL OR peak_starts_at < peak_ends_at),
+  CHECK (max_peak_per_week IS NULL OR peak_starts_at IS NOT NULL)
+);
+
+INSERT INTO booking_rules (max_per_day) VALUES (1);
+
+ALTER TABLE reservation DROP CONSTRAINT IF EXISTS one_reservation_per_day;
+
+CREATE INDEX idx_reservation_member_date ON reservation(member_id, reservation_date);
@@ -0,0 +1,22 @@
+-- Cancelled reservations are kept with their cancellation instead of being deleted, and no
+-- longer block their court.
+
+ALTER TABLE reservation
+  ADD COLUMN cancelled_at TIMESTAMPTZ,
+  ADD COLUMN cancelled_by CHAR(6), -- kept when the member is deleted
+  ADD COLUMN cancellation_reason TEXT,
+  ADD COLUMN is_late_cancellation BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE reservation DROP CONSTRAINT no_overlapping_reservations;
+ALTER TABLE reservation
+  ADD CONSTRAINT no_overlapping_reservations
+  EXCLUDE USING gist (court_number WITH =, period WITH &&) WHERE (cancelled_at IS NULL);
+
+-- Members cannot cancel themselves less than cancellation_cutoff_minutes before the start,
+-- and cancellations less than late_cancellation_minutes before the start are counted as late
+ALTER TABLE booking_rules
+  ADD COLUMN cancellation_cutoff_minutes INTEGER CHECK (cancellation_cutoff_minutes >= 0),
+  ADD COLUMN late_cancellation_minutes INTEGER CHECK (late_cancellation_minutes >= 0);
+
+-- Kept on the member, since past reservations are cleaned up
+ALTER TABLE member ADD COLUMN late_cancellations INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,22 @@
+-- Check-in of reservations, no-shows marked once the reservation has ended without a check-in,
+-- and suspension of the bookings of members after repeated no-shows.
+
+ALTER TABLE reservation
+  ADD COLUMN checked_in_at TIMESTAMPTZ,
+  ADD COLUMN checked_in_by CHAR(6),
+  ADD COLUMN is_no_show BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Members can check in from check_in_opens_minutes before the start until
+-- check_in_closes_minutes after it
+ALTER TABLE booking_rules
+  ADD COLUMN check_in_opens_minutes INTEGER CHECK (check_in_opens_minutes >= 0),
+  ADD COLUMN check_in_closes_minutes INTEGER CHECK (check_in_closes_minutes >= 0),
+  ADD COLUMN no_show_suspension_threshold SMALLINT CHECK (no_show_suspension_threshold > 0),
+  ADD COLUMN no_show_suspension_days SMALLINT CHECK (no_show_suspension_days > 0),
+  ADD CHECK ((no_show_suspension_threshold IS NULL) = (no_show_suspension_days IS NULL));
+
+UPDATE booking_rules SET check_in_opens_minutes = 15, check_in_closes_minutes = 15;
+
+ALTER TABLE member
+  ADD COLUMN no_shows INTEGER NOT NULL DEFAULT 0,
+  ADD COLUMN suspended_until DATE;
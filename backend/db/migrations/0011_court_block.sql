@@ -0,0 +1,25 @@
+-- Closures of courts for maintenance or bad weather, during which they cannot be booked.
+
+CREATE TABLE court_block (
+  id SERIAL PRIMARY KEY,
+  -- Blocked courts, every court if NULL
+  court_numbers SMALLINT[] CHECK (cardinality(court_numbers) > 0),
+  -- Local time of the club, like reservations
+  starts_at TIMESTAMP NOT NULL,
+  ends_at TIMESTAMP NOT NULL,
+  reason TEXT NOT NULL,
+  -- Repeats every week or every other week, until until_date included
+  interval_weeks SMALLINT CHECK (interval_weeks IN (1, 2)),
+  until_date DATE,
+  -- End of the last occurrence at the latest
+  last_ends_at TIMESTAMP NOT NULL GENERATED ALWAYS AS (
+    COALESCE(until_date + starts_at::TIME, starts_at) + (ends_at - starts_at)
+  ) STORED,
+  created_by CHAR(6),
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  CHECK (ends_at > starts_at),
+  CHECK ((interval_weeks IS NULL) = (until_date IS NULL)),
+  CHECK (until_date IS NULL OR until_date >= starts_at::DATE)
+);
+
+CREATE INDEX idx_court_block_period ON court_block(starts_at, last_ends_at);
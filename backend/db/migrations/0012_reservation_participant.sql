@@ -0,0 +1,17 @@
+-- Other members and guests playing on a reservation, members having to accept their invitation.
+
+CREATE TABLE reservation_participant (
+  id SERIAL PRIMARY KEY,
+  reservation_id CHAR(6) NOT NULL REFERENCES reservation(id) ON DELETE CASCADE,
+  member_id CHAR(6) REFERENCES member(id) ON DELETE CASCADE,
+  guest_name TEXT CHECK (guest_name <> ''),
+  -- Guests do not answer invitations and are always accepted
+  status TEXT NOT NULL DEFAULT 'invited' CHECK (status IN ('invited', 'accepted', 'declined')),
+  CHECK ((member_id IS NULL) <> (guest_name IS NULL)),
+  UNIQUE (reservation_id, member_id)
+);
+
+CREATE INDEX idx_reservation_participant_member_id ON reservation_participant(member_id);
+
+-- Whether the reservations members are invited to count towards their own quotas
+ALTER TABLE booking_rules ADD COLUMN count_participants BOOLEAN NOT NULL DEFAULT FALSE;
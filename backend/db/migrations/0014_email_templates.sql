@@ -0,0 +1,5 @@
+-- Language of the emails sent to the member.
+ALTER TABLE member ADD COLUMN locale TEXT NOT NULL DEFAULT 'fr' CHECK (locale IN ('fr', 'en', 'nl'));
+
+-- Plain text alternative of the queued emails
+ALTER TABLE email_outbox ADD COLUMN text_body TEXT NOT NULL DEFAULT '';
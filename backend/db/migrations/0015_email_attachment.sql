@@ -0,0 +1,8 @@
+-- Attachment of the queued emails, such as the calendar event of a reservation.
+ALTER TABLE email_outbox ADD COLUMN attachment_filename TEXT;
+ALTER TABLE email_outbox ADD COLUMN attachment_content_type TEXT;
+ALTER TABLE email_outbox ADD COLUMN attachment_content TEXT;
+ALTER TABLE email_outbox ADD CHECK (
+  (attachment_filename IS NULL) = (attachment_content_type IS NULL)
+  AND (attachment_filename IS NULL) = (attachment_content IS NULL)
+);
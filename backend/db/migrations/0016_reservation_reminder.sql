@@ -0,0 +1,15 @@
+-- Reminders sent before reservations, and the channels members are reminded on.
+
+ALTER TABLE member
+  ADD COLUMN email_reminders BOOLEAN NOT NULL DEFAULT TRUE,
+  ADD COLUMN sms_reminders BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- A reminder is only sent once per channel for a given start of the reservation, so that
+-- reruns of the job do not send it again while a moved reservation is reminded anew
+CREATE TABLE reservation_reminder (
+  reservation_id CHAR(6) NOT NULL REFERENCES reservation(id) ON DELETE CASCADE,
+  channel TEXT NOT NULL CHECK (channel IN ('email', 'sms')),
+  starts_at TIMESTAMP NOT NULL, -- local time of the club
+  sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (reservation_id, channel, starts_at)
+);
@@ -0,0 +1,5 @@
+-- Refresh token replaced by the last rotation of the session. Presenting it again means that
+-- it was stolen or replayed, and the session is revoked.
+ALTER TABLE session ADD COLUMN previous_refresh_token_hash CHAR(64);
+
+CREATE INDEX IF NOT EXISTS session_previous_refresh_token_hash_idx ON session (previous_refresh_token_hash);
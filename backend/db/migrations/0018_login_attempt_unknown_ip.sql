@@ -0,0 +1,4 @@
+-- Attempts whose client address could not be determined are not counted per IP address,
+-- instead of sharing a single bucket.
+ALTER TABLE login_attempt ALTER COLUMN ip_address DROP NOT NULL;
+UPDATE login_attempt SET ip_address = NULL WHERE ip_address = 'unknown';
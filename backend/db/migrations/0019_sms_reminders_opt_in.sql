@@ -0,0 +1,4 @@
+-- No text message gateway is configured yet, so members opt in to SMS reminders instead of
+-- being opted in by default.
+ALTER TABLE member ALTER COLUMN sms_reminders SET DEFAULT FALSE;
+UPDATE member SET sms_reminders = FALSE;
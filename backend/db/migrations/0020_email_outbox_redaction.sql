@@ -0,0 +1,3 @@
+-- Emails carrying credentials, such as password reset links, are kept without their content
+-- once delivered.
+ALTER TABLE email_outbox ADD COLUMN redact_when_sent BOOLEAN NOT NULL DEFAULT FALSE;
@@ -1,54 +1,80 @@
 use crate::api::auth;
 use crate::api::wrappers;
+use crate::api::wrappers::reservation_series::OccurrenceConflict;
+use crate::config::Config;
+use crate::db::migrations::{self, MigrationError};
+use crate::db::tls::{self, TlsError};
+use crate::mail::{self, Mailer};
+use crate::rules::RuleViolation;
+use crate::sms::{self, SmsGateway};
 use axum::http::HeaderMap;
-use axum::http::HeaderValue;
-use axum::http::header::AUTHORIZATION;
-use axum::http::header::CONTENT_TYPE;
+use axum::http::header::RETRY_AFTER;
 use axum::{
     Json,
-    http::{Method, StatusCode},
+    http::StatusCode,
     response::{IntoResponse, Response},
     routing::patch,
 };
 use axum::{
     Router,
-    routing::{get, post},
+    routing::{delete, get, post, put},
 };
+use chrono::{NaiveDate, NaiveTime};
 use deadpool_postgres;
 use deadpool_postgres::{ManagerConfig, Pool, RecyclingMethod, Runtime};
-use dotenvy::dotenv;
-use std::env;
+use std::sync::Arc;
 use thiserror::Error;
-use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 
+#[cfg(feature = "local")]
+use {
+    axum::http::{
+        HeaderValue, Method,
+        header::{AUTHORIZATION, CONTENT_TYPE},
+    },
+    tower_http::cors::CorsLayer,
+};
+
 #[derive(Clone)]
 pub struct AppState {
     pub pool: Pool,
+    pub config: Arc<Config>,
+    pub mailer: Arc<dyn Mailer>,
+    /// Gateway of the text messages, if they are sent.
+    pub sms: Option<Arc<dyn SmsGateway>>,
 }
 
-pub async fn build_state() -> AppState {
-    dotenv().ok();
+#[derive(Debug, Error)]
+pub enum StartupError {
+    #[error("invalid Postgres TLS configuration: {0}")]
+    Tls(#[from] TlsError),
+    #[error("could not create the Postgres connection pool: {0}")]
+    CreatePool(#[from] deadpool_postgres::CreatePoolError),
+    #[error(
+        "the certificate of the database server {host} could not be verified ({reason}), check that POSTGRES_CA_BUNDLE points to the CA bundle of your database provider"
+    )]
+    UntrustedCertificate { host: String, reason: String },
+    #[error("could not connect to the database server {host}:{port}: {source}")]
+    Connect {
+        host: String,
+        port: u16,
+        source: deadpool_postgres::PoolError,
+    },
+    #[error("could not get a database connection: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+    #[error("could not migrate the database: {0}")]
+    Migration(#[from] MigrationError),
+}
 
-    let postgres_user: String =
-        env::var("POSTGRES_USER").expect("Undefined POSTGRES_USER environment variable");
-    let postgres_db: String =
-        env::var("POSTGRES_DB").expect("Undefined POSTGRES_DB environment variable");
-    let postgres_password: String =
-        env::var("POSTGRES_PASSWORD").expect("Undefined POSTGRES_PASSWORD environment variable");
-    let postgres_host: String =
-        env::var("POSTGRES_HOST").expect("Undefined POSTGRES_HOST environment variable");
-    let postgres_port: u16 = str::parse(
-        &env::var("POSTGRES_PORT").expect("Undefined POSTGRES_PORT environment variable"),
-    )
-    .unwrap();
+pub async fn build_state(config: Config) -> Result<AppState, StartupError> {
+    let postgres = &config.postgres;
 
     let mut cfg = deadpool_postgres::Config::new();
-    cfg.host = Some(postgres_host.clone());
-    cfg.user = Some(postgres_user);
-    cfg.dbname = Some(postgres_db);
-    cfg.password = Some(postgres_password);
-    cfg.port = Some(postgres_port);
+    cfg.host = Some(postgres.host.clone());
+    cfg.user = Some(postgres.user.clone());
+    cfg.dbname = Some(postgres.db.clone());
+    cfg.password = Some(postgres.password.clone());
+    cfg.port = Some(postgres.port);
     cfg.manager = Some(ManagerConfig {
         recycling_method: RecyclingMethod::Fast,
     });
@@ -57,54 +83,72 @@ pub async fn build_state() -> AppState {
     {
         // For local development, use NoTls or disable SSL
         cfg.ssl_mode = Some(deadpool_postgres::SslMode::Disable);
-        let pool = cfg
-            .create_pool(Some(Runtime::Tokio1), tokio_postgres::NoTls)
-            .unwrap();
-        AppState { pool }
+        let pool = cfg.create_pool(Some(Runtime::Tokio1), tokio_postgres::NoTls)?;
+        check_connection(&pool, &postgres.host, postgres.port).await?;
+        if config.run_migrations {
+            migrate(&pool).await?;
+        }
+        Ok(AppState {
+            pool,
+            mailer: mail::build_mailer(&config.mail_transport, &config.smtp),
+            sms: sms::build_sms_gateway(&config.sms_transport),
+            config: Arc::new(config),
+        })
     }
 
     #[cfg(not(feature = "local"))]
     {
-        // For production with Supabase
-        if postgres_host.contains("supabase") {
-            tracing::info!("Connecting to Supabase database...");
-
-            // For Supabase, we need to accept their certificate
-            cfg.ssl_mode = Some(deadpool_postgres::SslMode::Require);
-
-            // Create a TLS connector that accepts Supabase's certificate
-            let connector = native_tls::TlsConnector::builder()
-                .danger_accept_invalid_certs(true) // Required for Supabase
-                .build()
-                .expect("Failed to create TLS connector");
+        // Certificates are verified against the CA bundle of the provider (e.g. Amazon RDS or
+        // Supabase) when one is configured, otherwise against the system and Mozilla roots
+        tracing::info!("Connecting to database {}...", postgres.host);
+        cfg.ssl_mode = Some(deadpool_postgres::SslMode::Require);
 
-            let connector = postgres_native_tls::MakeTlsConnector::new(connector);
+        let connector = tls::make_tls_connector(postgres.ca_bundle.as_deref())?;
+        let pool = cfg.create_pool(Some(Runtime::Tokio1), connector)?;
+        check_connection(&pool, &postgres.host, postgres.port).await?;
 
-            let pool = cfg
-                .create_pool(Some(Runtime::Tokio1), connector)
-                .expect("Failed to create pool with Supabase");
-
-            tracing::info!("Successfully connected to Supabase database");
-            AppState { pool }
-        } else {
-            // For other providers or if you want to disable SSL
-            tracing::info!("Connecting to non-Supabase database...");
-            cfg.ssl_mode = Some(deadpool_postgres::SslMode::Prefer);
+        tracing::info!("Successfully connected to database {}", postgres.host);
+        if config.run_migrations {
+            migrate(&pool).await?;
+        }
+        Ok(AppState {
+            pool,
+            mailer: mail::build_mailer(&config.mail_transport, &config.smtp),
+            sms: sms::build_sms_gateway(&config.sms_transport),
+            config: Arc::new(config),
+        })
+    }
+}
 
-            // Use native TLS with relaxed certificate validation
-            let connector = native_tls::TlsConnector::builder()
-                .danger_accept_invalid_certs(true)
-                .build()
-                .expect("Failed to create TLS connector");
+/// Applies the pending database migrations.
+pub async fn migrate(pool: &Pool) -> Result<(), StartupError> {
+    let mut client = pool.get().await?;
+    let applied: Vec<i32> = migrations::run(&mut client).await?;
 
-            let connector = postgres_native_tls::MakeTlsConnector::new(connector);
+    if applied.is_empty() {
+        tracing::info!("Database schema is up to date");
+    } else {
+        tracing::info!("Applied {} migration(s): {:?}", applied.len(), applied);
+    }
 
-            let pool = cfg
-                .create_pool(Some(Runtime::Tokio1), connector)
-                .expect("Failed to create pool");
+    Ok(())
+}
 
-            AppState { pool }
-        }
+/// Opens a first connection so that an unreachable or untrusted server fails at startup.
+async fn check_connection(pool: &Pool, host: &str, port: u16) -> Result<(), StartupError> {
+    match pool.get().await {
+        Ok(_) => Ok(()),
+        Err(error) => match tls::certificate_error(&error) {
+            Some(reason) => Err(StartupError::UntrustedCertificate {
+                host: host.to_string(),
+                reason,
+            }),
+            None => Err(StartupError::Connect {
+                host: host.to_string(),
+                port,
+                source: error,
+            }),
+        },
     }
 }
 
@@ -122,14 +166,90 @@ pub enum ApiError {
     NewPasswordMustBeDifferent,
     #[error("gone")]
     TokenExpired,
-    #[error("bad request")]
+    #[error("unauthorized")]
     MissingAuthToken,
     #[error("unauthorized")]
     InvalidAuthToken,
+    #[error("unauthorized")]
+    InvalidRefreshToken,
+    #[error("locked")]
+    AccountLocked { retry_after: i64 },
+    #[error("too many requests")]
+    TooManyLoginAttempts { retry_after: i64 },
+    #[error("forbidden")]
+    AdminRequired,
+    #[error("forbidden")]
+    NotProfileOwner,
+    #[error("forbidden")]
+    NotReservationOwner,
+    #[error("forbidden")]
+    CancellationDeadlinePassed { cutoff_minutes: i32 },
+    #[error("forbidden")]
+    MemberSuspended { until: NaiveDate },
+    #[error("bad request")]
+    InvalidDateRange,
+    #[error("bad request")]
+    InvalidPage,
+    #[error("unprocessable entity")]
+    CourtUnavailable,
+    #[error("unprocessable entity")]
+    InvalidDuration,
+    #[error("unprocessable entity")]
+    InvalidRecurrence,
+    #[error("unprocessable entity")]
+    CheckInClosed,
+    #[error("unprocessable entity")]
+    CourtBlocked { court_number: i16, reason: String },
+    #[error("unprocessable entity")]
+    InvalidCourtBlock,
+    #[error("conflict")]
+    SeriesConflicts(Vec<OccurrenceConflict>),
+    #[error("unprocessable entity")]
+    RuleViolations(Vec<RuleViolation>),
+    #[error("unprocessable entity")]
+    InvalidParticipants,
+    #[error("unprocessable entity")]
+    ParticipantRuleViolations {
+        member_id: String,
+        violations: Vec<RuleViolation>,
+    },
+    #[error("unprocessable entity")]
+    OutsideOpeningHours {
+        court_number: i16,
+        date: NaiveDate,
+        /// Start times of the slots of the court on that date.
+        slots: Vec<NaiveTime>,
+    },
+}
+
+impl ApiError {
+    /// Number of seconds after which the client may retry, if relevant.
+    fn retry_after(&self) -> Option<i64> {
+        match self {
+            ApiError::AccountLocked { retry_after }
+            | ApiError::TooManyLoginAttempts { retry_after } => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// Additional field of the response body, if relevant.
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        let (field, value) = match self {
+            ApiError::SeriesConflicts(conflicts) => ("conflicts", serde_json::to_value(conflicts)),
+            ApiError::RuleViolations(violations)
+            | ApiError::ParticipantRuleViolations { violations, .. } => {
+                ("violations", serde_json::to_value(violations))
+            }
+            _ => return None,
+        };
+        value.ok().map(|value| (field, value))
+    }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        let retry_after: Option<i64> = self.retry_after();
+        let details: Option<(&'static str, serde_json::Value)> = self.details();
         let (status, msg) = match self {
             ApiError::NotFound => (StatusCode::NOT_FOUND, "Resource not found".to_string()),
             ApiError::Db(db_err) => {
@@ -143,6 +263,14 @@ impl IntoResponse for ApiError {
                                 StatusCode::BAD_REQUEST,
                                 "Foreign key constraint violation".to_string(),
                             ),
+                            "23P01" => (
+                                StatusCode::CONFLICT,
+                                "The court is already booked at that time, join the waitlist to be offered it if it is freed".to_string(),
+                            ),
+                            "23514" => (
+                                StatusCode::BAD_REQUEST,
+                                "Check constraint violation".to_string(),
+                            ),
                             _ => (
                                 StatusCode::INTERNAL_SERVER_ERROR,
                                 format!("Database error: {db_err}"),
@@ -171,22 +299,166 @@ impl IntoResponse for ApiError {
             ),
             ApiError::TokenExpired => (StatusCode::GONE, "Token expired".to_string()),
             ApiError::MissingAuthToken => (
-                StatusCode::BAD_REQUEST,
+                StatusCode::UNAUTHORIZED,
                 "Authentication token required".to_string(),
             ),
             ApiError::InvalidAuthToken => (
                 StatusCode::UNAUTHORIZED,
                 "Invalid authentication token".to_string(),
             ),
+            ApiError::InvalidRefreshToken => (
+                StatusCode::UNAUTHORIZED,
+                "Invalid or expired refresh token".to_string(),
+            ),
+            ApiError::AccountLocked { .. } => (
+                StatusCode::LOCKED,
+                "Too many failed login attempts, account temporarily locked".to_string(),
+            ),
+            ApiError::TooManyLoginAttempts { .. } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many failed login attempts, please try again later".to_string(),
+            ),
+            ApiError::AdminRequired => (
+                StatusCode::FORBIDDEN,
+                "Administrator privileges required".to_string(),
+            ),
+            ApiError::NotProfileOwner => (
+                StatusCode::FORBIDDEN,
+                "You can only access your own profile".to_string(),
+            ),
+            ApiError::NotReservationOwner => (
+                StatusCode::FORBIDDEN,
+                "You can only manage your own reservations".to_string(),
+            ),
+            ApiError::CancellationDeadlinePassed { cutoff_minutes } => (
+                StatusCode::FORBIDDEN,
+                format!(
+                    "Reservations cannot be cancelled less than {cutoff_minutes} minutes before they start, please contact the club"
+                ),
+            ),
+            ApiError::MemberSuspended { until } => (
+                StatusCode::FORBIDDEN,
+                format!(
+                    "Your bookings are suspended until {} after repeated no-shows, please contact the club",
+                    until.format("%d/%m/%Y")
+                ),
+            ),
+            ApiError::InvalidDateRange => (
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Invalid date range, it must cover between 1 and {} days",
+                    crate::planning::MAX_AVAILABILITY_DAYS
+                ),
+            ),
+            ApiError::InvalidPage => (StatusCode::BAD_REQUEST, "Invalid page number".to_string()),
+            ApiError::CourtUnavailable => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "This court does not exist or is closed".to_string(),
+            ),
+            ApiError::InvalidDuration => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "A reservation lasts {} minutes",
+                    crate::planning::DURATIONS
+                        .iter()
+                        .map(i16::to_string)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            ),
+            ApiError::InvalidRecurrence => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "A series needs either an end date or a number of occurrences, and at most {} occurrences",
+                    crate::planning::MAX_OCCURRENCES
+                ),
+            ),
+            ApiError::CheckInClosed => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Check-in is not open for this reservation".to_string(),
+            ),
+            ApiError::CourtBlocked {
+                court_number,
+                reason,
+            } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Court {court_number} is closed at that time: {reason}"),
+            ),
+            ApiError::InvalidCourtBlock => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "A block must end after it starts, and a repeated block needs an end date"
+                    .to_string(),
+            ),
+            ApiError::SeriesConflicts(_) => (
+                StatusCode::CONFLICT,
+                "Some occurrences of the series cannot be booked".to_string(),
+            ),
+            ApiError::RuleViolations(violations) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                violations
+                    .iter()
+                    .map(RuleViolation::message)
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+            ApiError::InvalidParticipants => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "A reservation has at most {} participants besides its member, each being another member or a named guest",
+                    crate::planning::MAX_PARTICIPANTS
+                ),
+            ),
+            ApiError::ParticipantRuleViolations {
+                member_id,
+                violations,
+            } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "Member {member_id} cannot join this reservation: {}",
+                    violations
+                        .iter()
+                        .map(RuleViolation::message)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            ),
+            ApiError::OutsideOpeningHours {
+                court_number,
+                date,
+                slots,
+            } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                if slots.is_empty() {
+                    format!("Court {court_number} is closed on {date}")
+                } else {
+                    format!(
+                        "Outside opening hours, court {court_number} can be booked on {date} at {}",
+                        slots
+                            .iter()
+                            .map(|start| start.format("%H:%M").to_string())
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    )
+                },
+            ),
         };
-        (status, Json(serde_json::json!({ "error": msg }))).into_response()
+        let mut body = serde_json::json!({ "error": msg });
+        if let Some((field, value)) = details {
+            body[field] = value;
+        }
+        let mut response = (status, Json(body)).into_response();
+        if let Some(seconds) = retry_after {
+            response.headers_mut().insert(RETRY_AFTER, seconds.into());
+        }
+        response
     }
 }
 
 pub async fn router(app_state: AppState) -> Router {
-    dotenv().ok();
+    #[cfg(feature = "local")]
+    let frontend_url: String = app_state.config.frontend_url.clone();
 
-    let mut router = Router::new()
+    let router = Router::new()
         // Member routes
         .route(
             "/member",
@@ -201,6 +473,11 @@ pub async fn router(app_state: AppState) -> Router {
             "/member/{id}",
             get(wrappers::member::get_member).delete(wrappers::member::delete_member),
         )
+        .route("/member/{id}/unlock", post(wrappers::member::unlock_member))
+        .route(
+            "/member/{id}/suspension",
+            delete(wrappers::member::lift_suspension),
+        )
         .route("/password", patch(wrappers::member::update_password))
         .route("/password-reset", patch(wrappers::member::password_reset))
         // Reservation routes
@@ -209,6 +486,14 @@ pub async fn router(app_state: AppState) -> Router {
             patch(wrappers::reservation::update_reservation)
                 .post(wrappers::reservation::add_reservation),
         )
+        .route(
+            "/me/reservations",
+            get(wrappers::reservation::get_my_reservations),
+        )
+        .route(
+            "/reservation-series",
+            post(wrappers::reservation_series::add_series),
+        )
         .route(
             "/reservations/{date}",
             get(wrappers::reservation::get_reservations_with_names_by_date),
@@ -216,11 +501,78 @@ pub async fn router(app_state: AppState) -> Router {
         .route(
             "/reservation/{id}",
             get(wrappers::reservation::get_reservation)
-                .delete(wrappers::reservation::delete_reservation),
+                .delete(wrappers::reservation::cancel_reservation),
+        )
+        .route(
+            "/reservation/{id}/check-in",
+            post(wrappers::check_in::check_in),
+        )
+        .route(
+            "/reservation/{id}/participants",
+            put(wrappers::participant::set_participants),
+        )
+        .route(
+            "/reservation/{id}/participation",
+            patch(wrappers::participant::answer_invitation),
+        )
+        // Waitlist routes
+        .route(
+            "/waitlist",
+            get(wrappers::waitlist::get_waitlist).post(wrappers::waitlist::join_waitlist),
+        )
+        .route("/waitlist/claim", post(wrappers::waitlist::claim_waitlist))
+        .route("/waitlist/{id}", delete(wrappers::waitlist::leave_waitlist))
+        // Court routes
+        .route(
+            "/court",
+            post(wrappers::court::add_court).patch(wrappers::court::update_court),
+        )
+        .route("/courts", get(wrappers::court::get_all_courts))
+        .route(
+            "/court/{number}",
+            get(wrappers::court::get_court).delete(wrappers::court::delete_court),
+        )
+        // Opening hours routes
+        .route(
+            "/opening-hours",
+            get(wrappers::opening_hours::get_all_opening_hours)
+                .post(wrappers::opening_hours::add_opening_hours)
+                .patch(wrappers::opening_hours::update_opening_hours),
+        )
+        .route(
+            "/opening-hours/{id}",
+            delete(wrappers::opening_hours::delete_opening_hours),
+        )
+        // Court block routes
+        .route(
+            "/court-blocks",
+            get(wrappers::court_block::get_upcoming_blocks).post(wrappers::court_block::add_block),
+        )
+        .route(
+            "/court-blocks/{id}",
+            delete(wrappers::court_block::delete_block),
+        )
+        // Booking rules routes
+        .route(
+            "/booking-rules",
+            get(wrappers::booking_rules::get_booking_rules)
+                .put(wrappers::booking_rules::update_booking_rules),
+        )
+        // Availability routes
+        .route(
+            "/availability",
+            get(wrappers::availability::get_availability),
+        )
+        // Email outbox routes
+        .route("/email-outbox", get(wrappers::email_outbox::get_outbox))
+        .route(
+            "/email-outbox/{id}/retry",
+            post(wrappers::email_outbox::retry_email),
         )
         // Authentication routes
         .route("/login", post(auth::login))
         .route("/logout", post(auth::logout))
+        .route("/logout-all", post(auth::logout_all))
         .route("/verify-token", get(auth::verify_token))
         .route("/refresh-jwt", post(auth::refresh_jwt))
         .route("/password-forgotten", post(auth::password_forgotten))
@@ -278,30 +630,24 @@ pub async fn router(app_state: AppState) -> Router {
 
     #[cfg(feature = "local")]
     {
-        let frontend_ip: &str =
-            &env::var("FRONTEND_IP").expect("Undefined FRONTEND_IP environment variable");
-        let frontend_port: u16 = str::parse(
-            &env::var("FRONTEND_PORT").expect("Undefined FRONTEND_PORT environment variable"),
-        )
-        .unwrap();
-
         let cors = CorsLayer::new()
-            .allow_origin(
-                HeaderValue::from_str(&format!("http://{frontend_ip}:{frontend_port}"))
-                    .expect("Invalid origin header"),
-            )
+            .allow_origin(HeaderValue::from_str(&frontend_url).expect("Invalid origin header"))
             .allow_methods(vec![
                 Method::GET,
                 Method::POST,
+                Method::PUT,
                 Method::PATCH,
                 Method::DELETE,
             ])
             .allow_headers([AUTHORIZATION, CONTENT_TYPE])
-            .expose_headers([CONTENT_TYPE])
+            .expose_headers([CONTENT_TYPE, RETRY_AFTER])
             .allow_credentials(true);
 
-        router = router.layer(cors)
+        router.layer(cors)
     }
 
-    router
+    #[cfg(not(feature = "local"))]
+    {
+        router
+    }
 }
@@ -1,15 +1,17 @@
 use crate::api::app::{ApiError, AppState};
 use crate::api::email::EmailService;
+use crate::api::login_guard::{self, ClientIp};
+use crate::config::JwtConfig;
 use crate::db::{models, queries};
-use crate::jwt::{create_jwt, verify_jwt};
+use crate::jwt::{Claims, create_jwt, verify_jwt};
+use crate::utils::{gen_refresh_token, hash_token};
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
-use axum::extract::{Json, State};
-use axum::http::{HeaderMap, StatusCode};
+use axum::extract::{FromRequestParts, Json, State};
+use axum::http::{HeaderMap, StatusCode, request::Parts};
 use axum::response::{IntoResponse, Response};
-use dotenvy::dotenv;
 use serde::Deserialize;
 use serde_json::json;
-use std::env;
+use tokio_postgres::Client;
 use uuid::Uuid;
 
 #[derive(Deserialize)]
@@ -20,27 +22,41 @@ pub struct LoginPayload {
 
 #[derive(Deserialize)]
 pub struct RefreshJwtPayload {
-    member_id: String,
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct LogoutPayload {
+    refresh_token: String,
 }
 
 // Login endpoint that returns JWT token in response body
 pub async fn login(
     State(state): State<AppState>,
+    ClientIp(ip_address): ClientIp,
     Json(payload): Json<LoginPayload>,
 ) -> Result<Response, ApiError> {
     tracing::debug!("Login attempt for phone: {}", payload.phone);
 
-    let client = state.pool.get().await.map_err(|e| {
+    let mut client = state.pool.get().await.map_err(|e| {
         tracing::error!("Failed to get database connection: {:?}", e);
         ApiError::Pool(e)
     })?;
 
-    let member: models::Member = queries::member::get_member_by_phone(&client, &payload.phone)
-        .await
-        .map_err(|e| {
-            tracing::warn!("Member not found for phone {}: {:?}", payload.phone, e);
-            ApiError::NotFound
-        })?;
+    let ip_address: Option<&str> = ip_address.as_deref();
+    let transaction = client.transaction().await?;
+    login_guard::check_attempt(&transaction, &payload.phone, ip_address).await?;
+
+    let member: models::Member =
+        match queries::member::get_member_by_phone(&transaction, &payload.phone).await {
+            Ok(member) => member,
+            Err(e) => {
+                tracing::warn!("Member not found for phone {}: {:?}", payload.phone, e);
+                login_guard::record_failure(&transaction, &payload.phone, ip_address).await?;
+                transaction.commit().await?;
+                return Err(ApiError::NotFound);
+            }
+        };
 
     let parsed_hash = PasswordHash::new(&member.password).map_err(|e| {
         tracing::error!("Failed to parse password hash: {:?}", e);
@@ -49,14 +65,30 @@ pub async fn login(
 
     if let Err(e) = Argon2::default().verify_password(payload.password.as_bytes(), &parsed_hash) {
         tracing::warn!("Invalid password for user {}: {:?}", member.id, e);
+        login_guard::record_failure(&transaction, &payload.phone, ip_address).await?;
+        transaction.commit().await?;
         return Err(ApiError::NotFound);
     }
 
+    login_guard::record_success(&transaction, &payload.phone, ip_address).await?;
+    transaction.commit().await?;
+
     let is_profile_complete: bool =
         member.email.is_some() && member.first_name.is_some() && member.last_name.is_some();
 
+    let refresh_token: String = gen_refresh_token();
+    let session_id: Uuid = queries::session::create_session(
+        &client,
+        &member.id,
+        &hash_token(&refresh_token),
+        state.config.jwt.refresh_token_ttl_days,
+    )
+    .await?;
+
     let token: String = create_jwt(
+        &state.config.jwt,
         &member.id,
+        &session_id.to_string(),
         &member.phone,
         is_profile_complete,
         member.is_admin,
@@ -73,6 +105,9 @@ pub async fn login(
         Json(json!({
             "message": "Login successful",
             "token": token,
+            "expires_in": state.config.jwt.access_token_ttl_minutes * 60,
+            "refresh_token": refresh_token,
+            "refresh_token_expires_in": state.config.jwt.refresh_token_ttl_days * 24 * 60 * 60,
             "id": member.id,
             "phone": member.phone,
             "is_profile_complete": is_profile_complete,
@@ -82,33 +117,55 @@ pub async fn login(
         .into_response())
 }
 
-// Logout endpoint (mainly for logging purposes, actual logout happens client-side)
-pub async fn logout(headers: HeaderMap) -> impl IntoResponse {
-    // Extract user info from token if provided (for logging)
-    if let Some(auth_header) = headers.get("Authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..];
-                if let Ok(claims) = verify_jwt(token) {
-                    tracing::info!("User {} logged out", claims.sub);
-                }
-            }
+// Logout endpoint: revokes the current session, identified by the access token or the refresh token
+pub async fn logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    payload: Option<Json<LogoutPayload>>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+
+    if let Some(Json(payload)) = payload {
+        queries::session::revoke_session_by_token_hash(
+            &client,
+            &hash_token(&payload.refresh_token),
+        )
+        .await?;
+    }
+
+    if let Ok(claims) = require_auth(&state.config.jwt, &headers) {
+        if let Ok(session_id) = Uuid::parse_str(&claims.sid) {
+            queries::session::revoke_session(&client, &session_id).await?;
         }
+        tracing::info!("User {} logged out", claims.sub);
     }
 
-    (StatusCode::NO_CONTENT, "")
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Revokes every session of the current member ("log out all devices")
+pub async fn logout_all(
+    State(state): State<AppState>,
+    auth: AuthMember,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    let revoked = queries::session::revoke_member_sessions(&client, auth.id(), None).await?;
+
+    tracing::info!("User {} logged out of {} session(s)", auth.id(), revoked);
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
 // Verify token endpoint - used by frontend to validate stored tokens
 pub async fn verify_token(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse, ApiError> {
     // Extract token from Authorization header
     let token = extract_token_from_headers(&headers)?;
 
     // Verify and decode the JWT
-    let claims = match verify_jwt(&token) {
+    let claims = match verify_jwt(&state.config.jwt, &token) {
         Ok(claims) => claims,
         Err(jwt_error) => {
             tracing::debug!("JWT verification failed: {}", jwt_error);
@@ -147,21 +204,17 @@ pub fn extract_token_from_headers(headers: &HeaderMap) -> Result<String, ApiErro
         .to_str()
         .map_err(|_| ApiError::InvalidAuthToken)?;
 
-    if !auth_str.starts_with("Bearer ") {
-        return Err(ApiError::InvalidAuthToken);
-    }
-
-    Ok(auth_str[7..].to_string())
+    auth_str
+        .strip_prefix("Bearer ")
+        .map(str::to_string)
+        .ok_or(ApiError::InvalidAuthToken)
 }
 
-// Middleware function to verify JWT from headers (for protected routes)
-pub async fn require_auth(
-    headers: HeaderMap,
-    State(_state): State<AppState>,
-) -> Result<crate::jwt::Claims, ApiError> {
-    let token = extract_token_from_headers(&headers)?;
+// Verifies the JWT from the Authorization header (for protected routes)
+pub fn require_auth(config: &JwtConfig, headers: &HeaderMap) -> Result<Claims, ApiError> {
+    let token = extract_token_from_headers(headers)?;
 
-    match verify_jwt(&token) {
+    match verify_jwt(config, &token) {
         Ok(claims) => Ok(claims),
         Err(jwt_error) => {
             tracing::debug!("JWT verification failed in middleware: {}", jwt_error);
@@ -173,6 +226,61 @@ pub async fn require_auth(
     }
 }
 
+/// Extractor for routes that require a logged-in member.
+pub struct AuthMember(pub Claims);
+
+impl AuthMember {
+    pub fn id(&self) -> &str {
+        &self.0.sub
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.0.is_admin
+    }
+
+    /// Session the access token was issued for.
+    pub fn session_id(&self) -> Option<Uuid> {
+        Uuid::parse_str(&self.0.sid).ok()
+    }
+
+    /// Whether the member may act on resources belonging to `member_id`.
+    pub fn can_act_for(&self, member_id: &str) -> bool {
+        self.is_admin() || self.id() == member_id
+    }
+}
+
+impl FromRequestParts<AppState> for AuthMember {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        require_auth(&state.config.jwt, &parts.headers).map(AuthMember)
+    }
+}
+
+/// Extractor for routes that are restricted to administrators.
+pub struct AdminOnly(pub Claims);
+
+impl FromRequestParts<AppState> for AdminOnly {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let claims = require_auth(&state.config.jwt, &parts.headers)?;
+
+        if !claims.is_admin {
+            tracing::warn!("Member {} attempted to access an admin route", claims.sub);
+            return Err(ApiError::AdminRequired);
+        }
+
+        Ok(AdminOnly(claims))
+    }
+}
+
 #[derive(Deserialize)]
 pub struct PasswordForgottenPayload {
     pub email: String,
@@ -182,84 +290,81 @@ pub async fn password_forgotten(
     State(state): State<AppState>,
     Json(payload): Json<PasswordForgottenPayload>,
 ) -> Result<Response, ApiError> {
-    dotenv().ok();
-    let smtp_server: String =
-        env::var("SMTP_SERVER").expect("Undefined SMTP_SERVER environment variable");
-    let smtp_user: String =
-        env::var("SMTP_USER").expect("Undefined SMTP_USER environment variable");
-    let smtp_password: String =
-        env::var("SMTP_PASSWORD").expect("Undefined SMTP_PASSWORD environment variable");
-    let smtp_sender: String =
-        env::var("SMTP_SENDER").expect("Undefined SMTP_SENDER environment variable");
-
-    let base_url: String;
-
-    #[cfg(feature = "local")]
-    {
-        let frontend_ip: &str =
-            &env::var("FRONTEND_IP").expect("Undefined FRONTEND_IP environment variable");
-        let frontend_port: &str =
-            &env::var("FRONTEND_PORT").expect("Undefined FRONTEND_PORT environment variable");
-        base_url = format!("http://{frontend_ip}:{frontend_port}");
-    }
-
-    #[cfg(not(feature = "local"))]
-    {
-        let public_url: &str = &env::var("CUSTOM_DOMAIN_URL")
-            .expect("Undefined CUSTOM_DOMAIN_URL environment variable");
-
-        base_url = public_url.to_string();
-    }
+    let base_url: &str = &state.config.frontend_url;
 
-    let email_service = EmailService::new(&smtp_server, &smtp_user, &smtp_password, &smtp_sender);
-
-    let client = state.pool.get().await?;
+    let mut client = state.pool.get().await?;
     let member: models::Member =
         match queries::member::get_member_by_email(&client, &payload.email).await {
             Ok(member) => member,
             Err(_) => return Ok((StatusCode::OK).into_response()),
         };
 
-    let token: Uuid = queries::password_reset_token::create_token(&client, &member.id).await?;
+    let transaction = client.transaction().await?;
+    let token: Uuid = queries::password_reset_token::create_token(&transaction, &member.id).await?;
+    EmailService::new(&transaction, &state.config)
+        .send_password_reset_email(&member, &token, &format!("{base_url}/password-reset"))
+        .await?;
+    transaction.commit().await?;
+    tracing::info!("Password reset email queued for member {}", member.id);
 
-    match email_service
-        .send_password_reset_email(
-            &payload.email,
-            &member.first_name.unwrap(),
-            &token,
-            &format!("{base_url}/password-reset"),
-        )
-        .await
+    Ok((StatusCode::OK).into_response())
+}
+
+/// Revokes the session if `refresh_token_hash` is the refresh token it was rotated from, which
+/// was then either stolen or replayed.
+async fn revoke_on_reuse(client: &Client, refresh_token_hash: &str) -> Result<(), ApiError> {
+    if queries::session::revoke_session_by_previous_token_hash(client, refresh_token_hash).await?
+        > 0
     {
-        Ok(()) => println!("Password reset email sent with token: {token}",),
-        Err(error) => {
-            println!("{error:?}");
-            return Err(ApiError::NotFound);
-        }
+        tracing::warn!("Rotated refresh token presented again, session revoked");
     }
-
-    Ok((StatusCode::OK).into_response())
+    Ok(())
 }
 
-// Refresh JWT endpoint - creates new token with updated claims
+// Refresh JWT endpoint - consumes a refresh token and returns a new access token along with
+// a new refresh token. The previous refresh token can no longer be used, and the session is
+// extended by the refresh token TTL.
 pub async fn refresh_jwt(
     State(state): State<AppState>,
-    headers: HeaderMap,
     Json(payload): Json<RefreshJwtPayload>,
 ) -> Result<Response, ApiError> {
-    // Verify the current token is valid
-    let token = extract_token_from_headers(&headers)?;
-    let _ = verify_jwt(&token).map_err(|_| ApiError::WrongCredentials)?;
+    let client = state.pool.get().await?;
+
+    let current_hash: String = hash_token(&payload.refresh_token);
+    let session: models::Session =
+        match queries::session::get_active_session_by_token_hash(&client, &current_hash).await {
+            Ok(session) => session,
+            Err(_) => {
+                revoke_on_reuse(&client, &current_hash).await?;
+                return Err(ApiError::InvalidRefreshToken);
+            }
+        };
+
+    let new_refresh_token: String = gen_refresh_token();
+    let affected = queries::session::rotate_refresh_token(
+        &client,
+        &session.id,
+        &current_hash,
+        &hash_token(&new_refresh_token),
+        state.config.jwt.refresh_token_ttl_days,
+    )
+    .await?;
+    if affected != 1 {
+        // The token was rotated by a concurrent request
+        revoke_on_reuse(&client, &current_hash).await?;
+        return Err(ApiError::InvalidRefreshToken);
+    }
 
     // Fetch the latest user data from database
-    let client = state.pool.get().await?;
-    let member: models::Member = queries::member::get_member(&client, &payload.member_id).await?;
+    let member: models::Member = queries::member::get_member(&client, &session.member_id).await?;
     let is_profile_complete: bool =
         member.email.is_some() && member.first_name.is_some() && member.last_name.is_some();
 
     // Create a new JWT with updated profile completion status
     let new_token: String = create_jwt(
+        &state.config.jwt,
         &member.id,
+        &session.id.to_string(),
         &member.phone,
         is_profile_complete,
         member.is_admin,
@@ -271,6 +376,9 @@ pub async fn refresh_jwt(
         Json(json!({
             "message": "JWT refreshed successfully",
             "token": new_token,
+            "expires_in": state.config.jwt.access_token_ttl_minutes * 60,
+            "refresh_token": new_refresh_token,
+            "refresh_token_expires_in": state.config.jwt.refresh_token_ttl_days * 24 * 60 * 60,
             "is_profile_complete": is_profile_complete,
             "is_admin": member.is_admin
         })),
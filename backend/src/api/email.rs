@@ -1,64 +1,314 @@
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::{Message, SmtpTransport, Transport, message::header::ContentType};
+use crate::calendar::{self, Method};
+use crate::config::Config;
+use crate::db::models::{Member, Reservation};
+use crate::db::queries::email_outbox;
+use crate::mail::{Attachment, Email};
+use chrono::{FixedOffset, Utc};
+use deadpool_postgres::GenericClient;
+use lettre::message::Mailbox;
+use minijinja::{Environment, UndefinedBehavior, Value, context};
+use std::sync::LazyLock;
+use tokio_postgres::Error;
+use url::form_urlencoded;
 use uuid::Uuid;
 
-pub struct EmailService {
-    transport: SmtpTransport,
-    from_email: String,
+macro_rules! templates {
+    ($($name:literal),* $(,)?) => {
+        [$(($name, include_str!(concat!("../../templates/emails/", $name)))),*]
+    };
 }
 
-impl EmailService {
-    pub fn new(smtp_server: &str, smtp_user: &str, smtp_password: &str, from_email: &str) -> Self {
-        let creds = Credentials::new(smtp_user.to_string(), smtp_password.to_string());
+/// Emails carrying live credentials, whose bodies are not kept in the outbox once sent.
+const REDACTED_EMAILS: &[&str] = &["password_reset"];
 
-        let transport = SmtpTransport::relay(smtp_server)
-            .unwrap()
-            .credentials(creds)
-            .build();
+/// Templates of the emails, which have an HTML and a plain text version per locale. Both extend
+/// the layout of their locale, itself extending the `base` layout shared by every locale. The
+/// subject of an email is the `subject` block of its plain text version.
+static TEMPLATES: LazyLock<Environment<'static>> = LazyLock::new(|| {
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
 
+    let templates = templates![
+        "base.html",
+        "base.txt",
+        "fr/layout.html",
+        "fr/layout.txt",
+        "fr/password_reset.html",
+        "fr/password_reset.txt",
+        "fr/waitlist_claim.html",
+        "fr/waitlist_claim.txt",
+        "fr/waitlist_booking.html",
+        "fr/waitlist_booking.txt",
+        "fr/court_block_cancellation.html",
+        "fr/court_block_cancellation.txt",
+        "fr/reservation_confirmed.html",
+        "fr/reservation_confirmed.txt",
+        "fr/reservation_modified.html",
+        "fr/reservation_modified.txt",
+        "fr/reservation_cancelled.html",
+        "fr/reservation_cancelled.txt",
+        "fr/reservation_reminder.html",
+        "fr/reservation_reminder.txt",
+        "en/layout.html",
+        "en/layout.txt",
+        "en/password_reset.html",
+        "en/password_reset.txt",
+        "en/waitlist_claim.html",
+        "en/waitlist_claim.txt",
+        "en/waitlist_booking.html",
+        "en/waitlist_booking.txt",
+        "en/court_block_cancellation.html",
+        "en/court_block_cancellation.txt",
+        "en/reservation_confirmed.html",
+        "en/reservation_confirmed.txt",
+        "en/reservation_modified.html",
+        "en/reservation_modified.txt",
+        "en/reservation_cancelled.html",
+        "en/reservation_cancelled.txt",
+        "en/reservation_reminder.html",
+        "en/reservation_reminder.txt",
+        "nl/layout.html",
+        "nl/layout.txt",
+        "nl/password_reset.html",
+        "nl/password_reset.txt",
+        "nl/waitlist_claim.html",
+        "nl/waitlist_claim.txt",
+        "nl/waitlist_booking.html",
+        "nl/waitlist_booking.txt",
+        "nl/court_block_cancellation.html",
+        "nl/court_block_cancellation.txt",
+        "nl/reservation_confirmed.html",
+        "nl/reservation_confirmed.txt",
+        "nl/reservation_modified.html",
+        "nl/reservation_modified.txt",
+        "nl/reservation_cancelled.html",
+        "nl/reservation_cancelled.txt",
+        "nl/reservation_reminder.html",
+        "nl/reservation_reminder.txt",
+    ];
+    for (name, source) in templates {
+        env.add_template(name, source)
+            .expect("Invalid email template.");
+    }
+    env
+});
+
+/// Calendar event of a reservation, attached to an email about it. Its summary is the `event`
+/// block of the plain text version of the email.
+struct Event<'r> {
+    reservation: &'r Reservation,
+    method: Method,
+    organizer: &'r str,
+    club_utc_offset: FixedOffset,
+}
+
+/// Renders the `name` email to `to` in the locale of `member`. Values are HTML escaped in the
+/// HTML version only.
+fn render(member: &Member, to: &str, name: &str, ctx: Value, event: Option<Event>) -> Email {
+    let locale: &str = member.locale.as_str();
+    let ctx: Value = context! {
+        locale,
+        first_name => member.first_name.as_deref().unwrap_or_default(),
+        ..ctx
+    };
+
+    let mut text = TEMPLATES
+        .get_template(&format!("{locale}/{name}.txt"))
+        .and_then(|text| text.render_captured(&ctx))
+        .expect("Could not render the email text.");
+    let subject: String = text
+        .with_state_mut(|state| state.render_block("subject"))
+        .expect("Could not render the email subject.");
+    let attachment: Option<Attachment> = event.map(|event| {
+        let summary: String = text
+            .with_state_mut(|state| state.render_block("event"))
+            .expect("Could not render the event summary.");
+        let calendar: String = calendar::reservation_calendar(
+            event.reservation,
+            &summary,
+            event.method,
+            event.organizer,
+            to,
+            event.club_utc_offset,
+            Utc::now(),
+        );
+        calendar::attachment(calendar, event.method)
+    });
+    let text_body: String = text.into_output();
+    let html_body: String = TEMPLATES
+        .get_template(&format!("{locale}/{name}.html"))
+        .and_then(|html| html.render(context! { heading => &subject, ..ctx }))
+        .expect("Could not render the email HTML.");
+
+    Email {
+        to: to.to_string(),
+        subject: format!("Beach Garden SXM - {subject}"),
+        html_body,
+        text_body,
+        attachment,
+    }
+}
+
+/// Text message reminding `member` of `reservation`, in the locale of the member. It is the `sms`
+/// block of the plain text version of the reminder email.
+pub fn reminder_sms(member: &Member, reservation: &Reservation) -> String {
+    let locale: &str = member.locale.as_str();
+    let ctx: Value = context! {
+        locale,
+        first_name => member.first_name.as_deref().unwrap_or_default(),
+        ..slot(reservation)
+    };
+
+    TEMPLATES
+        .get_template(&format!("{locale}/reservation_reminder.txt"))
+        .and_then(|text| text.render_captured(&ctx))
+        .and_then(|mut text| text.with_state_mut(|state| state.render_block("sms")))
+        .expect("Could not render the text message.")
+}
+
+fn slot(reservation: &Reservation) -> Value {
+    context! {
+        court_number => reservation.court_number,
+        date => reservation.reservation_date.format("%d/%m/%Y").to_string(),
+        time => reservation.start_time.format("%H:%M").to_string(),
+        duration_minutes => reservation.duration_minutes,
+    }
+}
+
+/// Change of a reservation the member is told about, with its calendar event.
+#[derive(Debug, Clone, Copy)]
+pub enum ReservationChange<'a> {
+    Confirmed,
+    Modified,
+    Cancelled { reason: Option<&'a str> },
+}
+
+/// Emails sent to the members in their language, queued in the outbox within the transaction
+/// of `client` so that they are only delivered if it is committed. Members without an email
+/// are not sent anything. Calendar events are in UTC, converted from the times of the club, and
+/// organized by the address emails are sent from.
+pub struct EmailService<'a, C: GenericClient> {
+    client: &'a C,
+    club_utc_offset: FixedOffset,
+    organizer: String,
+}
+
+impl<'a, C: GenericClient> EmailService<'a, C> {
+    pub fn new(client: &'a C, config: &Config) -> Self {
+        let sender: &str = &config.smtp.sender;
         Self {
-            transport,
-            from_email: from_email.to_string(),
+            client,
+            club_utc_offset: config.club_utc_offset,
+            // The sender may come with a display name
+            organizer: sender
+                .parse::<Mailbox>()
+                .map_or_else(|_| sender.to_string(), |mailbox| mailbox.email.to_string()),
         }
     }
 
+    async fn send(
+        &self,
+        member: &Member,
+        name: &str,
+        ctx: Value,
+        event: Option<(&Reservation, Method)>,
+    ) -> Result<(), Error> {
+        let Some(to) = &member.email else {
+            return Ok(());
+        };
+        let event: Option<Event> = event.map(|(reservation, method)| Event {
+            reservation,
+            method,
+            organizer: &self.organizer,
+            club_utc_offset: self.club_utc_offset,
+        });
+        let email: Email = render(member, to, name, ctx, event);
+        email_outbox::enqueue(self.client, &email, REDACTED_EMAILS.contains(&name)).await?;
+        Ok(())
+    }
+
     pub async fn send_password_reset_email(
         &self,
-        to_email: &str,
-        first_name: &str,
+        member: &Member,
         reset_token: &Uuid,
         base_url: &str,
-    ) -> Result<(), lettre::transport::smtp::Error> {
-        let reset_url = format!("{base_url}?token={reset_token}&email={to_email}");
-
-        let email_body = format!(
-            r#"
-            <html>
-            <body>
-                <h2>Mot de passe oublié</h2>
-                <br>
-                <p>Bonjour {first_name},</p>
-                <br>
-                <p>Pour réinitialiser votre mot de passe, veuillez cliquer sur le lien suivant :</p>
-                <p><a href="{reset_url}">Réinitialisation de mon mot de passe</a></p>
-                <p>Ce lien expirera dans une heure.</p>
-                <br>
-                <p>Sportivement.</p>
-                <p>Beach Garden SXM</p>
-            </body>
-            </html>
-            "#
-        );
+    ) -> Result<(), Error> {
+        let email: &str = member.email.as_deref().unwrap_or_default();
+        let query: String = form_urlencoded::Serializer::new(String::new())
+            .append_pair("token", &reset_token.to_string())
+            .append_pair("email", email)
+            .finish();
+        let reset_url = format!("{base_url}?{query}");
+        self.send(member, "password_reset", context! { reset_url }, None)
+            .await
+    }
 
-        let email = Message::builder()
-            .from(self.from_email.parse().unwrap())
-            .to(to_email.parse().unwrap())
-            .subject("Beach Garden SXM - Mot de passe oublié")
-            .header(ContentType::TEXT_HTML)
-            .body(email_body)
-            .unwrap();
+    pub async fn send_waitlist_claim_email(
+        &self,
+        member: &Member,
+        reservation: &Reservation,
+        claim_token: &Uuid,
+        base_url: &str,
+        expires_in_minutes: i64,
+    ) -> Result<(), Error> {
+        let claim_url = format!("{base_url}?token={claim_token}");
+        let ctx: Value = context! { claim_url, expires_in_minutes, ..slot(reservation) };
+        self.send(member, "waitlist_claim", ctx, None).await
+    }
 
-        self.transport.send(&email)?;
-        Ok(())
+    pub async fn send_waitlist_booking_email(
+        &self,
+        member: &Member,
+        reservation: &Reservation,
+    ) -> Result<(), Error> {
+        let event = (reservation, Method::Request);
+        self.send(member, "waitlist_booking", slot(reservation), Some(event))
+            .await
+    }
+
+    pub async fn send_court_block_cancellation_email(
+        &self,
+        member: &Member,
+        reservation: &Reservation,
+        reason: &str,
+    ) -> Result<(), Error> {
+        let ctx: Value = context! { reason, ..slot(reservation) };
+        let event = (reservation, Method::Cancel);
+        self.send(member, "court_block_cancellation", ctx, Some(event))
+            .await
+    }
+
+    pub async fn send_reminder_email(
+        &self,
+        member: &Member,
+        reservation: &Reservation,
+    ) -> Result<(), Error> {
+        self.send(member, "reservation_reminder", slot(reservation), None)
+            .await
+    }
+
+    pub async fn send_reservation_email(
+        &self,
+        member: &Member,
+        reservation: &Reservation,
+        change: ReservationChange<'_>,
+    ) -> Result<(), Error> {
+        let (name, ctx, method): (&str, Value, Method) = match change {
+            ReservationChange::Confirmed => {
+                ("reservation_confirmed", slot(reservation), Method::Request)
+            }
+            ReservationChange::Modified => {
+                ("reservation_modified", slot(reservation), Method::Request)
+            }
+            ReservationChange::Cancelled { reason } => (
+                "reservation_cancelled",
+                context! { reason, ..slot(reservation) },
+                Method::Cancel,
+            ),
+        };
+        self.send(member, name, ctx, Some((reservation, method)))
+            .await
     }
 }
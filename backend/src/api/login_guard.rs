@@ -0,0 +1,166 @@
+//! Brute-force protection for the login endpoint.
+//!
+//! Attempts are stored in Postgres so that the limits hold across Lambda instances. Failures
+//! are tracked per phone and per IP address: repeated failures for a phone first space out
+//! the attempts allowed, then lock the phone out for a while. Attempts made too soon are
+//! rejected with a `Retry-After` rather than delayed, so that no connection is held meanwhile.
+
+use crate::api::app::ApiError;
+use crate::db::queries::login_attempt;
+use axum::extract::FromRequestParts;
+use axum::http::{Extensions, HeaderMap, request::Parts};
+use deadpool_postgres::GenericClient;
+use lambda_http::RequestExt;
+use lambda_http::request::RequestContext;
+use std::convert::Infallible;
+use std::time::Duration;
+
+/// Period over which failed attempts are counted.
+const WINDOW_MINUTES: i32 = 15;
+/// Failures for a phone after which the next attempt must wait for a delay.
+const DELAY_AFTER_FAILURES: i64 = 2;
+/// Upper bound for the delay of a single attempt.
+const MAX_DELAY: Duration = Duration::from_secs(4);
+/// Failures for a phone after which it is locked out.
+const LOCKOUT_AFTER_FAILURES: i64 = 5;
+/// Duration of a first lockout, doubled on each consecutive lockout.
+const LOCKOUT_MINUTES: i32 = 15;
+/// Caps the lockout duration to `LOCKOUT_MINUTES * 2^LOCKOUT_MAX_DOUBLINGS`.
+const LOCKOUT_MAX_DOUBLINGS: i32 = 4;
+/// Failures from a single IP address after which its attempts are rejected.
+const MAX_IP_FAILURES: i64 = 20;
+
+/// Address of the client, `None` when it cannot be trusted.
+///
+/// Behind the API Gateway it is the source IP of the request context. Otherwise it is the last
+/// `X-Forwarded-For` entry, the one appended by the reverse proxy, earlier entries being set by
+/// the client.
+pub struct ClientIp(pub Option<String>);
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(ClientIp(client_ip(&parts.extensions, &parts.headers)))
+    }
+}
+
+fn client_ip(extensions: &Extensions, headers: &HeaderMap) -> Option<String> {
+    let source_ip: Option<&String> = match extensions.request_context_ref() {
+        Some(RequestContext::ApiGatewayV1(context)) => context.identity.source_ip.as_ref(),
+        Some(RequestContext::ApiGatewayV2(context)) => context.http.source_ip.as_ref(),
+        Some(RequestContext::WebSocket(context)) => context.identity.source_ip.as_ref(),
+        _ => None,
+    };
+
+    source_ip
+        .cloned()
+        .or_else(|| {
+            headers
+                .get_all("X-Forwarded-For")
+                .iter()
+                .next_back()
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit(',').next())
+                .map(|ip| ip.trim().to_string())
+        })
+        .filter(|ip| !ip.is_empty())
+}
+
+/// Rejects the attempt if the phone is locked out, if the IP address has failed too often, or
+/// if it comes sooner after the last failure than a delay growing with the number of recent
+/// failures. Attempts from unknown addresses are only limited per phone.
+///
+/// Concurrent attempts for the phone wait until the transaction of `client` ends, so it must
+/// also record the outcome of this attempt.
+pub async fn check_attempt(
+    client: &impl GenericClient,
+    phone: &str,
+    ip_address: Option<&str>,
+) -> Result<(), ApiError> {
+    login_attempt::lock_attempts(client, phone).await?;
+
+    if let Some(retry_after) = login_attempt::get_active_lockout(client, phone).await? {
+        tracing::warn!("Login attempt for locked phone {}", phone);
+        return Err(ApiError::AccountLocked { retry_after });
+    }
+
+    if ip_address.is_none() {
+        tracing::warn!("Login attempt for {} from an unknown address", phone);
+    }
+    if let Some(ip_address) = ip_address {
+        let ip_failures =
+            login_attempt::count_recent_ip_failures(client, ip_address, WINDOW_MINUTES).await?;
+        if ip_failures >= MAX_IP_FAILURES {
+            tracing::warn!("Too many failed login attempts from {}", ip_address);
+            return Err(ApiError::TooManyLoginAttempts {
+                retry_after: i64::from(WINDOW_MINUTES) * 60,
+            });
+        }
+    }
+
+    let phone_failures =
+        login_attempt::count_recent_phone_failures(client, phone, WINDOW_MINUTES).await?;
+    let delay = delay_for(phone_failures);
+    if !delay.is_zero()
+        && let Some(elapsed) =
+            login_attempt::get_millis_since_last_phone_failure(client, phone).await?
+    {
+        let remaining = delay.saturating_sub(Duration::from_millis(elapsed.max(0) as u64));
+        if !remaining.is_zero() {
+            tracing::debug!("Login attempt for {} too soon, {:?} left", phone, remaining);
+            return Err(ApiError::TooManyLoginAttempts {
+                retry_after: remaining.as_secs_f64().ceil() as i64,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Records a failed attempt and locks the phone out once it reaches the threshold.
+pub async fn record_failure(
+    client: &impl GenericClient,
+    phone: &str,
+    ip_address: Option<&str>,
+) -> Result<(), ApiError> {
+    login_attempt::add_login_attempt(client, phone, ip_address, false).await?;
+
+    let failures =
+        login_attempt::count_recent_phone_failures(client, phone, WINDOW_MINUTES).await?;
+    if failures >= LOCKOUT_AFTER_FAILURES {
+        let locked_until =
+            login_attempt::lock_phone(client, phone, LOCKOUT_MINUTES, LOCKOUT_MAX_DOUBLINGS)
+                .await?;
+        tracing::warn!("Phone {} locked out until {}", phone, locked_until);
+    }
+
+    Ok(())
+}
+
+/// Records a successful attempt, which resets the failure count and lockout escalation.
+pub async fn record_success(
+    client: &impl GenericClient,
+    phone: &str,
+    ip_address: Option<&str>,
+) -> Result<(), ApiError> {
+    login_attempt::add_login_attempt(client, phone, ip_address, true).await?;
+    login_attempt::delete_lockout(client, phone).await?;
+    Ok(())
+}
+
+/// Lifts the lockout of a phone, e.g. when an admin unlocks a member.
+pub async fn unlock(client: &impl GenericClient, phone: &str) -> Result<(), ApiError> {
+    login_attempt::delete_failed_attempts(client, phone).await?;
+    login_attempt::delete_lockout(client, phone).await?;
+    Ok(())
+}
+
+fn delay_for(failures: i64) -> Duration {
+    if failures < DELAY_AFTER_FAILURES {
+        return Duration::ZERO;
+    }
+
+    let exponent = (failures - DELAY_AFTER_FAILURES).min(8) as u32;
+    (Duration::from_millis(250) * 2u32.pow(exponent)).min(MAX_DELAY)
+}
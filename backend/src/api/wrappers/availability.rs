@@ -0,0 +1,69 @@
+use crate::api::app::{ApiError, AppState};
+use crate::db::models::Court;
+use crate::db::queries::{court, court_block, opening_hours, reservation};
+use crate::planning::{self, DayAvailability, MAX_AVAILABILITY_DAYS};
+use axum::extract::{Json, Query, State};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct AvailabilityParams {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub court: Option<i16>,
+}
+
+#[derive(Serialize)]
+pub struct AvailabilityResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub days: Vec<DayAvailability>,
+}
+
+// Public, since it does not tell who booked the courts
+pub async fn get_availability(
+    State(state): State<AppState>,
+    Query(params): Query<AvailabilityParams>,
+) -> Result<Json<AvailabilityResponse>, ApiError> {
+    let days: i64 = (params.to - params.from).num_days() + 1;
+    if !(1..=MAX_AVAILABILITY_DAYS).contains(&days) {
+        return Err(ApiError::InvalidDateRange);
+    }
+
+    let client = state.pool.get().await?;
+
+    // Closed courts are only listed when explicitly requested
+    let courts: Vec<Court> = court::get_all_courts(&client)
+        .await?
+        .into_iter()
+        .filter(|court| match params.court {
+            Some(number) => court.number == number,
+            None => court.is_active,
+        })
+        .collect();
+    if params.court.is_some() && courts.is_empty() {
+        return Err(ApiError::NotFound);
+    }
+
+    let opening_hours = opening_hours::get_all_opening_hours(&client).await?;
+    let reservations =
+        reservation::get_reservations_between(&client, &params.from, &params.to, params.court)
+            .await?;
+    let blocks = court_block::get_blocks_between(&client, &params.from, &params.to).await?;
+
+    let now: NaiveDateTime = state.config.club_now();
+
+    Ok(Json(AvailabilityResponse {
+        from: params.from,
+        to: params.to,
+        days: planning::availability(
+            params.from,
+            params.to,
+            &courts,
+            &opening_hours,
+            &reservations,
+            &blocks,
+            now,
+        ),
+    }))
+}
@@ -0,0 +1,28 @@
+use crate::api::app::{ApiError, AppState};
+use crate::api::auth::AdminOnly;
+use crate::db::models::BookingRules;
+use crate::db::queries::booking_rules;
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+
+pub async fn get_booking_rules(
+    State(state): State<AppState>,
+) -> Result<Json<BookingRules>, ApiError> {
+    let client = state.pool.get().await?;
+    let rules: BookingRules = booking_rules::get_booking_rules(&client).await?;
+    Ok(Json(rules))
+}
+
+/// Replaces the booking rules with the full set in the body: rules left out are disabled, and
+/// participants do not count unless `count_participants` is set.
+pub async fn update_booking_rules(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Json(payload): Json<BookingRules>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    booking_rules::update_booking_rules(&client, &payload).await?;
+    Ok(StatusCode::OK)
+}
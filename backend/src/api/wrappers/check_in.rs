@@ -0,0 +1,100 @@
+use crate::api::app::{ApiError, AppState};
+use crate::api::auth::AuthMember;
+use crate::db::models::{BookingRules, Reservation};
+use crate::db::queries::{booking_rules, member, reservation};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use deadpool_postgres::{Client, Transaction};
+use tokio_postgres::Error;
+
+/// Reservations which ended longer ago are no longer marked as no-shows, so that a job which
+/// did not run for a while does not penalize members for past days.
+const NO_SHOW_LOOKBACK_HOURS: i64 = 24;
+
+/// Checks the member in for their reservation. Members can only do so around the start time,
+/// while admins can check in a member at any time, clearing the no-show recorded meanwhile and
+/// the suspension it caused.
+pub async fn check_in(
+    State(state): State<AppState>,
+    auth: AuthMember,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let mut client = state.pool.get().await?;
+    let existing: Reservation = reservation::get_reservation(&client, &id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if !auth.can_act_for(&existing.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+    if existing.cancellation.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    if existing.check_in.is_some() {
+        return Ok(StatusCode::OK);
+    }
+
+    let rules: BookingRules = booking_rules::get_booking_rules(&client).await?;
+    if !auth.is_admin()
+        && (existing.is_no_show || !rules.can_check_in(&existing, state.config.club_now()))
+    {
+        return Err(ApiError::CheckInClosed);
+    }
+
+    let transaction: Transaction = client.transaction().await?;
+    if reservation::check_in_reservation(&transaction, &id, auth.id()).await? == 1
+        && existing.is_no_show
+    {
+        let no_show_count: i32 =
+            member::decrement_no_shows(&transaction, &existing.member_id).await?;
+        // The forgiven no-show is the one which reached the threshold
+        if let Some(threshold) = rules.no_show_suspension_threshold
+            && (no_show_count + 1) % i32::from(threshold) == 0
+        {
+            member::set_suspended_until(&transaction, &existing.member_id, None).await?;
+            tracing::info!(
+                "Suspension of member {} lifted after a no-show was cleared",
+                existing.member_id
+            );
+        }
+    }
+    transaction.commit().await?;
+    Ok(StatusCode::OK)
+}
+
+/// Marks as no-shows the reservations which ended by `now` without a check-in, and suspends
+/// the bookings of members reaching the no-show threshold. Returns the number of no-shows.
+pub async fn mark_no_shows(client: &mut Client, now: NaiveDateTime) -> Result<u64, Error> {
+    let rules: BookingRules = booking_rules::get_booking_rules(client).await?;
+    let transaction: Transaction = client.transaction().await?;
+    let no_shows: Vec<Reservation> = reservation::mark_no_shows(
+        &transaction,
+        &(now - Duration::hours(NO_SHOW_LOOKBACK_HOURS)),
+        &now,
+    )
+    .await?;
+
+    for no_show in &no_shows {
+        let no_show_count: i32 =
+            member::increment_no_shows(&transaction, &no_show.member_id).await?;
+
+        if let (Some(threshold), Some(days)) = (
+            rules.no_show_suspension_threshold,
+            rules.no_show_suspension_days,
+        ) && no_show_count % i32::from(threshold) == 0
+        {
+            let until: NaiveDate = now.date() + Duration::days(days.into());
+            member::set_suspended_until(&transaction, &no_show.member_id, Some(&until)).await?;
+            tracing::info!(
+                "Bookings of member {} suspended until {} after {} no-shows",
+                no_show.member_id,
+                until,
+                no_show_count
+            );
+        }
+    }
+    transaction.commit().await?;
+    Ok(no_shows.len() as u64)
+}
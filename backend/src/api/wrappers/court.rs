@@ -0,0 +1,65 @@
+use crate::api::app::{ApiError, AppState};
+use crate::api::auth::AdminOnly;
+use crate::db::models::Court;
+use crate::db::queries::court;
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+
+pub async fn add_court(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Json(payload): Json<Court>,
+) -> Result<Json<i16>, ApiError> {
+    let client = state.pool.get().await?;
+    let number: i16 = court::add_court(&client, &payload).await?;
+    Ok(Json(number))
+}
+
+// Public, like the availability of the courts
+pub async fn get_all_courts(State(state): State<AppState>) -> Result<Json<Vec<Court>>, ApiError> {
+    let client = state.pool.get().await?;
+    let courts: Vec<Court> = court::get_all_courts(&client).await?;
+    Ok(Json(courts))
+}
+
+pub async fn get_court(
+    State(state): State<AppState>,
+    Path(number): Path<i16>,
+) -> Result<Json<Court>, ApiError> {
+    let client = state.pool.get().await?;
+    match court::get_court(&client, number).await? {
+        Some(court) => Ok(Json(court)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+pub async fn update_court(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Json(payload): Json<Court>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    let affected = court::update_court(&client, &payload).await?;
+    if affected == 1 {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+// Courts which have been booked cannot be deleted, only deactivated
+pub async fn delete_court(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Path(number): Path<i16>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    let affected = court::delete_court(&client, number).await?;
+    if affected == 1 {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
@@ -0,0 +1,146 @@
+use crate::api::app::{ApiError, AppState};
+use crate::api::auth::AdminOnly;
+use crate::api::email::EmailService;
+use crate::api::wrappers::reservation_series::Frequency;
+use crate::db::models::{CourtBlock, Member, Reservation};
+use crate::db::queries::{court, court_block, member, reservation};
+use crate::planning;
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct CourtBlockPayload {
+    /// Every court if `None`.
+    pub court_numbers: Option<Vec<i16>>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub reason: String,
+    /// Repeats the block until `until`.
+    pub frequency: Option<Frequency>,
+    pub until: Option<NaiveDate>,
+    /// Cancels the reservations overlapping the block and notifies their members, instead of
+    /// keeping them.
+    #[serde(default)]
+    pub cancel_overlapping: bool,
+}
+
+#[derive(Serialize)]
+pub struct CourtBlockResponse {
+    pub id: i32,
+    /// Reservations overlapping the block, cancelled if requested.
+    pub overlapping: Vec<String>,
+}
+
+pub async fn add_block(
+    State(state): State<AppState>,
+    AdminOnly(admin): AdminOnly,
+    Json(payload): Json<CourtBlockPayload>,
+) -> Result<Json<CourtBlockResponse>, ApiError> {
+    let block = CourtBlock {
+        id: 0,
+        court_numbers: payload.court_numbers.filter(|numbers| !numbers.is_empty()),
+        starts_at: payload.starts_at,
+        ends_at: payload.ends_at,
+        reason: payload.reason,
+        interval_weeks: payload.frequency.map(|frequency| match frequency {
+            Frequency::Weekly => 1,
+            Frequency::Biweekly => 2,
+        }),
+        until_date: payload.until,
+        created_by: Some(admin.sub.clone()),
+    };
+    if block.ends_at <= block.starts_at
+        || block.interval_weeks.is_some() != block.until_date.is_some()
+        || block
+            .until_date
+            .is_some_and(|until| until < block.starts_at.date())
+    {
+        return Err(ApiError::InvalidCourtBlock);
+    }
+
+    let mut client = state.pool.get().await?;
+    if let Some(court_numbers) = &block.court_numbers {
+        for court_number in court_numbers {
+            if court::get_court(&client, *court_number).await?.is_none() {
+                return Err(ApiError::NotFound);
+            }
+        }
+    }
+
+    // Reservations are looked for once the block is added, which waits for those being booked
+    let transaction = client.transaction().await?;
+    let id: i32 = court_block::add_block(&transaction, &block).await?;
+    let last_date: NaiveDate = block
+        .periods()
+        .last()
+        .map_or(block.ends_at, |(_, end)| end)
+        .date();
+    let overlapping: Vec<Reservation> = reservation::get_reservations_between(
+        &transaction,
+        &block.starts_at.date(),
+        &last_date,
+        None,
+    )
+    .await?
+    .into_iter()
+    .filter(|r| planning::blocking(r, std::slice::from_ref(&block)).is_some())
+    .collect();
+
+    if payload.cancel_overlapping {
+        let email_service = EmailService::new(&transaction, &state.config);
+        for cancelled in &overlapping {
+            let member: Member = member::get_member(&transaction, &cancelled.member_id).await?;
+            reservation::cancel_reservation(
+                &transaction,
+                &cancelled.id,
+                &admin.sub,
+                Some(&block.reason),
+                false,
+            )
+            .await?;
+
+            email_service
+                .send_court_block_cancellation_email(&member, cancelled, &block.reason)
+                .await?;
+        }
+        tracing::info!(
+            "Court block {} cancelled {} reservation(s)",
+            id,
+            overlapping.len()
+        );
+    }
+    transaction.commit().await?;
+
+    Ok(Json(CourtBlockResponse {
+        id,
+        overlapping: overlapping.into_iter().map(|r| r.id).collect(),
+    }))
+}
+
+/// Blocks not over yet, public like the opening hours.
+pub async fn get_upcoming_blocks(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CourtBlock>>, ApiError> {
+    let client = state.pool.get().await?;
+    let today: NaiveDate = state.config.club_now().date();
+    let blocks: Vec<CourtBlock> = court_block::get_upcoming_blocks(&client, &today).await?;
+    Ok(Json(blocks))
+}
+
+pub async fn delete_block(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    let affected = court_block::delete_block(&client, id).await?;
+    if affected == 1 {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
@@ -0,0 +1,43 @@
+use crate::api::app::{ApiError, AppState};
+use crate::api::auth::AdminOnly;
+use crate::db::models::{OutboxEmailSummary, OutboxStatus};
+use crate::db::queries::email_outbox;
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+
+/// Emails listed at most, the most recent first.
+const OUTBOX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct OutboxParams {
+    pub status: Option<OutboxStatus>,
+}
+
+/// Queued emails and the outcome of their delivery, for admins to spot undelivered ones. Their
+/// content is left out, as it may hold links signing the recipient in.
+pub async fn get_outbox(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Query(params): Query<OutboxParams>,
+) -> Result<Json<Vec<OutboxEmailSummary>>, ApiError> {
+    let client = state.pool.get().await?;
+    let emails: Vec<OutboxEmailSummary> =
+        email_outbox::get_emails(&client, params.status, OUTBOX_PAGE_SIZE).await?;
+    Ok(Json(emails))
+}
+
+/// Queues an email given up on again, once the cause of its failure is fixed.
+pub async fn retry_email(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    match email_outbox::retry_email(&client, id).await? {
+        0 => Err(ApiError::NotFound),
+        _ => Ok(StatusCode::OK),
+    }
+}
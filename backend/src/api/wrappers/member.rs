@@ -1,6 +1,8 @@
 use crate::api::app::{ApiError, AppState};
-use crate::db::models::{self, PasswordResetToken};
-use crate::db::queries::{member, password_reset_token};
+use crate::api::auth::{AdminOnly, AuthMember};
+use crate::api::login_guard;
+use crate::db::models::{self, Locale, PasswordResetToken};
+use crate::db::queries::{member, password_reset_token, session};
 use crate::utils::{gen_id, hash_password};
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use axum::response::IntoResponse;
@@ -47,6 +49,16 @@ pub struct MemberPayload {
     pub email: String,
     pub first_name: String,
     pub last_name: String,
+    /// Language of the emails, unchanged on updates if `None`.
+    #[serde(default)]
+    pub locale: Option<Locale>,
+    /// Whether to remind the member of reservations by email, unchanged on updates if `None`.
+    #[serde(default)]
+    pub email_reminders: Option<bool>,
+    /// Whether to remind the member of reservations by text message, unchanged on updates if
+    /// `None`.
+    #[serde(default)]
+    pub sms_reminders: Option<bool>,
 }
 
 #[derive(Deserialize)]
@@ -56,6 +68,16 @@ pub struct UpdateMemberPayload {
     pub email: String,
     pub first_name: String,
     pub last_name: String,
+    /// Language of the emails, unchanged on updates if `None`.
+    #[serde(default)]
+    pub locale: Option<Locale>,
+    /// Whether to remind the member of reservations by email, unchanged on updates if `None`.
+    #[serde(default)]
+    pub email_reminders: Option<bool>,
+    /// Whether to remind the member of reservations by text message, unchanged on updates if
+    /// `None`.
+    #[serde(default)]
+    pub sms_reminders: Option<bool>,
 }
 
 #[derive(Deserialize)]
@@ -74,6 +96,7 @@ pub struct PasswordResetPayload {
 
 pub async fn add_member(
     State(state): State<AppState>,
+    _admin: AdminOnly,
     Json(payload): Json<MemberPayload>,
 ) -> Result<Response, ApiError> {
     let id: String = match payload.id {
@@ -114,6 +137,12 @@ pub async fn add_member(
             first_name,
             last_name,
             is_admin: false,
+            late_cancellations: 0,
+            no_shows: 0,
+            suspended_until: None,
+            locale: payload.locale.unwrap_or_default(),
+            email_reminders: payload.email_reminders.unwrap_or(true),
+            sms_reminders: payload.sms_reminders.unwrap_or(false),
         },
     )
     .await?;
@@ -132,8 +161,13 @@ pub async fn add_member(
 
 pub async fn get_member(
     State(state): State<AppState>,
+    auth: AuthMember,
     Path(id): Path<String>,
 ) -> Result<Json<models::Member>, ApiError> {
+    if !auth.can_act_for(&id) {
+        return Err(ApiError::NotProfileOwner);
+    }
+
     let client = state.pool.get().await?;
     match member::get_member(&client, &id).await {
         Ok(member) => Ok(Json(member)),
@@ -143,6 +177,7 @@ pub async fn get_member(
 
 pub async fn get_all_members(
     State(state): State<AppState>,
+    _admin: AdminOnly,
     Query(params): Query<PaginationParams>,
 ) -> Result<Json<PaginatedResponse>, ApiError> {
     let client = state.pool.get().await?;
@@ -171,8 +206,13 @@ pub async fn get_all_members(
 
 pub async fn update_member(
     State(state): State<AppState>,
+    auth: AuthMember,
     Json(payload): Json<UpdateMemberPayload>,
 ) -> Result<StatusCode, ApiError> {
+    if !auth.can_act_for(&payload.id) {
+        return Err(ApiError::NotProfileOwner);
+    }
+
     let email: Option<String> = match payload.email {
         ref email if email.is_empty() => None,
         email => Some(email),
@@ -192,13 +232,24 @@ pub async fn update_member(
     let affected = member::update_member(
         &client,
         &models::Member {
-            id: payload.id,
+            id: payload.id.clone(),
             phone: payload.phone,
             password: "".to_string(), // Unused
             email,
             first_name,
             last_name,
             is_admin: false,
+            late_cancellations: 0,
+            no_shows: 0,
+            suspended_until: None,
+            locale: Locale::default(), // Unused
+            email_reminders: true,     // Unused
+            sms_reminders: true,       // Unused
+        },
+        &models::MemberPreferences {
+            locale: payload.locale,
+            email_reminders: payload.email_reminders,
+            sms_reminders: payload.sms_reminders,
         },
     )
     .await?;
@@ -212,8 +263,13 @@ pub async fn update_member(
 
 pub async fn update_member_with_password(
     State(state): State<AppState>,
+    auth: AuthMember,
     Json(payload): Json<MemberPayload>,
 ) -> Result<StatusCode, ApiError> {
+    if !auth.can_act_for(&payload.id) {
+        return Err(ApiError::NotProfileOwner);
+    }
+
     let hashed_password: String =
         hash_password(&payload.password).expect("Could not hash the password.");
 
@@ -236,18 +292,33 @@ pub async fn update_member_with_password(
     let affected = member::update_member_with_password(
         &client,
         &models::Member {
-            id: payload.id,
+            id: payload.id.clone(),
             phone: payload.phone,
             password: hashed_password,
             email,
             first_name,
             last_name,
             is_admin: false,
+            late_cancellations: 0,
+            no_shows: 0,
+            suspended_until: None,
+            locale: Locale::default(), // Unused
+            email_reminders: true,     // Unused
+            sms_reminders: true,       // Unused
+        },
+        &models::MemberPreferences {
+            locale: payload.locale,
+            email_reminders: payload.email_reminders,
+            sms_reminders: payload.sms_reminders,
         },
     )
     .await?;
 
     if affected == 1 {
+        let keep = (auth.id() == payload.id)
+            .then(|| auth.session_id())
+            .flatten();
+        session::revoke_member_sessions(&client, &payload.id, keep.as_ref()).await?;
         Ok(StatusCode::OK)
     } else {
         Err(ApiError::NotFound)
@@ -256,6 +327,7 @@ pub async fn update_member_with_password(
 
 pub async fn delete_member(
     State(state): State<AppState>,
+    _admin: AdminOnly,
     Path(id): Path<String>,
 ) -> Result<StatusCode, ApiError> {
     let client = state.pool.get().await?;
@@ -267,10 +339,52 @@ pub async fn delete_member(
     }
 }
 
+pub async fn unlock_member(
+    State(state): State<AppState>,
+    AdminOnly(admin): AdminOnly,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    let member: models::Member = member::get_member(&client, &id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+
+    login_guard::unlock(&client, &member.phone).await?;
+    tracing::info!("Member {} unlocked by admin {}", member.id, admin.sub);
+
+    Ok(StatusCode::OK)
+}
+
+/// Lets a member suspended after repeated no-shows book again.
+pub async fn lift_suspension(
+    State(state): State<AppState>,
+    AdminOnly(admin): AdminOnly,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    let member: models::Member = member::get_member(&client, &id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+
+    member::set_suspended_until(&client, &member.id, None).await?;
+    tracing::info!(
+        "Suspension of member {} lifted by admin {}",
+        member.id,
+        admin.sub
+    );
+
+    Ok(StatusCode::OK)
+}
+
 pub async fn update_password(
     State(state): State<AppState>,
+    auth: AuthMember,
     Json(payload): Json<EditPasswordPayload>,
 ) -> Result<Response, ApiError> {
+    if !auth.can_act_for(&payload.id) {
+        return Err(ApiError::NotProfileOwner);
+    }
+
     let client = state.pool.get().await?;
 
     // Check if current_password is correct
@@ -299,6 +413,12 @@ pub async fn update_password(
         member::update_member_password(&client, &member.id, &hashed_new_password).await?;
 
     if affected == 1 {
+        // Log out the member's other devices
+        let keep = (auth.id() == member.id)
+            .then(|| auth.session_id())
+            .flatten();
+        session::revoke_member_sessions(&client, &member.id, keep.as_ref()).await?;
+
         let response = (
             StatusCode::OK,
             Json(json!({
@@ -341,6 +461,8 @@ pub async fn password_reset(
             .await?;
 
     if affected == 1 {
+        session::revoke_member_sessions(&client, &token_from_db.member_id, None).await?;
+
         let response = (
             StatusCode::OK,
             Json(json!({
@@ -0,0 +1,54 @@
+use crate::api::app::{ApiError, AppState};
+use crate::api::auth::AdminOnly;
+use crate::db::models::OpeningHours;
+use crate::db::queries::opening_hours;
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+
+pub async fn add_opening_hours(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Json(payload): Json<OpeningHours>,
+) -> Result<Json<i32>, ApiError> {
+    let client = state.pool.get().await?;
+    let id: i32 = opening_hours::add_opening_hours(&client, &payload).await?;
+    Ok(Json(id))
+}
+
+pub async fn get_all_opening_hours(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<OpeningHours>>, ApiError> {
+    let client = state.pool.get().await?;
+    let hours: Vec<OpeningHours> = opening_hours::get_all_opening_hours(&client).await?;
+    Ok(Json(hours))
+}
+
+pub async fn update_opening_hours(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Json(payload): Json<OpeningHours>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    let affected = opening_hours::update_opening_hours(&client, &payload).await?;
+    if affected == 1 {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+pub async fn delete_opening_hours(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    let affected = opening_hours::delete_opening_hours(&client, id).await?;
+    if affected == 1 {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
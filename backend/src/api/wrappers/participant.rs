@@ -0,0 +1,157 @@
+use crate::api::app::{ApiError, AppState};
+use crate::api::auth::AuthMember;
+use crate::api::wrappers::reservation::rule_violations;
+use crate::db::models::{BookingRules, Participant, ParticipantStatus, Reservation};
+use crate::db::queries::{booking_rules, member, participant, reservation};
+use crate::planning::MAX_PARTICIPANTS;
+use crate::rules::RuleViolation;
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use chrono::NaiveDateTime;
+use deadpool_postgres::{Client, GenericClient, Transaction};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ParticipantsPayload {
+    #[serde(default)]
+    pub member_ids: Vec<String>,
+    #[serde(default)]
+    pub guest_names: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AnswerPayload {
+    pub member_id: String,
+    pub accept: bool,
+}
+
+async fn get_active_reservation(client: &Client, id: &String) -> Result<Reservation, ApiError> {
+    reservation::get_reservation(client, id)
+        .await
+        .ok()
+        .filter(|r| r.cancellation.is_none())
+        .ok_or(ApiError::NotFound)
+}
+
+/// Checks that `member_id` is not suspended and, when the booking rules count participants,
+/// that joining the reservation keeps them within their quotas.
+async fn ensure_participant_rules(
+    state: &AppState,
+    client: &impl GenericClient,
+    member_id: &str,
+    reservation: &Reservation,
+) -> Result<(), ApiError> {
+    member::lock_member(client, member_id).await?;
+    let now: NaiveDateTime = state.config.club_now();
+    if let Some(until) = member::get_member(client, &member_id.to_string())
+        .await?
+        .suspended_until
+        .filter(|until| *until >= now.date())
+    {
+        return Err(ApiError::MemberSuspended { until });
+    }
+
+    let rules: BookingRules = booking_rules::get_booking_rules(client).await?;
+    if !rules.count_participants {
+        return Ok(());
+    }
+    let violations: Vec<RuleViolation> =
+        rule_violations(client, &rules, member_id, reservation, now)
+            .await?
+            .into_iter()
+            .filter(RuleViolation::is_quota)
+            .collect();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::ParticipantRuleViolations {
+            member_id: member_id.to_string(),
+            violations,
+        })
+    }
+}
+
+/// Replaces the members invited to a reservation and its guests.
+pub async fn set_participants(
+    State(state): State<AppState>,
+    auth: AuthMember,
+    Path(id): Path<String>,
+    Json(payload): Json<ParticipantsPayload>,
+) -> Result<Json<Vec<Participant>>, ApiError> {
+    let mut client = state.pool.get().await?;
+    let existing: Reservation = get_active_reservation(&client, &id).await?;
+    if !auth.can_act_for(&existing.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+
+    let mut member_ids: Vec<String> = payload.member_ids;
+    member_ids.sort();
+    member_ids.dedup();
+    let guest_names: Vec<String> = payload
+        .guest_names
+        .iter()
+        .map(|name| name.trim().to_string())
+        .collect();
+    if member_ids.len() + guest_names.len() > MAX_PARTICIPANTS
+        || member_ids.contains(&existing.member_id)
+        || guest_names.iter().any(String::is_empty)
+    {
+        return Err(ApiError::InvalidParticipants);
+    }
+
+    let transaction: Transaction = client.transaction().await?;
+    for member_id in &member_ids {
+        if member::get_member(&transaction, member_id).await.is_err() {
+            return Err(ApiError::InvalidParticipants);
+        }
+    }
+
+    participant::set_participants(&transaction, &id, &member_ids, &guest_names).await?;
+    transaction.commit().await?;
+
+    let participants: Vec<Participant> = participant::get_participants(&client, &id).await?;
+    Ok(Json(participants))
+}
+
+/// Accepts or declines the invitation of a member to a reservation.
+pub async fn answer_invitation(
+    State(state): State<AppState>,
+    auth: AuthMember,
+    Path(id): Path<String>,
+    Json(payload): Json<AnswerPayload>,
+) -> Result<StatusCode, ApiError> {
+    if !auth.can_act_for(&payload.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+
+    let mut client = state.pool.get().await?;
+    let existing: Reservation = get_active_reservation(&client, &id).await?;
+    let status: ParticipantStatus = if payload.accept {
+        ParticipantStatus::Accepted
+    } else {
+        ParticipantStatus::Declined
+    };
+
+    // Only accepted invitations count towards the quotas, so accepting one may not fit
+    let was_accepted: bool = participant::get_participants(&client, &id)
+        .await?
+        .iter()
+        .any(|p| {
+            p.member_id.as_ref() == Some(&payload.member_id)
+                && p.status == ParticipantStatus::Accepted
+        });
+    let transaction: Transaction = client.transaction().await?;
+    if payload.accept && !was_accepted && !auth.is_admin() {
+        ensure_participant_rules(&state, &transaction, &payload.member_id, &existing).await?;
+    }
+
+    let affected = participant::set_status(&transaction, &id, &payload.member_id, status).await?;
+    if affected == 1 {
+        transaction.commit().await?;
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
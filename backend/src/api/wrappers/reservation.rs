@@ -1,13 +1,24 @@
 use crate::api::app::{ApiError, AppState};
-use crate::db::models;
-use crate::db::queries::reservation;
+use crate::api::auth::AuthMember;
+use crate::api::email::{EmailService, ReservationChange};
+use crate::api::wrappers::{reservation_series, waitlist};
+use crate::db::models::{self, BookingRules, CourtBlock, OpeningHours};
+use crate::db::queries::{
+    booking_rules, court, court_block, member, opening_hours, participant, reservation,
+};
+use crate::planning;
+use crate::rules::{self, RuleViolation};
 use crate::utils::gen_id;
 use axum::{
-    extract::{Json, Path, State},
+    extract::{Json, Path, Query, State},
     http::StatusCode,
 };
-use chrono::NaiveDate;
-use serde::Deserialize;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use deadpool_postgres::{Client, GenericClient};
+use serde::{Deserialize, Serialize};
+
+/// Number of reservations per page of the history of a member.
+const HISTORY_PAGE_SIZE: i64 = 20;
 
 #[derive(Deserialize)]
 pub struct ReservationPayload {
@@ -15,36 +26,219 @@ pub struct ReservationPayload {
     pub member_id: String,
     pub court_number: i16,
     pub reservation_date: NaiveDate,
-    pub reservation_time: i16,
+    pub start_time: NaiveTime,
+    pub duration_minutes: i16,
+}
+
+/// Occurrences of a series affected by a change.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    #[default]
+    This,
+    /// This occurrence and the following ones, for admins only.
+    Following,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScopeParams {
+    #[serde(default)]
+    pub scope: Scope,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryStatus {
+    #[default]
+    Upcoming,
+    Past,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryParams {
+    #[serde(default)]
+    pub status: HistoryStatus,
+    /// Starting from 1.
+    pub page: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct ReservationPage {
+    pub page: i64,
+    pub page_size: i64,
+    pub total: i64,
+    pub reservations: Vec<models::Reservation>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelParams {
+    #[serde(default)]
+    pub scope: Scope,
+    pub reason: Option<String>,
+}
+
+/// Checks the duration, that the court is open and not blocked, and that the reservation starts
+/// at the start of a slot and ends before the court closes.
+pub(crate) async fn ensure_slot_bookable(
+    client: &impl GenericClient,
+    reservation: &models::Reservation,
+) -> Result<(), ApiError> {
+    if !planning::DURATIONS.contains(&reservation.duration_minutes) {
+        return Err(ApiError::InvalidDuration);
+    }
+
+    match court::get_court(client, reservation.court_number).await? {
+        Some(court) if court.is_active => {}
+        _ => return Err(ApiError::CourtUnavailable),
+    }
+
+    let hours: Vec<OpeningHours> = opening_hours::get_all_opening_hours(client).await?;
+    if !planning::fits_opening_hours(reservation, &hours) {
+        let date: NaiveDate = reservation.reservation_date;
+        let court_number: i16 = reservation.court_number;
+        return Err(ApiError::OutsideOpeningHours {
+            court_number,
+            date,
+            slots: planning::slots(date, court_number, &hours)
+                .into_iter()
+                .map(|slot| slot.start)
+                .collect(),
+        });
+    }
+
+    ensure_not_blocked(client, reservation).await
+}
+
+async fn ensure_not_blocked(
+    client: &impl GenericClient,
+    reservation: &models::Reservation,
+) -> Result<(), ApiError> {
+    let date: NaiveDate = reservation.reservation_date;
+    let blocks: Vec<CourtBlock> = court_block::get_blocks_between(client, &date, &date).await?;
+    if let Some(block) = planning::blocking(reservation, &blocks) {
+        return Err(ApiError::CourtBlocked {
+            court_number: reservation.court_number,
+            reason: block.reason.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks the court blocks again in the transaction writing the reservation, no block being
+/// added until it ends, since one may have been added after [`ensure_slot_bookable`].
+pub(crate) async fn ensure_still_unblocked(
+    client: &impl GenericClient,
+    reservation: &models::Reservation,
+) -> Result<(), ApiError> {
+    court_block::lock_blocks(client).await?;
+    ensure_not_blocked(client, reservation).await
+}
+
+/// Checks that the member is not suspended and the booking rules of the club, which do not
+/// apply to administrators. Runs in the transaction writing the reservation, the member being
+/// locked until it ends so that concurrent bookings cannot together exceed the quotas.
+pub(crate) async fn ensure_booking_rules(
+    state: &AppState,
+    client: &impl GenericClient,
+    reservation: &models::Reservation,
+) -> Result<(), ApiError> {
+    member::lock_member(client, &reservation.member_id).await?;
+    let now: NaiveDateTime = state.config.club_now();
+    if let Some(until) = member::get_member(client, &reservation.member_id)
+        .await?
+        .suspended_until
+        .filter(|until| *until >= now.date())
+    {
+        return Err(ApiError::MemberSuspended { until });
+    }
+
+    let rules: BookingRules = booking_rules::get_booking_rules(client).await?;
+    let violations: Vec<RuleViolation> =
+        rule_violations(client, &rules, &reservation.member_id, reservation, now).await?;
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::RuleViolations(violations))
+    }
+}
+
+/// Rules broken by `reservation` for `member_id`, who booked it or participates in it.
+pub(crate) async fn rule_violations(
+    client: &impl GenericClient,
+    rules: &BookingRules,
+    member_id: &str,
+    reservation: &models::Reservation,
+    now: NaiveDateTime,
+) -> Result<Vec<RuleViolation>, tokio_postgres::Error> {
+    let (monday, sunday) = rules::week_of(reservation.reservation_date);
+    let week_reservations: Vec<models::Reservation> = reservation::get_member_reservations_between(
+        client,
+        member_id,
+        &monday,
+        &sunday,
+        rules.count_participants,
+    )
+    .await?;
+    Ok(rules.check(reservation, &week_reservations, now))
+}
+
+/// Offers the time freed by a moved or deleted reservation to the waitlist. The change itself
+/// succeeded, so failures are only logged.
+async fn promote_waitlist(state: &AppState, client: &mut Client, freed: &models::Reservation) {
+    if let Err(e) = waitlist::promote(state, client, freed).await {
+        tracing::error!("Could not promote the waitlist: {}", e);
+    }
 }
 
 pub async fn add_reservation(
     State(state): State<AppState>,
+    auth: AuthMember,
     Json(payload): Json<ReservationPayload>,
 ) -> Result<Json<String>, ApiError> {
+    if !auth.can_act_for(&payload.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+
     let id: String = match payload.id {
         ref id if id.is_empty() => gen_id().expect("Could not generate an ID."),
         id => id,
     };
 
-    let client = state.pool.get().await?;
-    let id_from_db: String = reservation::add_reservation(
-        &client,
-        &models::Reservation {
-            id,
-            member_id: payload.member_id,
-            court_number: payload.court_number,
-            reservation_date: payload.reservation_date,
-            reservation_time: payload.reservation_time,
-        },
-    )
-    .await?;
+    let new_reservation = models::Reservation {
+        id,
+        member_id: payload.member_id,
+        court_number: payload.court_number,
+        reservation_date: payload.reservation_date,
+        start_time: payload.start_time,
+        duration_minutes: payload.duration_minutes,
+        series_id: None,
+        cancellation: None,
+        check_in: None,
+        is_no_show: false,
+    };
+
+    let mut client = state.pool.get().await?;
+    ensure_slot_bookable(&client, &new_reservation).await?;
+
+    let transaction = client.transaction().await?;
+    ensure_still_unblocked(&transaction, &new_reservation).await?;
+    if !auth.is_admin() {
+        ensure_booking_rules(&state, &transaction, &new_reservation).await?;
+    }
+    let id_from_db: String = reservation::add_reservation(&transaction, &new_reservation).await?;
+    let booker: models::Member =
+        member::get_member(&transaction, &new_reservation.member_id).await?;
+    EmailService::new(&transaction, &state.config)
+        .send_reservation_email(&booker, &new_reservation, ReservationChange::Confirmed)
+        .await?;
+    transaction.commit().await?;
 
     Ok(Json(id_from_db))
 }
 
 pub async fn get_reservation(
     State(state): State<AppState>,
+    _member: AuthMember,
     Path(reservation_id): Path<String>,
 ) -> Result<Json<models::Reservation>, ApiError> {
     let client = state.pool.get().await?;
@@ -56,44 +250,220 @@ pub async fn get_reservation(
 
 pub async fn get_reservations_with_names_by_date(
     State(state): State<AppState>,
+    _member: AuthMember,
     Path(date): Path<NaiveDate>,
 ) -> Result<Json<Vec<models::ReservationWithNames>>, ApiError> {
     let client = state.pool.get().await?;
-    let reservations = reservation::get_reservations_with_names_by_date(&client, &date).await?;
+    let mut reservations = reservation::get_reservations_with_names_by_date(&client, &date).await?;
+    for participant in participant::get_participants_by_date(&client, &date).await? {
+        if let Some(reservation) = reservations
+            .iter_mut()
+            .find(|r| r.id == participant.reservation_id)
+        {
+            reservation.participants.push(participant);
+        }
+    }
     Ok(Json(reservations))
 }
 
-pub async fn update_reservation(
+/// Reservations the member booked or joins, upcoming or past, one page at a time.
+pub async fn get_my_reservations(
     State(state): State<AppState>,
-    Json(payload): Json<ReservationPayload>,
-) -> Result<StatusCode, ApiError> {
+    auth: AuthMember,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<ReservationPage>, ApiError> {
+    let page: i64 = params.page.unwrap_or(1).max(1);
+    let offset: i64 = (page - 1)
+        .checked_mul(HISTORY_PAGE_SIZE)
+        .ok_or(ApiError::InvalidPage)?;
     let client = state.pool.get().await?;
-    let affected = reservation::update_reservation(
+    let (reservations, total) = reservation::get_member_reservations_page(
         &client,
-        &models::Reservation {
-            id: payload.id,
-            member_id: payload.member_id,
-            court_number: payload.court_number,
-            reservation_date: payload.reservation_date,
-            reservation_time: payload.reservation_time,
-        },
+        auth.id(),
+        params.status == HistoryStatus::Upcoming,
+        &state.config.club_now(),
+        HISTORY_PAGE_SIZE,
+        offset,
     )
     .await?;
 
+    Ok(Json(ReservationPage {
+        page,
+        page_size: HISTORY_PAGE_SIZE,
+        total,
+        reservations,
+    }))
+}
+
+pub async fn update_reservation(
+    State(state): State<AppState>,
+    auth: AuthMember,
+    Query(params): Query<ScopeParams>,
+    Json(payload): Json<ReservationPayload>,
+) -> Result<StatusCode, ApiError> {
+    if !auth.can_act_for(&payload.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+
+    let mut client = state.pool.get().await?;
+    let existing: models::Reservation = reservation::get_reservation(&client, &payload.id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if !auth.can_act_for(&existing.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+    if existing.cancellation.is_some() {
+        return Err(ApiError::NotFound);
+    }
+
+    if params.scope == Scope::Following
+        && let Some(series_id) = &existing.series_id
+    {
+        if !auth.is_admin() {
+            return Err(ApiError::AdminRequired);
+        }
+        reservation_series::update_following(&state, &mut client, &existing, series_id, &payload)
+            .await?;
+        return Ok(StatusCode::OK);
+    }
+
+    let updated_reservation = models::Reservation {
+        id: payload.id,
+        member_id: payload.member_id,
+        court_number: payload.court_number,
+        reservation_date: payload.reservation_date,
+        start_time: payload.start_time,
+        duration_minutes: payload.duration_minutes,
+        series_id: existing.series_id.clone(),
+        cancellation: None,
+        check_in: None,
+        is_no_show: false,
+    };
+    ensure_slot_bookable(&client, &updated_reservation).await?;
+
+    // Moving a reservation gives up its slot, so it is limited like a cancellation
+    let is_moved: bool = existing.reservation_date != updated_reservation.reservation_date
+        || existing.start_time != updated_reservation.start_time
+        || existing.court_number != updated_reservation.court_number;
+    let mut is_late: bool = false;
+    if is_moved && !auth.is_admin() {
+        let rules: BookingRules = booking_rules::get_booking_rules(&client).await?;
+        let now: NaiveDateTime = state.config.club_now();
+        if let Some(cutoff_minutes) = rules.cancellation_cutoff_minutes
+            && !rules.can_cancel(&existing, now)
+        {
+            return Err(ApiError::CancellationDeadlinePassed { cutoff_minutes });
+        }
+        is_late = rules.is_late_cancellation(&existing, now);
+    }
+
+    let transaction = client.transaction().await?;
+    ensure_still_unblocked(&transaction, &updated_reservation).await?;
+    if !auth.is_admin() {
+        ensure_booking_rules(&state, &transaction, &updated_reservation).await?;
+    }
+    let affected = reservation::update_reservation(&transaction, &updated_reservation).await?;
+
     if affected == 1 {
+        if is_late {
+            member::increment_late_cancellations(&transaction, &existing.member_id).await?;
+        }
+        let email_service = EmailService::new(&transaction, &state.config);
+        let booker: models::Member =
+            member::get_member(&transaction, &updated_reservation.member_id).await?;
+        if existing.member_id == updated_reservation.member_id {
+            email_service
+                .send_reservation_email(&booker, &updated_reservation, ReservationChange::Modified)
+                .await?;
+        } else {
+            // Moved to another member, who gets it as a new reservation
+            let previous: models::Member =
+                member::get_member(&transaction, &existing.member_id).await?;
+            email_service
+                .send_reservation_email(
+                    &previous,
+                    &existing,
+                    ReservationChange::Cancelled { reason: None },
+                )
+                .await?;
+            email_service
+                .send_reservation_email(&booker, &updated_reservation, ReservationChange::Confirmed)
+                .await?;
+        }
+        transaction.commit().await?;
+
+        promote_waitlist(&state, &mut client, &existing).await;
         Ok(StatusCode::OK)
     } else {
         Err(ApiError::NotFound)
     }
 }
 
-pub async fn delete_reservation(
+/// Cancels a reservation, which is kept with its cancellation. Members cannot cancel past the
+/// cancellation cutoff, and late cancellations are counted on the member.
+pub async fn cancel_reservation(
     State(state): State<AppState>,
+    auth: AuthMember,
     Path(id): Path<String>,
+    Query(params): Query<CancelParams>,
 ) -> Result<StatusCode, ApiError> {
-    let client = state.pool.get().await?;
-    let affected = reservation::delete_reservation(&client, &id).await?;
+    let mut client = state.pool.get().await?;
+    let existing: models::Reservation = reservation::get_reservation(&client, &id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if !auth.can_act_for(&existing.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+    if existing.cancellation.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    let reason: Option<&str> = params.reason.as_deref().filter(|reason| !reason.is_empty());
+
+    if params.scope == Scope::Following
+        && let Some(series_id) = &existing.series_id
+    {
+        if !auth.is_admin() {
+            return Err(ApiError::AdminRequired);
+        }
+        let cancelled: Vec<models::Reservation> = reservation_series::cancel_following(
+            &state,
+            &mut client,
+            &existing,
+            series_id,
+            auth.id(),
+            reason,
+        )
+        .await?;
+        for occurrence in &cancelled {
+            promote_waitlist(&state, &mut client, occurrence).await;
+        }
+        return Ok(StatusCode::OK);
+    }
+
+    let rules: BookingRules = booking_rules::get_booking_rules(&client).await?;
+    let now: NaiveDateTime = state.config.club_now();
+    if !auth.is_admin()
+        && let Some(cutoff_minutes) = rules.cancellation_cutoff_minutes
+        && !rules.can_cancel(&existing, now)
+    {
+        return Err(ApiError::CancellationDeadlinePassed { cutoff_minutes });
+    }
+    let is_late: bool = rules.is_late_cancellation(&existing, now);
+
+    let transaction = client.transaction().await?;
+    let affected =
+        reservation::cancel_reservation(&transaction, &id, auth.id(), reason, is_late).await?;
     if affected == 1 {
+        if is_late {
+            member::increment_late_cancellations(&transaction, &existing.member_id).await?;
+        }
+        let booker: models::Member = member::get_member(&transaction, &existing.member_id).await?;
+        EmailService::new(&transaction, &state.config)
+            .send_reservation_email(&booker, &existing, ReservationChange::Cancelled { reason })
+            .await?;
+        transaction.commit().await?;
+
+        promote_waitlist(&state, &mut client, &existing).await;
         Ok(StatusCode::OK)
     } else {
         Err(ApiError::NotFound)
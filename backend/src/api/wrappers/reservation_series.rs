@@ -0,0 +1,360 @@
+use crate::api::app::{ApiError, AppState};
+use crate::api::auth::AdminOnly;
+use crate::api::email::{EmailService, ReservationChange};
+use crate::api::wrappers::reservation::ReservationPayload;
+use crate::db::models::{CourtBlock, Member, OpeningHours, Reservation, ReservationSeries};
+use crate::db::queries::{
+    court, court_block, member, opening_hours, reservation, reservation_series,
+};
+use crate::planning;
+use crate::utils::gen_id;
+use axum::extract::{Json, State};
+use chrono::{Duration, NaiveDate, NaiveTime};
+use deadpool_postgres::{Client, GenericClient, Transaction};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::error::SqlState;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Weekly,
+    Biweekly,
+}
+
+#[derive(Deserialize)]
+pub struct SeriesPayload {
+    pub member_id: String,
+    pub court_number: i16,
+    pub start_time: NaiveTime,
+    pub duration_minutes: i16,
+    pub first_date: NaiveDate,
+    pub frequency: Frequency,
+    /// Last possible date of an occurrence, exclusive with `count`.
+    pub until: Option<NaiveDate>,
+    pub count: Option<i16>,
+    /// Creates the other occurrences when some conflict, instead of creating none.
+    #[serde(default)]
+    pub skip_conflicts: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictReason {
+    CourtAlreadyBooked,
+    OutsideOpeningHours,
+    CourtBlocked,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OccurrenceConflict {
+    pub date: NaiveDate,
+    pub reason: ConflictReason,
+}
+
+#[derive(Serialize)]
+pub struct Occurrence {
+    pub id: String,
+    pub date: NaiveDate,
+}
+
+#[derive(Serialize)]
+pub struct SeriesResponse {
+    pub id: String,
+    pub reservations: Vec<Occurrence>,
+    pub conflicts: Vec<OccurrenceConflict>,
+}
+
+/// Reason why an occurrence could not be inserted, `None` for unexpected errors.
+pub(crate) fn conflict_reason(error: &tokio_postgres::Error) -> Option<ConflictReason> {
+    match error.code() {
+        Some(code) if *code == SqlState::EXCLUSION_VIOLATION => {
+            Some(ConflictReason::CourtAlreadyBooked)
+        }
+        _ => None,
+    }
+}
+
+async fn ensure_court_active(
+    client: &impl GenericClient,
+    court_number: i16,
+) -> Result<(), ApiError> {
+    match court::get_court(client, court_number).await? {
+        Some(court) if court.is_active => Ok(()),
+        _ => Err(ApiError::CourtUnavailable),
+    }
+}
+
+// Every occurrence is inserted in a savepoint, so that the conflicting ones can be reported
+// and either skipped or cause the whole series to be rolled back
+pub async fn add_series(
+    State(state): State<AppState>,
+    _admin: AdminOnly,
+    Json(payload): Json<SeriesPayload>,
+) -> Result<Json<SeriesResponse>, ApiError> {
+    if !planning::DURATIONS.contains(&payload.duration_minutes) {
+        return Err(ApiError::InvalidDuration);
+    }
+
+    let series = ReservationSeries {
+        id: gen_id().expect("Could not generate an ID."),
+        member_id: payload.member_id,
+        court_number: payload.court_number,
+        start_time: payload.start_time,
+        duration_minutes: payload.duration_minutes,
+        first_date: payload.first_date,
+        interval_weeks: match payload.frequency {
+            Frequency::Weekly => 1,
+            Frequency::Biweekly => 2,
+        },
+        until_date: payload.until,
+        occurrence_count: payload.count,
+    };
+    let dates: Vec<NaiveDate> =
+        planning::occurrence_dates(&series).ok_or(ApiError::InvalidRecurrence)?;
+
+    let mut client: Client = state.pool.get().await?;
+    ensure_court_active(&client, series.court_number).await?;
+    let hours: Vec<OpeningHours> = opening_hours::get_all_opening_hours(&client).await?;
+
+    let mut transaction: Transaction = client.transaction().await?;
+    court_block::lock_blocks(&transaction).await?;
+    let blocks: Vec<CourtBlock> =
+        court_block::get_blocks_between(&transaction, &dates[0], &dates[dates.len() - 1]).await?;
+    reservation_series::add_series(&transaction, &series).await?;
+
+    let mut reservations: Vec<Occurrence> = Vec::new();
+    let mut added: Vec<Reservation> = Vec::new();
+    let mut conflicts: Vec<OccurrenceConflict> = Vec::new();
+    for date in dates {
+        let occurrence = Reservation {
+            id: gen_id().expect("Could not generate an ID."),
+            member_id: series.member_id.clone(),
+            court_number: series.court_number,
+            reservation_date: date,
+            start_time: series.start_time,
+            duration_minutes: series.duration_minutes,
+            series_id: Some(series.id.clone()),
+            cancellation: None,
+            check_in: None,
+            is_no_show: false,
+        };
+
+        if !planning::fits_opening_hours(&occurrence, &hours) {
+            conflicts.push(OccurrenceConflict {
+                date,
+                reason: ConflictReason::OutsideOpeningHours,
+            });
+            continue;
+        }
+        if planning::blocking(&occurrence, &blocks).is_some() {
+            conflicts.push(OccurrenceConflict {
+                date,
+                reason: ConflictReason::CourtBlocked,
+            });
+            continue;
+        }
+
+        let savepoint: Transaction = transaction.transaction().await?;
+        match reservation::add_reservation(&savepoint, &occurrence).await {
+            Ok(id) => {
+                savepoint.commit().await?;
+                reservations.push(Occurrence { id, date });
+                added.push(occurrence);
+            }
+            Err(e) => {
+                let reason: ConflictReason = conflict_reason(&e).ok_or(e)?;
+                savepoint.rollback().await?;
+                conflicts.push(OccurrenceConflict { date, reason });
+            }
+        }
+    }
+
+    if reservations.is_empty() || (!conflicts.is_empty() && !payload.skip_conflicts) {
+        transaction.rollback().await?;
+        return Err(ApiError::SeriesConflicts(conflicts));
+    }
+    let booker: Member = member::get_member(&transaction, &series.member_id).await?;
+    let email_service = EmailService::new(&transaction, &state.config);
+    for occurrence in &added {
+        email_service
+            .send_reservation_email(&booker, occurrence, ReservationChange::Confirmed)
+            .await?;
+    }
+    transaction.commit().await?;
+
+    Ok(Json(SeriesResponse {
+        id: series.id,
+        reservations,
+        conflicts,
+    }))
+}
+
+/// Applies the changes made to an occurrence to the following ones, all or nothing. When the
+/// occurrence is not the first one, the series is split in two.
+pub async fn update_following(
+    state: &AppState,
+    client: &mut Client,
+    existing: &Reservation,
+    series_id: &str,
+    payload: &ReservationPayload,
+) -> Result<(), ApiError> {
+    if !planning::DURATIONS.contains(&payload.duration_minutes) {
+        return Err(ApiError::InvalidDuration);
+    }
+    ensure_court_active(&*client, payload.court_number).await?;
+    let hours: Vec<OpeningHours> = opening_hours::get_all_opening_hours(&*client).await?;
+
+    let transaction: Transaction = client.transaction().await?;
+    let series: ReservationSeries = reservation_series::get_series(&transaction, series_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let mut following: Vec<Reservation> = reservation::get_series_reservations_from(
+        &transaction,
+        series_id,
+        &existing.reservation_date,
+    )
+    .await?;
+    let Some(last_date) = following.last().map(|r| r.reservation_date) else {
+        return Err(ApiError::NotFound);
+    };
+
+    let shift: Duration = payload.reservation_date - existing.reservation_date;
+    court_block::lock_blocks(&transaction).await?;
+    let blocks: Vec<CourtBlock> = court_block::get_blocks_between(
+        &transaction,
+        &payload.reservation_date,
+        &(last_date + shift),
+    )
+    .await?;
+    let updated_series = ReservationSeries {
+        id: if existing.reservation_date > series.first_date {
+            gen_id().expect("Could not generate an ID.")
+        } else {
+            series.id.clone()
+        },
+        member_id: payload.member_id.clone(),
+        court_number: payload.court_number,
+        start_time: payload.start_time,
+        duration_minutes: payload.duration_minutes,
+        first_date: payload.reservation_date,
+        interval_weeks: series.interval_weeks,
+        until_date: Some(last_date + shift),
+        occurrence_count: None,
+    };
+
+    if updated_series.id == series.id {
+        reservation_series::update_series(&transaction, &updated_series).await?;
+    } else {
+        reservation_series::add_series(&transaction, &updated_series).await?;
+        reservation::move_series_reservations_from(
+            &transaction,
+            series_id,
+            &existing.reservation_date,
+            &updated_series.id,
+        )
+        .await?;
+        reservation_series::end_series(
+            &transaction,
+            series_id,
+            &(existing.reservation_date - Duration::days(1)),
+        )
+        .await?;
+    }
+
+    // Moving later occurrences first keeps them from overlapping the ones not moved yet
+    if shift > Duration::zero() {
+        following.reverse();
+    }
+    let booker: Member = member::get_member(&transaction, &payload.member_id).await?;
+    let email_service = EmailService::new(&transaction, &state.config);
+    for occurrence in following {
+        let updated = Reservation {
+            id: occurrence.id.clone(),
+            member_id: payload.member_id.clone(),
+            court_number: payload.court_number,
+            reservation_date: occurrence.reservation_date + shift,
+            start_time: payload.start_time,
+            duration_minutes: payload.duration_minutes,
+            series_id: Some(updated_series.id.clone()),
+            cancellation: None,
+            check_in: None,
+            is_no_show: false,
+        };
+        if !planning::fits_opening_hours(&updated, &hours) {
+            return Err(ApiError::OutsideOpeningHours {
+                court_number: updated.court_number,
+                date: updated.reservation_date,
+                slots: planning::slots(updated.reservation_date, updated.court_number, &hours)
+                    .into_iter()
+                    .map(|slot| slot.start)
+                    .collect(),
+            });
+        }
+        if let Some(block) = planning::blocking(&updated, &blocks) {
+            return Err(ApiError::CourtBlocked {
+                court_number: updated.court_number,
+                reason: block.reason.clone(),
+            });
+        }
+        reservation::update_reservation(&transaction, &updated).await?;
+
+        if occurrence.member_id == updated.member_id {
+            email_service
+                .send_reservation_email(&booker, &updated, ReservationChange::Modified)
+                .await?;
+        } else {
+            // Moved to another member, who gets it as a new reservation
+            let previous: Member = member::get_member(&transaction, &occurrence.member_id).await?;
+            email_service
+                .send_reservation_email(
+                    &previous,
+                    &occurrence,
+                    ReservationChange::Cancelled { reason: None },
+                )
+                .await?;
+            email_service
+                .send_reservation_email(&booker, &updated, ReservationChange::Confirmed)
+                .await?;
+        }
+    }
+
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Cancels an occurrence and the following ones, ending the series the day before. Returns the
+/// cancelled occurrences.
+pub async fn cancel_following(
+    state: &AppState,
+    client: &mut Client,
+    existing: &Reservation,
+    series_id: &str,
+    cancelled_by: &str,
+    reason: Option<&str>,
+) -> Result<Vec<Reservation>, ApiError> {
+    let transaction: Transaction = client.transaction().await?;
+    let cancelled: Vec<Reservation> = reservation::cancel_series_reservations_from(
+        &transaction,
+        series_id,
+        &existing.reservation_date,
+        cancelled_by,
+        reason,
+    )
+    .await?;
+    reservation_series::end_series(
+        &transaction,
+        series_id,
+        &(existing.reservation_date - Duration::days(1)),
+    )
+    .await?;
+
+    let email_service = EmailService::new(&transaction, &state.config);
+    for occurrence in &cancelled {
+        let booker: Member = member::get_member(&transaction, &occurrence.member_id).await?;
+        email_service
+            .send_reservation_email(&booker, occurrence, ReservationChange::Cancelled { reason })
+            .await?;
+    }
+    transaction.commit().await?;
+    Ok(cancelled)
+}
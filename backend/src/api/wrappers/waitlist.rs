@@ -0,0 +1,276 @@
+use crate::api::app::{ApiError, AppState};
+use crate::api::auth::AuthMember;
+use crate::api::email::{EmailService, ReservationChange};
+use crate::api::wrappers::reservation::{
+    ensure_booking_rules, ensure_slot_bookable, ensure_still_unblocked,
+};
+use crate::api::wrappers::reservation_series::conflict_reason;
+use crate::db::models::{Court, CourtBlock, Member, OpeningHours, Reservation, WaitlistEntry};
+use crate::db::queries::{court, court_block, member, opening_hours, reservation, waitlist};
+use crate::planning;
+use crate::utils::gen_id;
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use deadpool_postgres::{Client, Transaction};
+use serde::Deserialize;
+use tokio_postgres::Error;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct WaitlistPayload {
+    pub member_id: String,
+    /// Any court if `None`.
+    pub court_number: Option<i16>,
+    pub reservation_date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub duration_minutes: i16,
+    #[serde(default)]
+    pub auto_book: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ClaimPayload {
+    pub token: Uuid,
+}
+
+fn requested_reservation(entry: &WaitlistEntry, court_number: i16) -> Reservation {
+    Reservation {
+        id: gen_id().expect("Could not generate an ID."),
+        member_id: entry.member_id.clone(),
+        court_number,
+        reservation_date: entry.reservation_date,
+        start_time: entry.start_time,
+        duration_minutes: entry.duration_minutes,
+        series_id: None,
+        cancellation: None,
+        check_in: None,
+        is_no_show: false,
+    }
+}
+
+pub async fn join_waitlist(
+    State(state): State<AppState>,
+    auth: AuthMember,
+    Json(payload): Json<WaitlistPayload>,
+) -> Result<Json<i32>, ApiError> {
+    if !auth.can_act_for(&payload.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+
+    let entry = WaitlistEntry {
+        id: 0,
+        member_id: payload.member_id,
+        court_number: payload.court_number,
+        reservation_date: payload.reservation_date,
+        start_time: payload.start_time,
+        duration_minutes: payload.duration_minutes,
+        auto_book: payload.auto_book,
+        created_at: Utc::now(),
+        claim_token: None,
+        claim_court_number: None,
+        claim_expires_at: None,
+    };
+
+    let client = state.pool.get().await?;
+    match entry.court_number {
+        Some(court_number) => {
+            ensure_slot_bookable(&client, &requested_reservation(&entry, court_number)).await?
+        }
+        None => {
+            if !planning::DURATIONS.contains(&entry.duration_minutes) {
+                return Err(ApiError::InvalidDuration);
+            }
+            let courts: Vec<Court> = court::get_all_courts(&client).await?;
+            let hours: Vec<OpeningHours> = opening_hours::get_all_opening_hours(&client).await?;
+            let bookable: bool = courts.iter().filter(|court| court.is_active).any(|court| {
+                planning::fits_opening_hours(&requested_reservation(&entry, court.number), &hours)
+            });
+            if !bookable {
+                return Err(ApiError::CourtUnavailable);
+            }
+        }
+    }
+
+    let id: i32 = waitlist::add_entry(&client, &entry).await?;
+    Ok(Json(id))
+}
+
+/// Entries of the member, or of every member for admins.
+pub async fn get_waitlist(
+    State(state): State<AppState>,
+    auth: AuthMember,
+) -> Result<Json<Vec<WaitlistEntry>>, ApiError> {
+    let client = state.pool.get().await?;
+    let member_id: Option<&str> = (!auth.is_admin()).then(|| auth.id());
+    let entries: Vec<WaitlistEntry> = waitlist::get_entries(&client, member_id).await?;
+    Ok(Json(entries))
+}
+
+pub async fn leave_waitlist(
+    State(state): State<AppState>,
+    auth: AuthMember,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    let client = state.pool.get().await?;
+    let entry: WaitlistEntry = waitlist::get_entry(&client, id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    if !auth.can_act_for(&entry.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+
+    waitlist::delete_entry(&client, id).await?;
+    Ok(StatusCode::OK)
+}
+
+/// Books the court offered by a claim link, if it has not expired nor been booked meanwhile.
+pub async fn claim_waitlist(
+    State(state): State<AppState>,
+    auth: AuthMember,
+    Json(payload): Json<ClaimPayload>,
+) -> Result<Json<String>, ApiError> {
+    let mut client = state.pool.get().await?;
+    let entry: WaitlistEntry = waitlist::get_entry_by_claim_token(&client, &payload.token)
+        .await?
+        .ok_or(ApiError::TokenExpired)?;
+    if !auth.can_act_for(&entry.member_id) {
+        return Err(ApiError::NotReservationOwner);
+    }
+    let (Some(court_number), Some(expires_at)) = (entry.claim_court_number, entry.claim_expires_at)
+    else {
+        return Err(ApiError::TokenExpired);
+    };
+    if expires_at < Utc::now() {
+        return Err(ApiError::TokenExpired);
+    }
+
+    let claimed: Reservation = requested_reservation(&entry, court_number);
+    ensure_slot_bookable(&client, &claimed).await?;
+
+    // The entry is removed first so that a concurrent claim of the same link books nothing
+    let transaction: Transaction = client.transaction().await?;
+    if waitlist::delete_entry(&transaction, entry.id).await? == 0 {
+        return Err(ApiError::TokenExpired);
+    }
+    ensure_still_unblocked(&transaction, &claimed).await?;
+    if !auth.is_admin() {
+        ensure_booking_rules(&state, &transaction, &claimed).await?;
+    }
+    let id: String = reservation::add_reservation(&transaction, &claimed).await?;
+    let booker: Member = member::get_member(&transaction, &claimed.member_id).await?;
+    EmailService::new(&transaction, &state.config)
+        .send_reservation_email(&booker, &claimed, ReservationChange::Confirmed)
+        .await?;
+    transaction.commit().await?;
+
+    Ok(Json(id))
+}
+
+/// Offers the court of `freed` to the first waiting member whose requested time it can now
+/// host: the member is booked right away or sent a claim link, depending on their choice.
+/// Members whom the booking rules prevent from being booked keep waiting.
+pub async fn promote(
+    state: &AppState,
+    client: &mut Client,
+    freed: &Reservation,
+) -> Result<(), Error> {
+    let entries: Vec<WaitlistEntry> = waitlist::get_waiting_entries(client, freed).await?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+    if !court::get_court(client, freed.court_number)
+        .await?
+        .is_some_and(|court| court.is_active)
+    {
+        return Ok(());
+    }
+
+    let date: NaiveDate = freed.reservation_date;
+    let reservations: Vec<Reservation> =
+        reservation::get_reservations_between(client, &date, &date, Some(freed.court_number))
+            .await?;
+    let hours: Vec<OpeningHours> = opening_hours::get_all_opening_hours(client).await?;
+    let now: NaiveDateTime = state.config.club_now();
+
+    for entry in entries {
+        let candidate: Reservation = requested_reservation(&entry, freed.court_number);
+        if date.and_time(candidate.start_time) <= now
+            || !planning::is_free(&candidate, &reservations)
+            || !planning::fits_opening_hours(&candidate, &hours)
+        {
+            continue;
+        }
+
+        let member: Member = member::get_member(client, &entry.member_id).await?;
+        let transaction = client.transaction().await?;
+        // Blocks are checked in the transaction, none being added until it ends
+        court_block::lock_blocks(&transaction).await?;
+        let blocks: Vec<CourtBlock> =
+            court_block::get_blocks_between(&transaction, &date, &date).await?;
+        if planning::blocking(&candidate, &blocks).is_some()
+            || !member.is_admin
+                && ensure_booking_rules(state, &transaction, &candidate)
+                    .await
+                    .is_err()
+        {
+            continue;
+        }
+        let email_service = EmailService::new(&transaction, &state.config);
+        if entry.auto_book {
+            if let Err(e) = reservation::add_reservation(&transaction, &candidate).await {
+                if conflict_reason(&e).is_some() {
+                    continue;
+                }
+                return Err(e);
+            }
+            waitlist::delete_entry(&transaction, entry.id).await?;
+            email_service
+                .send_waitlist_booking_email(&member, &candidate)
+                .await?;
+            transaction.commit().await?;
+            tracing::info!(
+                "Member {} booked from the waitlist on court {}",
+                member.id,
+                candidate.court_number
+            );
+        } else {
+            let minutes: i64 = state.config.waitlist_claim_minutes;
+            let token: Uuid =
+                waitlist::set_claim(&transaction, entry.id, candidate.court_number, minutes)
+                    .await?;
+            email_service
+                .send_waitlist_claim_email(
+                    &member,
+                    &candidate,
+                    &token,
+                    &format!("{}/waitlist-claim", state.config.frontend_url),
+                    minutes,
+                )
+                .await?;
+            transaction.commit().await?;
+            tracing::info!(
+                "Court {} offered to member {} from the waitlist",
+                candidate.court_number,
+                member.id
+            );
+        }
+        return Ok(());
+    }
+    Ok(())
+}
+
+/// Removes the entries of past days and those whose claim expired, offering the courts of the
+/// latter to the next waiting members. Returns the number of removed entries.
+pub async fn expire_entries(state: &AppState, client: &mut Client) -> Result<u64, Error> {
+    let past: u64 = waitlist::delete_past_entries(client, &state.config.club_now().date()).await?;
+    let expired: Vec<WaitlistEntry> = waitlist::delete_expired_claims(client).await?;
+    for entry in &expired {
+        if let Some(court_number) = entry.claim_court_number {
+            promote(state, client, &requested_reservation(entry, court_number)).await?;
+        }
+    }
+    Ok(past + expired.len() as u64)
+}
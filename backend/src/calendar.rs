@@ -0,0 +1,112 @@
+use crate::db::models::Reservation;
+use crate::mail::Attachment;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, TimeZone, Utc};
+
+// Longer lines are folded (RFC 5545)
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Request,
+    Cancel,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Request => "REQUEST",
+            Method::Cancel => "CANCEL",
+        }
+    }
+}
+
+// Only depends on the reservation, so that calendars update the event they already have
+pub fn event_uid(reservation: &Reservation) -> String {
+    format!("reservation-{}@beachgardensxm", reservation.id)
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut octets: usize = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn utc(local: NaiveDateTime, club_utc_offset: FixedOffset) -> DateTime<Utc> {
+    club_utc_offset
+        .from_local_datetime(&local)
+        .single()
+        .expect("Fixed offsets have no ambiguous times.")
+        .with_timezone(&Utc)
+}
+
+fn format_utc(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Times of the reservation are local to the club, those of the event in UTC
+pub fn reservation_calendar(
+    reservation: &Reservation,
+    summary: &str,
+    method: Method,
+    organizer: &str,
+    attendee: &str,
+    club_utc_offset: FixedOffset,
+    now: DateTime<Utc>,
+) -> String {
+    let starts_at: NaiveDateTime = reservation
+        .reservation_date
+        .and_time(reservation.start_time);
+    let ends_at: NaiveDateTime = starts_at + Duration::minutes(reservation.duration_minutes.into());
+    let status: &str = match method {
+        Method::Request => "CONFIRMED",
+        Method::Cancel => "CANCELLED",
+    };
+
+    [
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Beach Garden SXM//Planning//EN".to_string(),
+        format!("METHOD:{}", method.as_str()),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", event_uid(reservation)),
+        format!("DTSTAMP:{}", format_utc(now)),
+        format!("DTSTART:{}", format_utc(utc(starts_at, club_utc_offset))),
+        format!("DTEND:{}", format_utc(utc(ends_at, club_utc_offset))),
+        // Later versions of the event have higher sequences
+        format!("SEQUENCE:{}", now.timestamp()),
+        format!("ORGANIZER:mailto:{organizer}"),
+        format!("ATTENDEE:mailto:{attendee}"),
+        format!("SUMMARY:{}", escape_text(summary)),
+        "LOCATION:Beach Garden SXM".to_string(),
+        format!("STATUS:{status}"),
+        "END:VEVENT".to_string(),
+        "END:VCALENDAR".to_string(),
+    ]
+    .iter()
+    .map(|line| fold(line))
+    .collect()
+}
+
+pub fn attachment(calendar: String, method: Method) -> Attachment {
+    Attachment {
+        filename: "reservation.ics".to_string(),
+        content_type: format!("text/calendar; charset=UTF-8; method={}", method.as_str()),
+        content: calendar,
+    }
+}
@@ -0,0 +1,331 @@
+//! Application configuration, loaded and validated once at startup.
+//!
+//! Values are read from the config file pointed to by `CONFIG_FILE`, if any, then from the
+//! environment (including `.env`), the environment taking precedence. The config file is a
+//! flat TOML table whose keys are the environment variable names, in upper or lower case.
+
+use chrono::{FixedOffset, NaiveDateTime, Utc};
+use dotenvy::dotenv;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub api_ip: String,
+    pub api_port: u16,
+    /// Public URL of the frontend, used for CORS and in links sent by email.
+    pub frontend_url: String,
+    /// Whether pending migrations are applied when the backend starts.
+    pub run_migrations: bool,
+    /// Whether the periodic jobs are run by this instance.
+    pub scheduler_enabled: bool,
+    /// Offset of the club's local time from UTC, in which opening hours are expressed.
+    pub club_utc_offset: FixedOffset,
+    /// Time a waiting member has to claim a freed court before it is offered to the next one.
+    pub waitlist_claim_minutes: i64,
+    /// Time before the start of a reservation at which its member is reminded of it.
+    pub reminder_lead_minutes: i64,
+    pub postgres: PostgresConfig,
+    pub jwt: JwtConfig,
+    pub mail_transport: MailTransport,
+    pub smtp: SmtpConfig,
+    pub sms_transport: SmsTransport,
+}
+
+/// Where emails are delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MailTransport {
+    Smtp,
+    /// Written as `.eml` files to a directory, for local development.
+    File(PathBuf),
+    /// Kept in memory, for tests.
+    Memory,
+}
+
+/// Where text messages are delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmsTransport {
+    /// Text messages are not sent.
+    Disabled,
+    /// Written to the logs, for local development.
+    Log,
+    /// Kept in memory, for tests.
+    Memory,
+}
+
+#[derive(Debug, Clone)]
+pub struct PostgresConfig {
+    pub user: String,
+    pub password: String,
+    pub db: String,
+    pub host: String,
+    pub port: u16,
+    /// CA bundle of the database provider, used to verify the server certificate.
+    pub ca_bundle: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    pub secret: String,
+    pub access_token_ttl_minutes: i64,
+    pub refresh_token_ttl_days: i32,
+}
+
+/// Only the sender is required unless emails are sent over SMTP.
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub server: String,
+    pub user: String,
+    pub password: String,
+    pub sender: String,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("could not read config file {path}: {source}")]
+    ReadFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid config file {path}: {reason}")]
+    ParseFile { path: PathBuf, reason: String },
+    #[error("{}", describe_invalid(.missing, .invalid))]
+    Invalid {
+        missing: Vec<String>,
+        invalid: Vec<String>,
+    },
+}
+
+fn describe_invalid(missing: &[String], invalid: &[String]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if !missing.is_empty() {
+        parts.push(format!(
+            "missing configuration keys: {}",
+            missing.join(", ")
+        ));
+    }
+    if !invalid.is_empty() {
+        parts.push(format!(
+            "invalid configuration values: {}",
+            invalid.join(", ")
+        ));
+    }
+    parts.join("; ")
+}
+
+impl Config {
+    /// Current local time of the club.
+    pub fn club_now(&self) -> NaiveDateTime {
+        Utc::now()
+            .with_timezone(&self.club_utc_offset)
+            .naive_local()
+    }
+
+    /// Loads the configuration from `CONFIG_FILE`, `.env` and the environment.
+    pub fn load() -> Result<Config, ConfigError> {
+        dotenv().ok();
+
+        let mut values: HashMap<String, String> = HashMap::new();
+        if let Ok(path) = env::var("CONFIG_FILE")
+            && !path.is_empty()
+        {
+            values.extend(read_config_file(Path::new(&path))?);
+        }
+        values.extend(env::vars());
+
+        Config::from_values(values)
+    }
+
+    /// Builds the configuration from key/value pairs, reporting every missing or invalid key at once.
+    pub fn from_values<K, V>(
+        values: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<Config, ConfigError>
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let mut source = Source::new(values);
+
+        #[cfg(feature = "local")]
+        let frontend_url: String = format!(
+            "http://{}:{}",
+            source.or("FRONTEND_IP", "localhost"),
+            source.parsed_or::<u16>("FRONTEND_PORT", 3000)
+        );
+
+        #[cfg(not(feature = "local"))]
+        let frontend_url: String = source.required("CUSTOM_DOMAIN_URL");
+
+        let mail_transport: MailTransport = match source.or("MAIL_TRANSPORT", "smtp").as_str() {
+            "smtp" => MailTransport::Smtp,
+            "file" => MailTransport::File(PathBuf::from(source.or("MAIL_SPOOL_DIR", "mail"))),
+            "memory" => MailTransport::Memory,
+            other => {
+                source.reject("MAIL_TRANSPORT", "expected smtp, file or memory", other);
+                MailTransport::Smtp
+            }
+        };
+        let sms_transport: SmsTransport = match source.or("SMS_TRANSPORT", "disabled").as_str() {
+            "disabled" => SmsTransport::Disabled,
+            "log" => SmsTransport::Log,
+            "memory" => SmsTransport::Memory,
+            other => {
+                source.reject("SMS_TRANSPORT", "expected disabled, log or memory", other);
+                SmsTransport::Disabled
+            }
+        };
+        let smtp_required: bool = mail_transport == MailTransport::Smtp;
+
+        let config = Config {
+            api_ip: source.or("API_IP", "127.0.0.1"),
+            api_port: source.parsed_or("API_PORT", 8080),
+            frontend_url,
+            run_migrations: source.parsed_or("RUN_MIGRATIONS", true),
+            scheduler_enabled: source.parsed_or("SCHEDULER_ENABLED", true),
+            // Sint Maarten has no daylight saving time
+            club_utc_offset: source.parsed_or(
+                "CLUB_UTC_OFFSET",
+                FixedOffset::west_opt(4 * 3600).expect("Invalid UTC offset."),
+            ),
+            waitlist_claim_minutes: source.parsed_or("WAITLIST_CLAIM_MINUTES", 30),
+            reminder_lead_minutes: source.parsed_or("REMINDER_LEAD_MINUTES", 120),
+            postgres: PostgresConfig {
+                user: source.required("POSTGRES_USER"),
+                password: source.required("POSTGRES_PASSWORD"),
+                db: source.required("POSTGRES_DB"),
+                host: source.required("POSTGRES_HOST"),
+                port: source.parsed_or("POSTGRES_PORT", 5432),
+                ca_bundle: source.optional("POSTGRES_CA_BUNDLE").map(PathBuf::from),
+            },
+            jwt: JwtConfig {
+                secret: source.required("JWT_SECRET"),
+                access_token_ttl_minutes: source.parsed_or("ACCESS_TOKEN_TTL_MINUTES", 15),
+                refresh_token_ttl_days: source.parsed_or("REFRESH_TOKEN_TTL_DAYS", 30),
+            },
+            mail_transport,
+            smtp: SmtpConfig {
+                server: source.required_if(smtp_required, "SMTP_SERVER"),
+                user: source.required_if(smtp_required, "SMTP_USER"),
+                password: source.required_if(smtp_required, "SMTP_PASSWORD"),
+                sender: source.required("SMTP_SENDER"),
+            },
+            sms_transport,
+        };
+
+        source.finish()?;
+        Ok(config)
+    }
+}
+
+/// Raw configuration values, collecting problems instead of stopping at the first one.
+struct Source {
+    values: HashMap<String, String>,
+    missing: Vec<String>,
+    invalid: Vec<String>,
+}
+
+impl Source {
+    fn new<K: Into<String>, V: Into<String>>(values: impl IntoIterator<Item = (K, V)>) -> Self {
+        Self {
+            values: values
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+            missing: Vec::new(),
+            invalid: Vec::new(),
+        }
+    }
+
+    fn optional(&self, key: &str) -> Option<String> {
+        self.values
+            .get(key)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        self.optional(key).unwrap_or_else(|| {
+            self.missing.push(key.to_string());
+            String::new()
+        })
+    }
+
+    fn required_if(&mut self, required: bool, key: &str) -> String {
+        if required {
+            self.required(key)
+        } else {
+            self.or(key, "")
+        }
+    }
+
+    fn reject(&mut self, key: &str, reason: &str, value: &str) {
+        self.invalid.push(format!("{key} ({reason}, got {value})"));
+    }
+
+    fn or(&self, key: &str, default: &str) -> String {
+        self.optional(key).unwrap_or_else(|| default.to_string())
+    }
+
+    fn parsed_or<T>(&mut self, key: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.optional(key) {
+            None => default,
+            Some(value) => value.parse().unwrap_or_else(|e| {
+                self.invalid.push(format!("{key} ({e})"));
+                default
+            }),
+        }
+    }
+
+    fn finish(self) -> Result<(), ConfigError> {
+        if self.missing.is_empty() && self.invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid {
+                missing: self.missing,
+                invalid: self.invalid,
+            })
+        }
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<HashMap<String, String>, ConfigError> {
+    let content = std::fs::read_to_string(path).map_err(|source| ConfigError::ReadFile {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let table: toml::Table =
+        content
+            .parse()
+            .map_err(|e: toml::de::Error| ConfigError::ParseFile {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            })?;
+
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Float(value) => value.to_string(),
+                toml::Value::Boolean(value) => value.to_string(),
+                _ => {
+                    return Err(ConfigError::ParseFile {
+                        path: path.to_path_buf(),
+                        reason: format!("{key} must be a string, a number or a boolean"),
+                    });
+                }
+            };
+            Ok((key.to_uppercase(), value))
+        })
+        .collect()
+}
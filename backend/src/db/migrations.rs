@@ -0,0 +1,233 @@
+//! Versioned schema migrations, embedded in the binary.
+//!
+//! Migrations are applied in order, each in its own transaction, and recorded in the
+//! `schema_migrations` table along with the checksum of their SQL. A migration must never be
+//! edited once released: schema changes go in a new file in `db/migrations`, registered in
+//! [`MIGRATIONS`].
+
+use crate::utils::sha256_hex;
+use std::collections::HashMap;
+use thiserror::Error;
+use tokio_postgres::{Client, Row};
+
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+impl Migration {
+    pub fn checksum(&self) -> String {
+        sha256_hex(self.sql.as_bytes())
+    }
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../../db/migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "job_run",
+        sql: include_str!("../../db/migrations/0002_job_run.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "court",
+        sql: include_str!("../../db/migrations/0003_court.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "opening_hours",
+        sql: include_str!("../../db/migrations/0004_opening_hours.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "reservation_duration",
+        sql: include_str!("../../db/migrations/0005_reservation_duration.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "reservation_series",
+        sql: include_str!("../../db/migrations/0006_reservation_series.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "waitlist",
+        sql: include_str!("../../db/migrations/0007_waitlist.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "booking_rules",
+        sql: include_str!("../../db/migrations/0008_booking_rules.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "reservation_cancellation",
+        sql: include_str!("../../db/migrations/0009_reservation_cancellation.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "check_in",
+        sql: include_str!("../../db/migrations/0010_check_in.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "court_block",
+        sql: include_str!("../../db/migrations/0011_court_block.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "reservation_participant",
+        sql: include_str!("../../db/migrations/0012_reservation_participant.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "email_outbox",
+        sql: include_str!("../../db/migrations/0013_email_outbox.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "email_templates",
+        sql: include_str!("../../db/migrations/0014_email_templates.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "email_attachment",
+        sql: include_str!("../../db/migrations/0015_email_attachment.sql"),
+    },
+    Migration {
+        version: 16,
+        name: "reservation_reminder",
+        sql: include_str!("../../db/migrations/0016_reservation_reminder.sql"),
+    },
+    Migration {
+        version: 17,
+        name: "session_previous_token",
+        sql: include_str!("../../db/migrations/0017_session_previous_token.sql"),
+    },
+    Migration {
+        version: 18,
+        name: "login_attempt_unknown_ip",
+        sql: include_str!("../../db/migrations/0018_login_attempt_unknown_ip.sql"),
+    },
+    Migration {
+        version: 19,
+        name: "sms_reminders_opt_in",
+        sql: include_str!("../../db/migrations/0019_sms_reminders_opt_in.sql"),
+    },
+    Migration {
+        version: 20,
+        name: "email_outbox_redaction",
+        sql: include_str!("../../db/migrations/0020_email_outbox_redaction.sql"),
+    },
+];
+
+/// Arbitrary key of the advisory lock preventing concurrent migration runs.
+const MIGRATION_LOCK_KEY: i64 = 0x6265_6163_685f_6d67;
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("database error: {0}")]
+    Db(#[from] tokio_postgres::Error),
+    #[error("migration {version} ({name}) has been modified since it was applied")]
+    ChecksumMismatch { version: i32, name: &'static str },
+    #[error("migration {version} ({name}) failed: {source}")]
+    Failed {
+        version: i32,
+        name: &'static str,
+        source: tokio_postgres::Error,
+    },
+}
+
+/// Applies the pending migrations and returns their versions.
+pub async fn run(client: &mut Client) -> Result<Vec<i32>, MigrationError> {
+    // Several instances may start at the same time: only one of them migrates
+    client
+        .execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK_KEY])
+        .await?;
+
+    let result = apply_pending(client).await;
+
+    client
+        .execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK_KEY])
+        .await?;
+
+    result
+}
+
+async fn apply_pending(client: &mut Client) -> Result<Vec<i32>, MigrationError> {
+    client
+        .batch_execute(
+            "
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name VARCHAR(255) NOT NULL,
+                checksum CHAR(64) NOT NULL,
+                applied_at TIMESTAMP NOT NULL DEFAULT NOW()
+            )
+            ",
+        )
+        .await?;
+
+    let rows: Vec<Row> = client
+        .query("SELECT version, checksum FROM schema_migrations", &[])
+        .await?;
+    let mut applied: HashMap<i32, String> = rows
+        .into_iter()
+        .map(|row| -> Result<(i32, String), tokio_postgres::Error> {
+            Ok((row.try_get("version")?, row.try_get("checksum")?))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut newly_applied: Vec<i32> = Vec::new();
+    for migration in MIGRATIONS {
+        let checksum: String = migration.checksum();
+
+        if let Some(applied_checksum) = applied.remove(&migration.version) {
+            if applied_checksum != checksum {
+                return Err(MigrationError::ChecksumMismatch {
+                    version: migration.version,
+                    name: migration.name,
+                });
+            }
+            continue;
+        }
+
+        tracing::info!(
+            "Applying migration {} ({})",
+            migration.version,
+            migration.name
+        );
+
+        let transaction = client.transaction().await?;
+        transaction
+            .batch_execute(migration.sql)
+            .await
+            .map_err(|source| MigrationError::Failed {
+                version: migration.version,
+                name: migration.name,
+                source,
+            })?;
+        transaction
+            .execute(
+                "INSERT INTO schema_migrations (version, name, checksum) VALUES ($1, $2, $3)",
+                &[&migration.version, &migration.name, &checksum],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        newly_applied.push(migration.version);
+    }
+
+    for version in applied.keys() {
+        tracing::warn!(
+            "Database has migration {} which is unknown to this version of the backend",
+            version
+        );
+    }
+
+    Ok(newly_applied)
+}
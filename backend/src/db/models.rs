@@ -1,4 +1,5 @@
-use chrono::{NaiveDate, NaiveDateTime};
+use crate::mail::Attachment;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -11,6 +12,64 @@ pub struct Member {
     pub first_name: Option<String>,
     pub last_name: Option<String>,
     pub is_admin: bool,
+    /// Cancellations made too close to the start of the reservation.
+    #[serde(default)]
+    pub late_cancellations: i32,
+    /// Reservations ended without a check-in.
+    #[serde(default)]
+    pub no_shows: i32,
+    /// Last day the member cannot book, after repeated no-shows.
+    #[serde(default)]
+    pub suspended_until: Option<NaiveDate>,
+    /// Language of the emails sent to the member.
+    #[serde(default)]
+    pub locale: Locale,
+    /// Whether the member is reminded of reservations by email.
+    #[serde(default = "reminders_enabled")]
+    pub email_reminders: bool,
+    /// Whether the member is reminded of reservations by text message.
+    #[serde(default = "reminders_enabled")]
+    pub sms_reminders: bool,
+}
+
+fn reminders_enabled() -> bool {
+    true
+}
+
+/// Preferences changed along with the profile of a member, those `None` being left unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemberPreferences {
+    pub locale: Option<Locale>,
+    pub email_reminders: Option<bool>,
+    pub sms_reminders: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    Fr,
+    En,
+    Nl,
+}
+
+impl Locale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::Fr => "fr",
+            Locale::En => "en",
+            Locale::Nl => "nl",
+        }
+    }
+
+    pub fn from_db(locale: &str) -> Option<Self> {
+        match locale {
+            "fr" => Some(Locale::Fr),
+            "en" => Some(Locale::En),
+            "nl" => Some(Locale::Nl),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -19,18 +78,196 @@ pub struct Reservation {
     pub member_id: String,
     pub court_number: i16,
     pub reservation_date: NaiveDate,
-    pub reservation_time: i16,
+    pub start_time: NaiveTime,
+    /// One of 30, 60, 90 or 120 minutes.
+    pub duration_minutes: i16,
+    /// Series the reservation is an occurrence of, if any.
+    pub series_id: Option<String>,
+    #[serde(default)]
+    pub cancellation: Option<Cancellation>,
+    #[serde(default)]
+    pub check_in: Option<CheckIn>,
+    /// Whether the reservation ended without a check-in.
+    #[serde(default)]
+    pub is_no_show: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CheckIn {
+    pub checked_in_at: DateTime<Utc>,
+    /// The member, or the administrator who checked the member in.
+    pub checked_in_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Cancellation {
+    pub cancelled_at: DateTime<Utc>,
+    /// Member who cancelled, possibly an administrator on behalf of the member.
+    pub cancelled_by: Option<String>,
+    pub reason: Option<String>,
+    /// Whether it was cancelled less than the late cancellation delay before the start.
+    pub is_late: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ReservationWithNames {
     pub id: String,
     pub member_id: String,
     pub court_number: i16,
     pub reservation_date: NaiveDate,
-    pub reservation_time: i16,
+    pub start_time: NaiveTime,
+    pub duration_minutes: i16,
+    pub end_time: NaiveTime,
+    pub series_id: Option<String>,
     pub member_first_name: String,
     pub member_last_name: String,
+    #[serde(default)]
+    pub participants: Vec<Participant>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParticipantStatus {
+    Invited,
+    Accepted,
+    Declined,
+}
+
+impl ParticipantStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParticipantStatus::Invited => "invited",
+            ParticipantStatus::Accepted => "accepted",
+            ParticipantStatus::Declined => "declined",
+        }
+    }
+
+    pub fn from_db(status: &str) -> Option<Self> {
+        match status {
+            "invited" => Some(ParticipantStatus::Invited),
+            "accepted" => Some(ParticipantStatus::Accepted),
+            "declined" => Some(ParticipantStatus::Declined),
+            _ => None,
+        }
+    }
+}
+
+/// Member or guest playing on the reservation of another member.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Participant {
+    pub id: i32,
+    pub reservation_id: String,
+    /// Exclusive with `guest_name`.
+    pub member_id: Option<String>,
+    pub guest_name: Option<String>,
+    pub status: ParticipantStatus,
+    pub member_first_name: Option<String>,
+    pub member_last_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReservationSeries {
+    pub id: String,
+    pub member_id: String,
+    pub court_number: i16,
+    pub start_time: NaiveTime,
+    pub duration_minutes: i16,
+    pub first_date: NaiveDate,
+    /// 1 for a weekly series, 2 for a biweekly one.
+    pub interval_weeks: i16,
+    /// Either the last possible date or the number of occurrences is set.
+    pub until_date: Option<NaiveDate>,
+    pub occurrence_count: Option<i16>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WaitlistEntry {
+    pub id: i32,
+    pub member_id: String,
+    /// Requested court, any court if `None`.
+    pub court_number: Option<i16>,
+    pub reservation_date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub duration_minutes: i16,
+    /// Whether the member is booked as soon as a court is freed, instead of being sent a claim
+    /// link.
+    pub auto_book: bool,
+    pub created_at: DateTime<Utc>,
+    /// Only sent by email, so that the claim link cannot be guessed.
+    #[serde(skip)]
+    pub claim_token: Option<Uuid>,
+    /// Court offered to the member by the pending claim.
+    pub claim_court_number: Option<i16>,
+    pub claim_expires_at: Option<DateTime<Utc>>,
+}
+
+/// Quotas of the members, a rule being disabled when `None`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct BookingRules {
+    pub max_per_day: Option<i16>,
+    /// Per ISO week, from Monday to Sunday.
+    pub max_per_week: Option<i16>,
+    pub max_days_in_advance: Option<i16>,
+    pub min_notice_minutes: Option<i32>,
+    pub peak_starts_at: Option<NaiveTime>,
+    pub peak_ends_at: Option<NaiveTime>,
+    /// Per ISO week, counting the reservations overlapping the peak hours.
+    pub max_peak_per_week: Option<i16>,
+    /// Minimum delay before the start for members to cancel themselves.
+    pub cancellation_cutoff_minutes: Option<i32>,
+    /// Cancellations closer to the start are counted as late.
+    pub late_cancellation_minutes: Option<i32>,
+    /// Members can check in from this delay before the start.
+    pub check_in_opens_minutes: Option<i32>,
+    /// Members can check in until this delay after the start.
+    pub check_in_closes_minutes: Option<i32>,
+    /// Number of no-shows after which the bookings of a member are suspended.
+    pub no_show_suspension_threshold: Option<i16>,
+    pub no_show_suspension_days: Option<i16>,
+    /// Whether the reservations members are invited to count towards their own quotas.
+    #[serde(default)]
+    pub count_participants: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Court {
+    pub number: i16,
+    pub name: String,
+    pub surface: String,
+    pub is_lit: bool,
+    pub is_active: bool,
+    pub display_order: i16,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CourtBlock {
+    pub id: i32,
+    /// Blocked courts, every court if `None`.
+    pub court_numbers: Option<Vec<i16>>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub reason: String,
+    /// 1 for a block repeated every week, 2 for every other week.
+    pub interval_weeks: Option<i16>,
+    /// Last possible date of an occurrence, set for repeated blocks only.
+    pub until_date: Option<NaiveDate>,
+    pub created_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OpeningHours {
+    #[serde(default)]
+    pub id: i32,
+    /// Court the hours apply to, every court if `None`.
+    pub court_number: Option<i16>,
+    /// ISO 8601 weekday, Monday being 1.
+    pub weekday: i16,
+    pub opens_at: Option<NaiveTime>,
+    pub closes_at: Option<NaiveTime>,
+    pub slot_minutes: i16,
+    pub valid_from: Option<NaiveDate>,
+    pub valid_until: Option<NaiveDate>,
+    pub is_closed: bool,
 }
 
 #[derive(Debug)]
@@ -39,3 +276,101 @@ pub struct PasswordResetToken {
     pub member_id: String,
     pub expires_at: NaiveDateTime,
 }
+
+#[derive(Debug)]
+pub struct Session {
+    pub id: Uuid,
+    pub member_id: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug)]
+pub struct JobRun {
+    pub id: i64,
+    pub job_name: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub succeeded: Option<bool>,
+    pub rows_affected: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutboxStatus {
+    Pending,
+    Sent,
+    /// Given up after too many failed attempts.
+    Failed,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "pending",
+            OutboxStatus::Sent => "sent",
+            OutboxStatus::Failed => "failed",
+        }
+    }
+
+    pub fn from_db(status: &str) -> Option<Self> {
+        match status {
+            "pending" => Some(OutboxStatus::Pending),
+            "sent" => Some(OutboxStatus::Sent),
+            "failed" => Some(OutboxStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Email queued for delivery, along with the outcome of its delivery attempts.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OutboxEmail {
+    pub id: i64,
+    pub recipient: String,
+    pub subject: String,
+    pub html_body: String,
+    pub text_body: String,
+    pub attachment: Option<Attachment>,
+    pub status: OutboxStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// Queued email as listed to admins, without its content which may hold reset or claim links.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OutboxEmailSummary {
+    pub id: i64,
+    pub recipient: String,
+    pub subject: String,
+    pub attachment_filename: Option<String>,
+    pub status: OutboxStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// Channel a member is reminded of a reservation on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReminderChannel {
+    Email,
+    Sms,
+}
+
+impl ReminderChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReminderChannel::Email => "email",
+            ReminderChannel::Sms => "sms",
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::db::models::BookingRules;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Client, Error, Row, Statement};
+
+pub async fn get_booking_rules(client: &impl GenericClient) -> Result<BookingRules, Error> {
+    let stmt: Statement = client.prepare("SELECT * FROM booking_rules").await?;
+
+    let row: Option<Row> = client.query_opt(&stmt, &[]).await?;
+    let Some(row) = row else {
+        return Ok(BookingRules::default());
+    };
+
+    Ok(BookingRules {
+        max_per_day: row.try_get("max_per_day")?,
+        max_per_week: row.try_get("max_per_week")?,
+        max_days_in_advance: row.try_get("max_days_in_advance")?,
+        min_notice_minutes: row.try_get("min_notice_minutes")?,
+        peak_starts_at: row.try_get("peak_starts_at")?,
+        peak_ends_at: row.try_get("peak_ends_at")?,
+        max_peak_per_week: row.try_get("max_peak_per_week")?,
+        cancellation_cutoff_minutes: row.try_get("cancellation_cutoff_minutes")?,
+        late_cancellation_minutes: row.try_get("late_cancellation_minutes")?,
+        check_in_opens_minutes: row.try_get("check_in_opens_minutes")?,
+        check_in_closes_minutes: row.try_get("check_in_closes_minutes")?,
+        no_show_suspension_threshold: row.try_get("no_show_suspension_threshold")?,
+        no_show_suspension_days: row.try_get("no_show_suspension_days")?,
+        count_participants: row.try_get("count_participants")?,
+    })
+}
+
+pub async fn update_booking_rules(client: &Client, rules: &BookingRules) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "INSERT INTO booking_rules (id, max_per_day, max_per_week, max_days_in_advance, min_notice_minutes, peak_starts_at, peak_ends_at, max_peak_per_week, cancellation_cutoff_minutes, late_cancellation_minutes, check_in_opens_minutes, check_in_closes_minutes, no_show_suspension_threshold, no_show_suspension_days, count_participants)
+             VALUES (TRUE, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+             ON CONFLICT (id) DO UPDATE SET max_per_day=$1, max_per_week=$2, max_days_in_advance=$3, min_notice_minutes=$4, peak_starts_at=$5, peak_ends_at=$6, max_peak_per_week=$7, cancellation_cutoff_minutes=$8, late_cancellation_minutes=$9, check_in_opens_minutes=$10, check_in_closes_minutes=$11, no_show_suspension_threshold=$12, no_show_suspension_days=$13, count_participants=$14",
+        )
+        .await?;
+
+    client
+        .execute(
+            &stmt,
+            &[
+                &rules.max_per_day,
+                &rules.max_per_week,
+                &rules.max_days_in_advance,
+                &rules.min_notice_minutes,
+                &rules.peak_starts_at,
+                &rules.peak_ends_at,
+                &rules.max_peak_per_week,
+                &rules.cancellation_cutoff_minutes,
+                &rules.late_cancellation_minutes,
+                &rules.check_in_opens_minutes,
+                &rules.check_in_closes_minutes,
+                &rules.no_show_suspension_threshold,
+                &rules.no_show_suspension_days,
+                &rules.count_participants,
+            ],
+        )
+        .await
+}
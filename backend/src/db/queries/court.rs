@@ -0,0 +1,83 @@
+use crate::db::models::Court;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Client, Error, Row, Statement};
+
+fn court_from_row(row: &Row) -> Result<Court, Error> {
+    Ok(Court {
+        number: row.try_get("number")?,
+        name: row.try_get("name")?,
+        surface: row.try_get("surface")?,
+        is_lit: row.try_get("is_lit")?,
+        is_active: row.try_get("is_active")?,
+        display_order: row.try_get("display_order")?,
+    })
+}
+
+pub async fn add_court(client: &Client, court: &Court) -> Result<i16, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "INSERT INTO court (number, name, surface, is_lit, is_active, display_order) VALUES ($1, $2, $3, $4, $5, $6) RETURNING number",
+        )
+        .await?;
+
+    let row: Row = client
+        .query_one(
+            &stmt,
+            &[
+                &court.number,
+                &court.name,
+                &court.surface,
+                &court.is_lit,
+                &court.is_active,
+                &court.display_order,
+            ],
+        )
+        .await?;
+
+    let number: i16 = row.try_get("number")?;
+    Ok(number)
+}
+
+pub async fn get_court(client: &impl GenericClient, number: i16) -> Result<Option<Court>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT * FROM court WHERE number=$1")
+        .await?;
+
+    let row: Option<Row> = client.query_opt(&stmt, &[&number]).await?;
+    row.as_ref().map(court_from_row).transpose()
+}
+
+pub async fn get_all_courts(client: &Client) -> Result<Vec<Court>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT * FROM court ORDER BY display_order, number")
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[]).await?;
+    rows.iter().map(court_from_row).collect()
+}
+
+pub async fn update_court(client: &Client, court: &Court) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE court SET name=$2, surface=$3, is_lit=$4, is_active=$5, display_order=$6 WHERE number=$1",
+        )
+        .await?;
+    client
+        .execute(
+            &stmt,
+            &[
+                &court.number,
+                &court.name,
+                &court.surface,
+                &court.is_lit,
+                &court.is_active,
+                &court.display_order,
+            ],
+        )
+        .await
+}
+
+pub async fn delete_court(client: &Client, number: i16) -> Result<u64, Error> {
+    let stmt: Statement = client.prepare("DELETE FROM court WHERE number=$1").await?;
+    client.execute(&stmt, &[&number]).await
+}
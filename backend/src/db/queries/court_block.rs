@@ -0,0 +1,89 @@
+use crate::db::models::CourtBlock;
+use chrono::NaiveDate;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Client, Error, Row, Statement};
+
+fn court_block_from_row(row: &Row) -> Result<CourtBlock, Error> {
+    Ok(CourtBlock {
+        id: row.try_get("id")?,
+        court_numbers: row.try_get("court_numbers")?,
+        starts_at: row.try_get("starts_at")?,
+        ends_at: row.try_get("ends_at")?,
+        reason: row.try_get("reason")?,
+        interval_weeks: row.try_get("interval_weeks")?,
+        until_date: row.try_get("until_date")?,
+        created_by: row.try_get("created_by")?,
+    })
+}
+
+/// Keeps blocks from being added until the end of the transaction, which books courts.
+pub async fn lock_blocks(client: &impl GenericClient) -> Result<(), Error> {
+    client
+        .batch_execute("LOCK TABLE court_block IN SHARE MODE")
+        .await
+}
+
+pub async fn add_block(client: &impl GenericClient, block: &CourtBlock) -> Result<i32, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "INSERT INTO court_block (court_numbers, starts_at, ends_at, reason, interval_weeks, until_date, created_by) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+        )
+        .await?;
+
+    let row: Row = client
+        .query_one(
+            &stmt,
+            &[
+                &block.court_numbers,
+                &block.starts_at,
+                &block.ends_at,
+                &block.reason,
+                &block.interval_weeks,
+                &block.until_date,
+                &block.created_by,
+            ],
+        )
+        .await?;
+
+    let id: i32 = row.try_get("id")?;
+    Ok(id)
+}
+
+/// Blocks which may cover part of the days between `from` and `to` included, the occurrences
+/// of repeated blocks being checked by [`crate::planning`].
+pub async fn get_blocks_between(
+    client: &impl GenericClient,
+    from: &NaiveDate,
+    to: &NaiveDate,
+) -> Result<Vec<CourtBlock>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "SELECT * FROM court_block
+             WHERE starts_at < $2::DATE + 1 AND last_ends_at > $1::DATE
+             ORDER BY starts_at, id",
+        )
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[from, to]).await?;
+    rows.iter().map(court_block_from_row).collect()
+}
+
+/// Blocks not over by `from`.
+pub async fn get_upcoming_blocks(
+    client: &Client,
+    from: &NaiveDate,
+) -> Result<Vec<CourtBlock>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT * FROM court_block WHERE last_ends_at > $1::DATE ORDER BY starts_at, id")
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[from]).await?;
+    rows.iter().map(court_block_from_row).collect()
+}
+
+pub async fn delete_block(client: &Client, id: i32) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM court_block WHERE id=$1")
+        .await?;
+    client.execute(&stmt, &[&id]).await
+}
@@ -0,0 +1,175 @@
+use crate::db::models::{OutboxEmail, OutboxEmailSummary, OutboxStatus};
+use crate::mail::{Attachment, Email};
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Client, Error, Row, Statement};
+
+/// Time during which claimed emails are not claimed again, in case the delivering instance
+/// stops before recording the outcome.
+const CLAIM_LEASE: &str = "10 minutes";
+
+fn outbox_email_from_row(row: &Row) -> Result<OutboxEmail, Error> {
+    let status: String = row.try_get("status")?;
+    let attachment: Option<Attachment> = match row.try_get("attachment_filename")? {
+        Some(filename) => Some(Attachment {
+            filename,
+            content_type: row.try_get("attachment_content_type")?,
+            content: row.try_get("attachment_content")?,
+        }),
+        None => None,
+    };
+    Ok(OutboxEmail {
+        id: row.try_get("id")?,
+        recipient: row.try_get("recipient")?,
+        subject: row.try_get("subject")?,
+        html_body: row.try_get("html_body")?,
+        text_body: row.try_get("text_body")?,
+        attachment,
+        status: OutboxStatus::from_db(&status).unwrap_or(OutboxStatus::Pending),
+        attempts: row.try_get("attempts")?,
+        next_attempt_at: row.try_get("next_attempt_at")?,
+        last_error: row.try_get("last_error")?,
+        created_at: row.try_get("created_at")?,
+        sent_at: row.try_get("sent_at")?,
+    })
+}
+
+/// Queues `email`, to be delivered once the transaction of `client` is committed. Its bodies
+/// are cleared once it is sent if `redact_when_sent`.
+pub async fn enqueue(
+    client: &impl GenericClient,
+    email: &Email,
+    redact_when_sent: bool,
+) -> Result<i64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "INSERT INTO email_outbox (recipient, subject, html_body, text_body, attachment_filename, attachment_content_type, attachment_content, redact_when_sent) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+        )
+        .await?;
+
+    let row: Row = client
+        .query_one(
+            &stmt,
+            &[
+                &email.to,
+                &email.subject,
+                &email.html_body,
+                &email.text_body,
+                &email.attachment.as_ref().map(|a| &a.filename),
+                &email.attachment.as_ref().map(|a| &a.content_type),
+                &email.attachment.as_ref().map(|a| &a.content),
+                &redact_when_sent,
+            ],
+        )
+        .await?;
+
+    let id: i64 = row.try_get("id")?;
+    Ok(id)
+}
+
+/// Claims at most `limit` pending emails due for an attempt, counting the attempt. Emails
+/// claimed by another instance are skipped.
+pub async fn claim_due_emails(client: &Client, limit: i64) -> Result<Vec<OutboxEmail>, Error> {
+    let stmt: Statement = client
+        .prepare(&format!(
+            "UPDATE email_outbox SET attempts=attempts + 1, next_attempt_at=NOW() + INTERVAL '{CLAIM_LEASE}'
+             WHERE id IN (
+                SELECT id FROM email_outbox
+                WHERE status='pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+             )
+             RETURNING *"
+        ))
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[&limit]).await?;
+    rows.iter().map(outbox_email_from_row).collect()
+}
+
+pub async fn mark_sent(client: &Client, id: i64) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE email_outbox SET status='sent', sent_at=NOW(), last_error=NULL,
+                html_body=CASE WHEN redact_when_sent THEN '' ELSE html_body END,
+                text_body=CASE WHEN redact_when_sent THEN '' ELSE text_body END
+             WHERE id=$1",
+        )
+        .await?;
+    client.execute(&stmt, &[&id]).await
+}
+
+/// Records a failed attempt, the email being retried after `retry_in_seconds` or given up if
+/// `None`.
+pub async fn mark_attempt_failed(
+    client: &Client,
+    id: i64,
+    error: &str,
+    retry_in_seconds: Option<i64>,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE email_outbox SET last_error=$2,
+                status=CASE WHEN $3::BIGINT IS NULL THEN 'failed' ELSE 'pending' END,
+                next_attempt_at=NOW() + COALESCE($3::BIGINT, 0) * INTERVAL '1 second'
+             WHERE id=$1",
+        )
+        .await?;
+    client
+        .execute(&stmt, &[&id, &error, &retry_in_seconds])
+        .await
+}
+
+/// Most recent emails first, optionally of a single status.
+pub async fn get_emails(
+    client: &Client,
+    status: Option<OutboxStatus>,
+    limit: i64,
+) -> Result<Vec<OutboxEmailSummary>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "SELECT id, recipient, subject, attachment_filename, status, attempts, next_attempt_at, last_error, created_at, sent_at
+             FROM email_outbox WHERE $1::TEXT IS NULL OR status=$1 ORDER BY created_at DESC, id DESC LIMIT $2",
+        )
+        .await?;
+
+    let status: Option<&str> = status.as_ref().map(OutboxStatus::as_str);
+    let rows: Vec<Row> = client.query(&stmt, &[&status, &limit]).await?;
+    rows.iter()
+        .map(|row| {
+            let status: String = row.try_get("status")?;
+            Ok(OutboxEmailSummary {
+                id: row.try_get("id")?,
+                recipient: row.try_get("recipient")?,
+                subject: row.try_get("subject")?,
+                attachment_filename: row.try_get("attachment_filename")?,
+                status: OutboxStatus::from_db(&status).unwrap_or(OutboxStatus::Pending),
+                attempts: row.try_get("attempts")?,
+                next_attempt_at: row.try_get("next_attempt_at")?,
+                last_error: row.try_get("last_error")?,
+                created_at: row.try_get("created_at")?,
+                sent_at: row.try_get("sent_at")?,
+            })
+        })
+        .collect()
+}
+
+/// Queues a failed email again, with a fresh count of attempts.
+pub async fn retry_email(client: &Client, id: i64) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE email_outbox SET status='pending', attempts=0, next_attempt_at=NOW() WHERE id=$1 AND status='failed'",
+        )
+        .await?;
+    client.execute(&stmt, &[&id]).await
+}
+
+/// Deletes the emails sent more than `retention_days` ago, whose content is no longer needed.
+pub async fn delete_sent_emails(client: &Client, retention_days: i32) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "DELETE FROM email_outbox WHERE status='sent' AND sent_at < NOW() - make_interval(days => $1)",
+        )
+        .await?;
+    client.execute(&stmt, &[&retention_days]).await
+}
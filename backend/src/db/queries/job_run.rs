@@ -0,0 +1,60 @@
+use crate::db::models::JobRun;
+use tokio_postgres::{Client, Error, Row, Statement};
+
+pub async fn start_job_run(client: &Client, job_name: &str) -> Result<i64, Error> {
+    let stmt: Statement = client
+        .prepare("INSERT INTO job_run (job_name) VALUES ($1) RETURNING id")
+        .await?;
+
+    let row: Row = client.query_one(&stmt, &[&job_name]).await?;
+
+    let id: i64 = row.try_get("id")?;
+    Ok(id)
+}
+
+pub async fn finish_job_run(
+    client: &Client,
+    id: i64,
+    rows_affected: Option<i64>,
+    error: Option<&str>,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE job_run SET finished_at=NOW(), succeeded=($3::TEXT IS NULL), rows_affected=$2, error=$3 WHERE id=$1",
+        )
+        .await?;
+    client.execute(&stmt, &[&id, &rows_affected, &error]).await
+}
+
+pub async fn get_last_job_run(client: &Client, job_name: &str) -> Result<Option<JobRun>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT * FROM job_run WHERE job_name=$1 ORDER BY started_at DESC LIMIT 1")
+        .await?;
+
+    let row: Option<Row> = client.query_opt(&stmt, &[&job_name]).await?;
+
+    row.map(|row| {
+        Ok(JobRun {
+            id: row.try_get("id")?,
+            job_name: row.try_get("job_name")?,
+            started_at: row.try_get("started_at")?,
+            finished_at: row.try_get("finished_at")?,
+            succeeded: row.try_get("succeeded")?,
+            rows_affected: row.try_get("rows_affected")?,
+            error: row.try_get("error")?,
+        })
+    })
+    .transpose()
+}
+
+/// Deletes the runs older than `retention_days`, except the last run of every job which tells
+/// when it is next due.
+pub async fn delete_old_job_runs(client: &Client, retention_days: i32) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "DELETE FROM job_run WHERE started_at < NOW() - make_interval(days => $1)
+             AND id NOT IN (SELECT MAX(id) FROM job_run GROUP BY job_name)",
+        )
+        .await?;
+    client.execute(&stmt, &[&retention_days]).await
+}
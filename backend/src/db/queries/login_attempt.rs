@@ -0,0 +1,148 @@
+use chrono::NaiveDateTime;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Client, Error, Row, Statement};
+
+/// Waits for the other login attempts for a phone to finish, until the end of the transaction.
+pub async fn lock_attempts(client: &impl GenericClient, phone: &str) -> Result<(), Error> {
+    let stmt: Statement = client
+        .prepare("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .await?;
+    client.execute(&stmt, &[&phone]).await?;
+    Ok(())
+}
+
+pub async fn add_login_attempt(
+    client: &impl GenericClient,
+    phone: &str,
+    ip_address: Option<&str>,
+    succeeded: bool,
+) -> Result<u64, Error> {
+    // Stamped when recorded rather than when the login transaction started
+    let stmt: Statement = client
+        .prepare("INSERT INTO login_attempt (phone, ip_address, succeeded, attempted_at) VALUES ($1, $2, $3, clock_timestamp())")
+        .await?;
+
+    client
+        .execute(&stmt, &[&phone, &ip_address, &succeeded])
+        .await
+}
+
+/// Counts the failed attempts for a phone in the last `window_minutes`, since its last successful login.
+pub async fn count_recent_phone_failures(
+    client: &impl GenericClient,
+    phone: &str,
+    window_minutes: i32,
+) -> Result<i64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "
+            SELECT COUNT(*)
+            FROM login_attempt
+            WHERE phone = $1
+              AND NOT succeeded
+              AND attempted_at > NOW() - make_interval(mins => $2)
+              AND attempted_at > COALESCE(
+                (SELECT MAX(attempted_at) FROM login_attempt WHERE phone = $1 AND succeeded),
+                '-infinity'
+              )
+            ",
+        )
+        .await?;
+
+    let row: Row = client.query_one(&stmt, &[&phone, &window_minutes]).await?;
+    row.try_get(0)
+}
+
+/// Returns the milliseconds elapsed since the last failed attempt for a phone, if any.
+pub async fn get_millis_since_last_phone_failure(
+    client: &impl GenericClient,
+    phone: &str,
+) -> Result<Option<i64>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT (EXTRACT(EPOCH FROM NOW() - MAX(attempted_at)) * 1000)::BIGINT FROM login_attempt WHERE phone=$1 AND NOT succeeded")
+        .await?;
+
+    let row: Row = client.query_one(&stmt, &[&phone]).await?;
+    row.try_get(0)
+}
+
+/// Counts the failed attempts coming from an IP address in the last `window_minutes`.
+pub async fn count_recent_ip_failures(
+    client: &impl GenericClient,
+    ip_address: &str,
+    window_minutes: i32,
+) -> Result<i64, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT COUNT(*) FROM login_attempt WHERE ip_address=$1 AND NOT succeeded AND attempted_at > NOW() - make_interval(mins => $2)")
+        .await?;
+
+    let row: Row = client
+        .query_one(&stmt, &[&ip_address, &window_minutes])
+        .await?;
+    row.try_get(0)
+}
+
+/// Returns the number of seconds left in the phone's lockout if it is currently locked.
+pub async fn get_active_lockout(
+    client: &impl GenericClient,
+    phone: &str,
+) -> Result<Option<i64>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT CEIL(EXTRACT(EPOCH FROM locked_until - NOW()))::BIGINT AS remaining FROM login_lockout WHERE phone=$1 AND locked_until > NOW()")
+        .await?;
+
+    let row: Option<Row> = client.query_opt(&stmt, &[&phone]).await?;
+    row.map(|row| row.try_get("remaining")).transpose()
+}
+
+/// Locks a phone out, doubling the lockout duration for each consecutive lockout.
+pub async fn lock_phone(
+    client: &impl GenericClient,
+    phone: &str,
+    base_minutes: i32,
+    max_doublings: i32,
+) -> Result<NaiveDateTime, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "
+            INSERT INTO login_lockout (phone, lockout_count, locked_until)
+            VALUES ($1, 1, NOW() + make_interval(mins => $2))
+            ON CONFLICT (phone) DO UPDATE SET
+              lockout_count = login_lockout.lockout_count + 1,
+              locked_until = NOW() + make_interval(
+                mins => $2 * (2 ^ LEAST(login_lockout.lockout_count, $3))::INTEGER
+              )
+            RETURNING locked_until
+            ",
+        )
+        .await?;
+
+    let row: Row = client
+        .query_one(&stmt, &[&phone, &base_minutes, &max_doublings])
+        .await?;
+    row.try_get("locked_until")
+}
+
+pub async fn delete_lockout(client: &impl GenericClient, phone: &str) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM login_lockout WHERE phone=$1")
+        .await?;
+    client.execute(&stmt, &[&phone]).await
+}
+
+pub async fn delete_failed_attempts(
+    client: &impl GenericClient,
+    phone: &str,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM login_attempt WHERE phone=$1 AND NOT succeeded")
+        .await?;
+    client.execute(&stmt, &[&phone]).await
+}
+
+pub async fn delete_old_attempts(client: &Client) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM login_attempt WHERE attempted_at < NOW() - INTERVAL '1 day'")
+        .await?;
+    client.execute(&stmt, &[]).await
+}
@@ -1,9 +1,11 @@
-use crate::db::models::Member;
+use crate::db::models::{Locale, Member, MemberPreferences};
+use chrono::NaiveDate;
+use deadpool_postgres::GenericClient;
 use tokio_postgres::{Client, Error, Row, Statement};
 
 pub async fn add_member(client: &Client, member: &Member) -> Result<String, Error> {
     let stmt: Statement = client
-        .prepare("INSERT INTO member (id, phone, password, email, first_name, last_name) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id")
+        .prepare("INSERT INTO member (id, phone, password, email, first_name, last_name, locale, email_reminders, sms_reminders) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id")
         .await?;
 
     let row: Row = client
@@ -16,6 +18,9 @@ pub async fn add_member(client: &Client, member: &Member) -> Result<String, Erro
                 &member.email,
                 &member.first_name,
                 &member.last_name,
+                &member.locale.as_str(),
+                &member.email_reminders,
+                &member.sms_reminders,
             ],
         )
         .await?;
@@ -24,7 +29,7 @@ pub async fn add_member(client: &Client, member: &Member) -> Result<String, Erro
     Ok(id)
 }
 
-pub async fn get_member(client: &Client, id: &String) -> Result<Member, Error> {
+pub async fn get_member(client: &impl GenericClient, id: &String) -> Result<Member, Error> {
     let stmt: Statement = client.prepare("SELECT * FROM member WHERE id=$1").await?;
 
     let row: Row = client.query_one(&stmt, &[id]).await?;
@@ -37,10 +42,19 @@ pub async fn get_member(client: &Client, id: &String) -> Result<Member, Error> {
         first_name: row.try_get("first_name")?,
         last_name: row.try_get("last_name")?,
         is_admin: row.try_get("is_admin")?,
+        late_cancellations: row.try_get("late_cancellations")?,
+        no_shows: row.try_get("no_shows")?,
+        suspended_until: row.try_get("suspended_until")?,
+        locale: Locale::from_db(row.try_get("locale")?).unwrap_or_default(),
+        email_reminders: row.try_get("email_reminders")?,
+        sms_reminders: row.try_get("sms_reminders")?,
     })
 }
 
-pub async fn get_member_by_phone(client: &Client, phone: &String) -> Result<Member, Error> {
+pub async fn get_member_by_phone(
+    client: &impl GenericClient,
+    phone: &String,
+) -> Result<Member, Error> {
     let stmt: Statement = client
         .prepare("SELECT * FROM member WHERE phone=$1")
         .await?;
@@ -55,6 +69,12 @@ pub async fn get_member_by_phone(client: &Client, phone: &String) -> Result<Memb
         first_name: row.try_get("first_name")?,
         last_name: row.try_get("last_name")?,
         is_admin: row.try_get("is_admin")?,
+        late_cancellations: row.try_get("late_cancellations")?,
+        no_shows: row.try_get("no_shows")?,
+        suspended_until: row.try_get("suspended_until")?,
+        locale: Locale::from_db(row.try_get("locale")?).unwrap_or_default(),
+        email_reminders: row.try_get("email_reminders")?,
+        sms_reminders: row.try_get("sms_reminders")?,
     })
 }
 
@@ -73,6 +93,12 @@ pub async fn get_member_by_email(client: &Client, email: &String) -> Result<Memb
         first_name: row.try_get("first_name")?,
         last_name: row.try_get("last_name")?,
         is_admin: row.try_get("is_admin")?,
+        late_cancellations: row.try_get("late_cancellations")?,
+        no_shows: row.try_get("no_shows")?,
+        suspended_until: row.try_get("suspended_until")?,
+        locale: Locale::from_db(row.try_get("locale")?).unwrap_or_default(),
+        email_reminders: row.try_get("email_reminders")?,
+        sms_reminders: row.try_get("sms_reminders")?,
     })
 }
 
@@ -106,6 +132,12 @@ pub async fn get_members_paginated(
                 first_name: row.try_get("first_name")?,
                 last_name: row.try_get("last_name")?,
                 is_admin: row.try_get("is_admin")?,
+                late_cancellations: row.try_get("late_cancellations")?,
+                no_shows: row.try_get("no_shows")?,
+                suspended_until: row.try_get("suspended_until")?,
+                locale: Locale::from_db(row.try_get("locale")?).unwrap_or_default(),
+                email_reminders: row.try_get("email_reminders")?,
+                sms_reminders: row.try_get("sms_reminders")?,
             })
         })
         .collect()
@@ -124,14 +156,26 @@ pub async fn get_all_members(client: &Client) -> Result<Vec<Member>, Error> {
                 first_name: row.try_get("first_name")?,
                 last_name: row.try_get("last_name")?,
                 is_admin: row.try_get("is_admin")?,
+                late_cancellations: row.try_get("late_cancellations")?,
+                no_shows: row.try_get("no_shows")?,
+                suspended_until: row.try_get("suspended_until")?,
+                locale: Locale::from_db(row.try_get("locale")?).unwrap_or_default(),
+                email_reminders: row.try_get("email_reminders")?,
+                sms_reminders: row.try_get("sms_reminders")?,
             })
         })
         .collect()
 }
 
-pub async fn update_member(client: &Client, updated_member: &Member) -> Result<u64, Error> {
+pub async fn update_member(
+    client: &Client,
+    updated_member: &Member,
+    preferences: &MemberPreferences,
+) -> Result<u64, Error> {
     let stmt: Statement = client
-        .prepare("UPDATE member SET phone=$1, email=$2, first_name=$3, last_name=$4 WHERE id=$5")
+        .prepare(
+            "UPDATE member SET phone=$1, email=$2, first_name=$3, last_name=$4, locale=COALESCE($6, locale), email_reminders=COALESCE($7, email_reminders), sms_reminders=COALESCE($8, sms_reminders) WHERE id=$5",
+        )
         .await?;
 
     client
@@ -143,6 +187,9 @@ pub async fn update_member(client: &Client, updated_member: &Member) -> Result<u
                 &updated_member.first_name,
                 &updated_member.last_name,
                 &updated_member.id,
+                &preferences.locale.as_ref().map(Locale::as_str),
+                &preferences.email_reminders,
+                &preferences.sms_reminders,
             ],
         )
         .await
@@ -151,8 +198,13 @@ pub async fn update_member(client: &Client, updated_member: &Member) -> Result<u
 pub async fn update_member_with_password(
     client: &Client,
     updated_member: &Member,
+    preferences: &MemberPreferences,
 ) -> Result<u64, Error> {
-    let stmt: Statement = client.prepare("UPDATE member SET phone=$1, password=$2, email=$3, first_name=$4, last_name=$5 WHERE id=$6").await?;
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE member SET phone=$1, password=$2, email=$3, first_name=$4, last_name=$5, locale=COALESCE($7, locale), email_reminders=COALESCE($8, email_reminders), sms_reminders=COALESCE($9, sms_reminders) WHERE id=$6",
+        )
+        .await?;
 
     client
         .execute(
@@ -164,6 +216,9 @@ pub async fn update_member_with_password(
                 &updated_member.first_name,
                 &updated_member.last_name,
                 &updated_member.id,
+                &preferences.locale.as_ref().map(Locale::as_str),
+                &preferences.email_reminders,
+                &preferences.sms_reminders,
             ],
         )
         .await
@@ -185,3 +240,54 @@ pub async fn delete_member(client: &Client, id: &String) -> Result<u64, Error> {
     let stmt: Statement = client.prepare("DELETE FROM member WHERE id=$1").await?;
     client.execute(&stmt, &[&id]).await
 }
+
+/// Locks the member until the end of the transaction, so that bookings counting towards their
+/// quotas are checked one at a time.
+pub async fn lock_member(client: &impl GenericClient, id: &str) -> Result<(), Error> {
+    let stmt: Statement = client
+        .prepare("SELECT id FROM member WHERE id=$1 FOR NO KEY UPDATE")
+        .await?;
+    client.query_opt(&stmt, &[&id]).await?;
+    Ok(())
+}
+
+pub async fn increment_late_cancellations(
+    client: &impl GenericClient,
+    id: &str,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("UPDATE member SET late_cancellations = late_cancellations + 1 WHERE id=$1")
+        .await?;
+    client.execute(&stmt, &[&id]).await
+}
+
+/// Counts a no-show of the member and returns their number of no-shows.
+pub async fn increment_no_shows(client: &impl GenericClient, id: &str) -> Result<i32, Error> {
+    let stmt: Statement = client
+        .prepare("UPDATE member SET no_shows = no_shows + 1 WHERE id=$1 RETURNING no_shows")
+        .await?;
+    let row: Row = client.query_one(&stmt, &[&id]).await?;
+    row.try_get("no_shows")
+}
+
+/// Forgives a no-show of the member and returns their number of no-shows.
+pub async fn decrement_no_shows(client: &impl GenericClient, id: &str) -> Result<i32, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE member SET no_shows = GREATEST(no_shows - 1, 0) WHERE id=$1 RETURNING no_shows",
+        )
+        .await?;
+    let row: Row = client.query_one(&stmt, &[&id]).await?;
+    row.try_get("no_shows")
+}
+
+pub async fn set_suspended_until(
+    client: &impl GenericClient,
+    id: &str,
+    until: Option<&NaiveDate>,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("UPDATE member SET suspended_until=$2 WHERE id=$1")
+        .await?;
+    client.execute(&stmt, &[&id, &until]).await
+}
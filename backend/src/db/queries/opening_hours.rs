@@ -0,0 +1,86 @@
+use crate::db::models::OpeningHours;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Client, Error, Row, Statement};
+
+fn opening_hours_from_row(row: &Row) -> Result<OpeningHours, Error> {
+    Ok(OpeningHours {
+        id: row.try_get("id")?,
+        court_number: row.try_get("court_number")?,
+        weekday: row.try_get("weekday")?,
+        opens_at: row.try_get("opens_at")?,
+        closes_at: row.try_get("closes_at")?,
+        slot_minutes: row.try_get("slot_minutes")?,
+        valid_from: row.try_get("valid_from")?,
+        valid_until: row.try_get("valid_until")?,
+        is_closed: row.try_get("is_closed")?,
+    })
+}
+
+pub async fn add_opening_hours(client: &Client, hours: &OpeningHours) -> Result<i32, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "INSERT INTO opening_hours (court_number, weekday, opens_at, closes_at, slot_minutes, valid_from, valid_until, is_closed) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+        )
+        .await?;
+
+    let row: Row = client
+        .query_one(
+            &stmt,
+            &[
+                &hours.court_number,
+                &hours.weekday,
+                &hours.opens_at,
+                &hours.closes_at,
+                &hours.slot_minutes,
+                &hours.valid_from,
+                &hours.valid_until,
+                &hours.is_closed,
+            ],
+        )
+        .await?;
+
+    let id: i32 = row.try_get("id")?;
+    Ok(id)
+}
+
+pub async fn get_all_opening_hours(
+    client: &impl GenericClient,
+) -> Result<Vec<OpeningHours>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT * FROM opening_hours ORDER BY weekday, court_number NULLS FIRST, opens_at")
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[]).await?;
+    rows.iter().map(opening_hours_from_row).collect()
+}
+
+pub async fn update_opening_hours(client: &Client, hours: &OpeningHours) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE opening_hours SET court_number=$2, weekday=$3, opens_at=$4, closes_at=$5, slot_minutes=$6, valid_from=$7, valid_until=$8, is_closed=$9 WHERE id=$1",
+        )
+        .await?;
+    client
+        .execute(
+            &stmt,
+            &[
+                &hours.id,
+                &hours.court_number,
+                &hours.weekday,
+                &hours.opens_at,
+                &hours.closes_at,
+                &hours.slot_minutes,
+                &hours.valid_from,
+                &hours.valid_until,
+                &hours.is_closed,
+            ],
+        )
+        .await
+}
+
+pub async fn delete_opening_hours(client: &Client, id: i32) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM opening_hours WHERE id=$1")
+        .await?;
+    client.execute(&stmt, &[&id]).await
+}
@@ -0,0 +1,113 @@
+use crate::db::models::{Participant, ParticipantStatus};
+use chrono::NaiveDate;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Client, Error, Row, Statement};
+
+const SELECT_PARTICIPANTS: &str = "
+    SELECT p.*, m.first_name, m.last_name
+    FROM reservation_participant p
+    LEFT JOIN member m ON p.member_id = m.id";
+
+fn participant_from_row(row: &Row) -> Result<Participant, Error> {
+    let status: String = row.try_get("status")?;
+    Ok(Participant {
+        id: row.try_get("id")?,
+        reservation_id: row.try_get("reservation_id")?,
+        member_id: row.try_get("member_id")?,
+        guest_name: row.try_get("guest_name")?,
+        status: ParticipantStatus::from_db(&status).unwrap_or(ParticipantStatus::Invited),
+        member_first_name: row.try_get("first_name")?,
+        member_last_name: row.try_get("last_name")?,
+    })
+}
+
+pub async fn get_participants(
+    client: &Client,
+    reservation_id: &str,
+) -> Result<Vec<Participant>, Error> {
+    let stmt: Statement = client
+        .prepare(&format!(
+            "{SELECT_PARTICIPANTS} WHERE p.reservation_id=$1 ORDER BY p.id"
+        ))
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[&reservation_id]).await?;
+    rows.iter().map(participant_from_row).collect()
+}
+
+/// Participants of the reservations of `date` which are not cancelled.
+pub async fn get_participants_by_date(
+    client: &Client,
+    date: &NaiveDate,
+) -> Result<Vec<Participant>, Error> {
+    let stmt: Statement = client
+        .prepare(&format!(
+            "{SELECT_PARTICIPANTS}
+             JOIN reservation r ON p.reservation_id = r.id
+             WHERE r.reservation_date=$1 AND r.cancelled_at IS NULL
+             ORDER BY p.id"
+        ))
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[date]).await?;
+    rows.iter().map(participant_from_row).collect()
+}
+
+/// Replaces the participants of a reservation, members still invited keeping their answer.
+pub async fn set_participants(
+    client: &impl GenericClient,
+    reservation_id: &str,
+    member_ids: &[String],
+    guest_names: &[String],
+) -> Result<(), Error> {
+    let delete_stmt: Statement = client
+        .prepare(
+            "DELETE FROM reservation_participant
+             WHERE reservation_id=$1 AND (member_id IS NULL OR NOT member_id = ANY($2))",
+        )
+        .await?;
+    client
+        .execute(&delete_stmt, &[&reservation_id, &member_ids])
+        .await?;
+
+    let member_stmt: Statement = client
+        .prepare(
+            "INSERT INTO reservation_participant (reservation_id, member_id) VALUES ($1, $2)
+             ON CONFLICT (reservation_id, member_id) DO NOTHING",
+        )
+        .await?;
+    for member_id in member_ids {
+        client
+            .execute(&member_stmt, &[&reservation_id, member_id])
+            .await?;
+    }
+
+    let guest_stmt: Statement = client
+        .prepare(
+            "INSERT INTO reservation_participant (reservation_id, guest_name, status) VALUES ($1, $2, 'accepted')",
+        )
+        .await?;
+    for guest_name in guest_names {
+        client
+            .execute(&guest_stmt, &[&reservation_id, guest_name])
+            .await?;
+    }
+    Ok(())
+}
+
+/// Records the answer of an invited member.
+pub async fn set_status(
+    client: &impl GenericClient,
+    reservation_id: &str,
+    member_id: &str,
+    status: ParticipantStatus,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE reservation_participant SET status=$3 WHERE reservation_id=$1 AND member_id=$2",
+        )
+        .await?;
+    client
+        .execute(&stmt, &[&reservation_id, &member_id, &status.as_str()])
+        .await
+}
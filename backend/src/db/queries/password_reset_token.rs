@@ -1,8 +1,9 @@
 use crate::db::models::PasswordResetToken;
+use deadpool_postgres::GenericClient;
 use tokio_postgres::{Client, Error, Row, Statement};
 use uuid::Uuid;
 
-pub async fn create_token(client: &Client, member_id: &str) -> Result<Uuid, Error> {
+pub async fn create_token(client: &impl GenericClient, member_id: &str) -> Result<Uuid, Error> {
     let stmt: Statement = client
         .prepare("INSERT INTO password_reset_token (member_id) VALUES ($1) RETURNING token")
         .await?;
@@ -35,3 +36,10 @@ pub async fn delete_token(client: &Client, token: &Uuid) -> Result<u64, Error> {
         .await?;
     client.execute(&stmt, &[&token]).await
 }
+
+pub async fn delete_expired_tokens(client: &Client) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM password_reset_token WHERE expires_at < NOW()")
+        .await?;
+    client.execute(&stmt, &[]).await
+}
@@ -0,0 +1,53 @@
+use crate::db::models::{ReminderChannel, Reservation};
+use crate::db::queries::reservation::reservation_from_row;
+use chrono::NaiveDateTime;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Client, Error, Row, Statement};
+
+/// Reservations starting after `from` and by `to` whose member has not opted out of reminders on
+/// `channel` nor been reminded of the current start on it yet, soonest first.
+pub async fn get_due_reminders(
+    client: &Client,
+    channel: ReminderChannel,
+    from: &NaiveDateTime,
+    to: &NaiveDateTime,
+) -> Result<Vec<Reservation>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "SELECT r.* FROM reservation r
+             JOIN member m ON m.id = r.member_id
+             WHERE r.cancelled_at IS NULL
+             AND lower(r.period) > $2 AND lower(r.period) <= $3
+             AND CASE $1 WHEN 'email' THEN m.email_reminders AND m.email IS NOT NULL
+                 ELSE m.sms_reminders END
+             AND NOT EXISTS (
+               SELECT 1 FROM reservation_reminder rr
+               WHERE rr.reservation_id = r.id AND rr.channel = $1 AND rr.starts_at = lower(r.period)
+             )
+             ORDER BY lower(r.period), r.court_number",
+        )
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[&channel.as_str(), from, to]).await?;
+    rows.iter().map(reservation_from_row).collect()
+}
+
+/// Records the reminder of the reservation starting at `starts_at` on `channel`, unless it was
+/// already recorded. Returns whether it was, in which case the reminder must be sent.
+pub async fn record_reminder(
+    client: &impl GenericClient,
+    reservation_id: &str,
+    channel: ReminderChannel,
+    starts_at: &NaiveDateTime,
+) -> Result<bool, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "INSERT INTO reservation_reminder (reservation_id, channel, starts_at) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
+        )
+        .await?;
+
+    let inserted: u64 = client
+        .execute(&stmt, &[&reservation_id, &channel.as_str(), starts_at])
+        .await?;
+    Ok(inserted == 1)
+}
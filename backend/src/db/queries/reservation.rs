@@ -1,10 +1,47 @@
-use crate::db::models::{Reservation, ReservationWithNames};
-use chrono::NaiveDate;
+use crate::db::models::{Cancellation, CheckIn, Reservation, ReservationWithNames};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use deadpool_postgres::GenericClient;
 use tokio_postgres::{Client, Error, Row, Statement};
 
-pub async fn add_reservation(client: &Client, reservation: &Reservation) -> Result<String, Error> {
+pub(crate) fn reservation_from_row(row: &Row) -> Result<Reservation, Error> {
+    Ok(Reservation {
+        id: row.try_get("id")?,
+        member_id: row.try_get("member_id")?,
+        court_number: row.try_get("court_number")?,
+        reservation_date: row.try_get("reservation_date")?,
+        start_time: row.try_get("start_time")?,
+        duration_minutes: row.try_get("duration_minutes")?,
+        series_id: row.try_get("series_id")?,
+        cancellation: row
+            .try_get::<_, Option<DateTime<Utc>>>("cancelled_at")?
+            .map(|cancelled_at| -> Result<Cancellation, Error> {
+                Ok(Cancellation {
+                    cancelled_at,
+                    cancelled_by: row.try_get("cancelled_by")?,
+                    reason: row.try_get("cancellation_reason")?,
+                    is_late: row.try_get("is_late_cancellation")?,
+                })
+            })
+            .transpose()?,
+        check_in: row
+            .try_get::<_, Option<DateTime<Utc>>>("checked_in_at")?
+            .map(|checked_in_at| -> Result<CheckIn, Error> {
+                Ok(CheckIn {
+                    checked_in_at,
+                    checked_in_by: row.try_get("checked_in_by")?,
+                })
+            })
+            .transpose()?,
+        is_no_show: row.try_get("is_no_show")?,
+    })
+}
+
+pub async fn add_reservation(
+    client: &impl GenericClient,
+    reservation: &Reservation,
+) -> Result<String, Error> {
     let stmt: Statement = client
-        .prepare("INSERT INTO reservation (id, member_id, court_number, reservation_date, reservation_time) VALUES ($1, $2, $3, $4, $5) RETURNING id")
+        .prepare("INSERT INTO reservation (id, member_id, court_number, reservation_date, start_time, duration_minutes, series_id) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id")
         .await?;
 
     let row: Row = client
@@ -15,7 +52,9 @@ pub async fn add_reservation(client: &Client, reservation: &Reservation) -> Resu
                 &reservation.member_id,
                 &reservation.court_number,
                 &reservation.reservation_date,
-                &reservation.reservation_time,
+                &reservation.start_time,
+                &reservation.duration_minutes,
+                &reservation.series_id,
             ],
         )
         .await?;
@@ -30,14 +69,28 @@ pub async fn get_reservation(client: &Client, id: &String) -> Result<Reservation
         .await?;
 
     let row: Row = client.query_one(&stmt, &[id]).await?;
+    reservation_from_row(&row)
+}
 
-    Ok(Reservation {
-        id: row.try_get("id")?,
-        member_id: row.try_get("member_id")?,
-        court_number: row.try_get("court_number")?,
-        reservation_date: row.try_get("reservation_date")?,
-        reservation_time: row.try_get("reservation_time")?,
-    })
+pub async fn get_reservations_between(
+    client: &impl GenericClient,
+    from: &NaiveDate,
+    to: &NaiveDate,
+    court_number: Option<i16>,
+) -> Result<Vec<Reservation>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "SELECT * FROM reservation
+             WHERE reservation_date BETWEEN $1 AND $2
+             AND ($3::SMALLINT IS NULL OR court_number=$3)
+             AND cancelled_at IS NULL
+             ORDER BY reservation_date, start_time, court_number",
+        )
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[from, to, &court_number]).await?;
+
+    rows.iter().map(reservation_from_row).collect()
 }
 
 pub async fn get_reservations_with_names_by_date(
@@ -52,13 +105,16 @@ pub async fn get_reservations_with_names_by_date(
               r.member_id,
               r.court_number,
               r.reservation_date,
-              r.reservation_time,
+              r.start_time,
+              r.duration_minutes,
+              (r.start_time + r.duration_minutes * INTERVAL '1 minute')::TIME AS end_time,
+              r.series_id,
               m.first_name,
               m.last_name
             FROM reservation r
             JOIN member m ON r.member_id = m.id
-            WHERE r.reservation_date = $1
-            ORDER BY r.reservation_time, r.court_number
+            WHERE r.reservation_date = $1 AND r.cancelled_at IS NULL
+            ORDER BY r.start_time, r.court_number
             ",
         )
         .await?;
@@ -72,37 +128,216 @@ pub async fn get_reservations_with_names_by_date(
                 member_id: row.try_get("member_id")?,
                 court_number: row.try_get("court_number")?,
                 reservation_date: row.try_get("reservation_date")?,
-                reservation_time: row.try_get("reservation_time")?,
+                start_time: row.try_get("start_time")?,
+                duration_minutes: row.try_get("duration_minutes")?,
+                end_time: row.try_get("end_time")?,
+                series_id: row.try_get("series_id")?,
                 member_first_name: row.try_get("first_name")?,
                 member_last_name: row.try_get("last_name")?,
+                participants: Vec::new(),
             })
         })
         .collect()
 }
 
 pub async fn update_reservation(
-    client: &Client,
+    client: &impl GenericClient,
     updated_reservation: &Reservation,
 ) -> Result<u64, Error> {
-    let stmt: Statement = client.prepare("UPDATE reservation SET member_id=$1, court_number=$2, reservation_date=$3, reservation_time=$4 WHERE id=$5").await?;
+    let stmt: Statement = client.prepare("UPDATE reservation SET member_id=$1, court_number=$2, reservation_date=$3, start_time=$4, duration_minutes=$5 WHERE id=$6 AND cancelled_at IS NULL").await?;
 
     client
         .execute(
             &stmt,
             &[
-                &updated_reservation.court_number,
                 &updated_reservation.member_id,
+                &updated_reservation.court_number,
                 &updated_reservation.reservation_date,
-                &updated_reservation.reservation_time,
+                &updated_reservation.start_time,
+                &updated_reservation.duration_minutes,
                 &updated_reservation.id,
             ],
         )
         .await
 }
 
-pub async fn delete_reservation(client: &Client, id: &String) -> Result<u64, Error> {
+pub async fn cancel_reservation(
+    client: &impl GenericClient,
+    id: &str,
+    cancelled_by: &str,
+    reason: Option<&str>,
+    is_late: bool,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE reservation SET cancelled_at=NOW(), cancelled_by=$2, cancellation_reason=$3, is_late_cancellation=$4 WHERE id=$1 AND cancelled_at IS NULL",
+        )
+        .await?;
+    client
+        .execute(&stmt, &[&id, &cancelled_by, &reason, &is_late])
+        .await
+}
+
+/// Occurrences of the series on or after `from` which are not cancelled, in chronological
+/// order.
+pub async fn get_series_reservations_from(
+    client: &impl GenericClient,
+    series_id: &str,
+    from: &NaiveDate,
+) -> Result<Vec<Reservation>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "SELECT * FROM reservation WHERE series_id=$1 AND reservation_date >= $2 AND cancelled_at IS NULL ORDER BY reservation_date",
+        )
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[&series_id, from]).await?;
+    rows.iter().map(reservation_from_row).collect()
+}
+
+/// Moves the occurrences of a series on or after `from` to another series.
+pub async fn move_series_reservations_from(
+    client: &impl GenericClient,
+    series_id: &str,
+    from: &NaiveDate,
+    new_series_id: &str,
+) -> Result<u64, Error> {
     let stmt: Statement = client
-        .prepare("DELETE FROM reservation WHERE id=$1")
+        .prepare(
+            "UPDATE reservation SET series_id=$3 WHERE series_id=$1 AND reservation_date >= $2",
+        )
+        .await?;
+    client
+        .execute(&stmt, &[&series_id, from, &new_series_id])
+        .await
+}
+
+/// Cancels the occurrences of a series from `from` on, and returns them.
+pub async fn cancel_series_reservations_from(
+    client: &impl GenericClient,
+    series_id: &str,
+    from: &NaiveDate,
+    cancelled_by: &str,
+    reason: Option<&str>,
+) -> Result<Vec<Reservation>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE reservation SET cancelled_at=NOW(), cancelled_by=$3, cancellation_reason=$4 WHERE series_id=$1 AND reservation_date >= $2 AND cancelled_at IS NULL RETURNING *",
+        )
+        .await?;
+    let rows: Vec<Row> = client
+        .query(&stmt, &[&series_id, from, &cancelled_by, &reason])
+        .await?;
+    rows.iter().map(reservation_from_row).collect()
+}
+
+/// Checks the member in, clearing a no-show recorded before the check-in.
+pub async fn check_in_reservation(
+    client: &impl GenericClient,
+    id: &str,
+    checked_in_by: &str,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE reservation SET checked_in_at=NOW(), checked_in_by=$2, is_no_show=FALSE WHERE id=$1 AND cancelled_at IS NULL AND checked_in_at IS NULL",
+        )
+        .await?;
+    client.execute(&stmt, &[&id, &checked_in_by]).await
+}
+
+/// Marks as no-shows the reservations which ended between `from` and `to` without a check-in,
+/// and returns them.
+pub async fn mark_no_shows(
+    client: &impl GenericClient,
+    from: &NaiveDateTime,
+    to: &NaiveDateTime,
+) -> Result<Vec<Reservation>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE reservation SET is_no_show=TRUE
+             WHERE cancelled_at IS NULL AND checked_in_at IS NULL AND NOT is_no_show
+             AND upper(period) > $1 AND upper(period) <= $2
+             RETURNING *",
+        )
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[from, to]).await?;
+    rows.iter().map(reservation_from_row).collect()
+}
+
+/// Filter of `get_member_reservations_page`, shared by the page and its count.
+const MEMBER_HISTORY_FILTER: &str = "(member_id=$1 OR EXISTS (
+               SELECT 1 FROM reservation_participant p
+               WHERE p.reservation_id = r.id AND p.member_id=$1 AND p.status <> 'declined'
+             ))
+             AND CASE WHEN $2 THEN upper(period) > $3 AND cancelled_at IS NULL
+                 ELSE upper(period) <= $3 END";
+
+/// A page of the reservations a member booked or has not declined to join, with the total
+/// number of them. Upcoming reservations are those not over by `now`, soonest first and
+/// without the cancelled ones, while past reservations come most recent first.
+pub async fn get_member_reservations_page(
+    client: &Client,
+    member_id: &str,
+    upcoming: bool,
+    now: &NaiveDateTime,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<Reservation>, i64), Error> {
+    // Counted apart from the page, which is empty past the last one
+    let count_stmt: Statement = client
+        .prepare(&format!(
+            "SELECT COUNT(*) AS total FROM reservation r WHERE {MEMBER_HISTORY_FILTER}"
+        ))
+        .await?;
+    let total: i64 = client
+        .query_one(&count_stmt, &[&member_id, &upcoming, now])
+        .await?
+        .try_get("total")?;
+
+    let stmt: Statement = client
+        .prepare(&format!(
+            "SELECT * FROM reservation r WHERE {MEMBER_HISTORY_FILTER}
+             ORDER BY
+               CASE WHEN $2 THEN upper(period) END ASC,
+               CASE WHEN NOT $2 THEN upper(period) END DESC,
+               court_number
+             LIMIT $4 OFFSET $5"
+        ))
+        .await?;
+    let rows: Vec<Row> = client
+        .query(&stmt, &[&member_id, &upcoming, now, &limit, &offset])
+        .await?;
+    let reservations = rows
+        .iter()
+        .map(reservation_from_row)
+        .collect::<Result<Vec<Reservation>, Error>>()?;
+    Ok((reservations, total))
+}
+
+/// Reservations of a member between `from` and `to` included, in chronological order, with
+/// those the member accepted to join if `with_participations`.
+pub async fn get_member_reservations_between(
+    client: &impl GenericClient,
+    member_id: &str,
+    from: &NaiveDate,
+    to: &NaiveDate,
+    with_participations: bool,
+) -> Result<Vec<Reservation>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "SELECT * FROM reservation r
+             WHERE reservation_date BETWEEN $2 AND $3 AND cancelled_at IS NULL
+             AND (member_id=$1 OR ($4 AND EXISTS (
+               SELECT 1 FROM reservation_participant p
+               WHERE p.reservation_id = r.id AND p.member_id=$1 AND p.status = 'accepted'
+             )))
+             ORDER BY reservation_date, start_time",
+        )
+        .await?;
+
+    let rows: Vec<Row> = client
+        .query(&stmt, &[&member_id, from, to, &with_participations])
         .await?;
-    client.execute(&stmt, &[id]).await
+    rows.iter().map(reservation_from_row).collect()
 }
@@ -0,0 +1,100 @@
+use crate::db::models::ReservationSeries;
+use chrono::NaiveDate;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Error, Row, Statement};
+
+pub async fn add_series(
+    client: &impl GenericClient,
+    series: &ReservationSeries,
+) -> Result<String, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "INSERT INTO reservation_series (id, member_id, court_number, start_time, duration_minutes, first_date, interval_weeks, until_date, occurrence_count) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+        )
+        .await?;
+
+    let row: Row = client
+        .query_one(
+            &stmt,
+            &[
+                &series.id,
+                &series.member_id,
+                &series.court_number,
+                &series.start_time,
+                &series.duration_minutes,
+                &series.first_date,
+                &series.interval_weeks,
+                &series.until_date,
+                &series.occurrence_count,
+            ],
+        )
+        .await?;
+
+    let id: String = row.try_get("id")?;
+    Ok(id)
+}
+
+pub async fn get_series(
+    client: &impl GenericClient,
+    id: &str,
+) -> Result<Option<ReservationSeries>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT * FROM reservation_series WHERE id=$1")
+        .await?;
+
+    let row: Option<Row> = client.query_opt(&stmt, &[&id]).await?;
+    row.map(|row| {
+        Ok(ReservationSeries {
+            id: row.try_get("id")?,
+            member_id: row.try_get("member_id")?,
+            court_number: row.try_get("court_number")?,
+            start_time: row.try_get("start_time")?,
+            duration_minutes: row.try_get("duration_minutes")?,
+            first_date: row.try_get("first_date")?,
+            interval_weeks: row.try_get("interval_weeks")?,
+            until_date: row.try_get("until_date")?,
+            occurrence_count: row.try_get("occurrence_count")?,
+        })
+    })
+    .transpose()
+}
+
+/// Updates the member, court and time of a series, which is no longer limited by a number of
+/// occurrences but by the date of its last one.
+pub async fn update_series(
+    client: &impl GenericClient,
+    series: &ReservationSeries,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE reservation_series SET member_id=$2, court_number=$3, start_time=$4, duration_minutes=$5, first_date=$6, until_date=$7, occurrence_count=$8 WHERE id=$1",
+        )
+        .await?;
+    client
+        .execute(
+            &stmt,
+            &[
+                &series.id,
+                &series.member_id,
+                &series.court_number,
+                &series.start_time,
+                &series.duration_minutes,
+                &series.first_date,
+                &series.until_date,
+                &series.occurrence_count,
+            ],
+        )
+        .await
+}
+
+/// Ends the series on `until`, its later occurrences having been moved or deleted.
+pub async fn end_series(
+    client: &impl GenericClient,
+    id: &str,
+    until: &NaiveDate,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("UPDATE reservation_series SET until_date=$2, occurrence_count=NULL WHERE id=$1")
+        .await?;
+    client.execute(&stmt, &[&id, until]).await
+}
@@ -0,0 +1,110 @@
+use crate::db::models::Session;
+use tokio_postgres::{Client, Error, Row, Statement};
+use uuid::Uuid;
+
+pub async fn create_session(
+    client: &Client,
+    member_id: &str,
+    refresh_token_hash: &str,
+    ttl_days: i32,
+) -> Result<Uuid, Error> {
+    let stmt: Statement = client
+        .prepare("INSERT INTO session (member_id, refresh_token_hash, expires_at) VALUES ($1, $2, NOW() + make_interval(days => $3)) RETURNING id")
+        .await?;
+
+    let row: Row = client
+        .query_one(&stmt, &[&member_id, &refresh_token_hash, &ttl_days])
+        .await?;
+
+    let id: Uuid = row.try_get("id")?;
+    Ok(id)
+}
+
+pub async fn get_active_session_by_token_hash(
+    client: &Client,
+    refresh_token_hash: &str,
+) -> Result<Session, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT * FROM session WHERE refresh_token_hash=$1 AND revoked_at IS NULL AND expires_at > NOW()")
+        .await?;
+
+    let row: Row = client.query_one(&stmt, &[&refresh_token_hash]).await?;
+
+    Ok(Session {
+        id: row.try_get("id")?,
+        member_id: row.try_get("member_id")?,
+        created_at: row.try_get("created_at")?,
+        last_used_at: row.try_get("last_used_at")?,
+        expires_at: row.try_get("expires_at")?,
+        revoked_at: row.try_get("revoked_at")?,
+    })
+}
+
+/// Replaces the refresh token of an active session, only if `old_hash` is still the current one,
+/// and extends the session by `ttl_days` from now. `old_hash` is kept to detect its reuse.
+pub async fn rotate_refresh_token(
+    client: &Client,
+    id: &Uuid,
+    old_hash: &str,
+    new_hash: &str,
+    ttl_days: i32,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("UPDATE session SET refresh_token_hash=$1, previous_refresh_token_hash=$3, last_used_at=NOW(), expires_at=NOW() + make_interval(days => $4) WHERE id=$2 AND refresh_token_hash=$3 AND revoked_at IS NULL")
+        .await?;
+
+    client
+        .execute(&stmt, &[&new_hash, &id, &old_hash, &ttl_days])
+        .await
+}
+
+pub async fn revoke_session(client: &Client, id: &Uuid) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("UPDATE session SET revoked_at=NOW() WHERE id=$1 AND revoked_at IS NULL")
+        .await?;
+
+    client.execute(&stmt, &[&id]).await
+}
+
+pub async fn revoke_session_by_token_hash(
+    client: &Client,
+    refresh_token_hash: &str,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("UPDATE session SET revoked_at=NOW() WHERE refresh_token_hash=$1 AND revoked_at IS NULL")
+        .await?;
+
+    client.execute(&stmt, &[&refresh_token_hash]).await
+}
+
+/// Revokes the session whose refresh token was rotated from `refresh_token_hash`.
+pub async fn revoke_session_by_previous_token_hash(
+    client: &Client,
+    refresh_token_hash: &str,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("UPDATE session SET revoked_at=NOW() WHERE previous_refresh_token_hash=$1 AND revoked_at IS NULL")
+        .await?;
+
+    client.execute(&stmt, &[&refresh_token_hash]).await
+}
+
+/// Revokes every active session of a member, except `keep` if given.
+pub async fn revoke_member_sessions(
+    client: &Client,
+    member_id: &str,
+    keep: Option<&Uuid>,
+) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("UPDATE session SET revoked_at=NOW() WHERE member_id=$1 AND revoked_at IS NULL AND ($2::UUID IS NULL OR id <> $2)")
+        .await?;
+
+    client.execute(&stmt, &[&member_id, &keep]).await
+}
+
+pub async fn delete_expired_sessions(client: &Client) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM session WHERE expires_at < NOW() OR revoked_at IS NOT NULL")
+        .await?;
+    client.execute(&stmt, &[]).await
+}
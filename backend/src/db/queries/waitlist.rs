@@ -0,0 +1,162 @@
+use crate::db::models::{Reservation, WaitlistEntry};
+use chrono::NaiveDate;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::{Client, Error, Row, Statement};
+use uuid::Uuid;
+
+fn waitlist_entry_from_row(row: &Row) -> Result<WaitlistEntry, Error> {
+    Ok(WaitlistEntry {
+        id: row.try_get("id")?,
+        member_id: row.try_get("member_id")?,
+        court_number: row.try_get("court_number")?,
+        reservation_date: row.try_get("reservation_date")?,
+        start_time: row.try_get("start_time")?,
+        duration_minutes: row.try_get("duration_minutes")?,
+        auto_book: row.try_get("auto_book")?,
+        created_at: row.try_get("created_at")?,
+        claim_token: row.try_get("claim_token")?,
+        claim_court_number: row.try_get("claim_court_number")?,
+        claim_expires_at: row.try_get("claim_expires_at")?,
+    })
+}
+
+pub async fn add_entry(client: &Client, entry: &WaitlistEntry) -> Result<i32, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "INSERT INTO waitlist_entry (member_id, court_number, reservation_date, start_time, duration_minutes, auto_book) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+        )
+        .await?;
+
+    let row: Row = client
+        .query_one(
+            &stmt,
+            &[
+                &entry.member_id,
+                &entry.court_number,
+                &entry.reservation_date,
+                &entry.start_time,
+                &entry.duration_minutes,
+                &entry.auto_book,
+            ],
+        )
+        .await?;
+
+    let id: i32 = row.try_get("id")?;
+    Ok(id)
+}
+
+pub async fn get_entry(client: &Client, id: i32) -> Result<Option<WaitlistEntry>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT * FROM waitlist_entry WHERE id=$1")
+        .await?;
+
+    let row: Option<Row> = client.query_opt(&stmt, &[&id]).await?;
+    row.as_ref().map(waitlist_entry_from_row).transpose()
+}
+
+pub async fn get_entry_by_claim_token(
+    client: &Client,
+    token: &Uuid,
+) -> Result<Option<WaitlistEntry>, Error> {
+    let stmt: Statement = client
+        .prepare("SELECT * FROM waitlist_entry WHERE claim_token=$1")
+        .await?;
+
+    let row: Option<Row> = client.query_opt(&stmt, &[token]).await?;
+    row.as_ref().map(waitlist_entry_from_row).transpose()
+}
+
+/// Entries of a member, or of every member if `None`.
+pub async fn get_entries(
+    client: &Client,
+    member_id: Option<&str>,
+) -> Result<Vec<WaitlistEntry>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "SELECT * FROM waitlist_entry
+             WHERE ($1::TEXT IS NULL OR member_id=$1)
+             ORDER BY reservation_date, start_time, created_at, id",
+        )
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[&member_id]).await?;
+    rows.iter().map(waitlist_entry_from_row).collect()
+}
+
+/// Entries without a pending claim whose requested time overlaps `freed` on its court, first
+/// come first served.
+pub async fn get_waiting_entries(
+    client: &Client,
+    freed: &Reservation,
+) -> Result<Vec<WaitlistEntry>, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "SELECT * FROM waitlist_entry
+             WHERE claim_token IS NULL
+             AND reservation_date=$1
+             AND (court_number IS NULL OR court_number=$2)
+             AND start_time < $3::TIME + $4::INT * INTERVAL '1 minute'
+             AND start_time + duration_minutes * INTERVAL '1 minute' > $3::TIME
+             ORDER BY created_at, id",
+        )
+        .await?;
+
+    let rows: Vec<Row> = client
+        .query(
+            &stmt,
+            &[
+                &freed.reservation_date,
+                &freed.court_number,
+                &freed.start_time,
+                &i32::from(freed.duration_minutes),
+            ],
+        )
+        .await?;
+    rows.iter().map(waitlist_entry_from_row).collect()
+}
+
+/// Offers `court_number` to the member of the entry for `minutes`, and returns the claim token.
+pub async fn set_claim(
+    client: &impl GenericClient,
+    id: i32,
+    court_number: i16,
+    minutes: i64,
+) -> Result<Uuid, Error> {
+    let stmt: Statement = client
+        .prepare(
+            "UPDATE waitlist_entry SET claim_token=gen_random_uuid(), claim_court_number=$2, claim_expires_at=NOW() + $3::BIGINT * INTERVAL '1 minute' WHERE id=$1 RETURNING claim_token",
+        )
+        .await?;
+
+    let row: Row = client
+        .query_one(&stmt, &[&id, &court_number, &minutes])
+        .await?;
+
+    let token: Uuid = row.try_get("claim_token")?;
+    Ok(token)
+}
+
+pub async fn delete_entry(client: &impl GenericClient, id: i32) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM waitlist_entry WHERE id=$1")
+        .await?;
+    client.execute(&stmt, &[&id]).await
+}
+
+/// Deletes the entries whose claim has expired and returns them.
+pub async fn delete_expired_claims(client: &Client) -> Result<Vec<WaitlistEntry>, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM waitlist_entry WHERE claim_expires_at < NOW() RETURNING *")
+        .await?;
+
+    let rows: Vec<Row> = client.query(&stmt, &[]).await?;
+    rows.iter().map(waitlist_entry_from_row).collect()
+}
+
+/// Deletes the entries of the days before `today`, the current date of the club.
+pub async fn delete_past_entries(client: &Client, today: &NaiveDate) -> Result<u64, Error> {
+    let stmt: Statement = client
+        .prepare("DELETE FROM waitlist_entry WHERE reservation_date < $1")
+        .await?;
+    client.execute(&stmt, &[today]).await
+}
@@ -0,0 +1,122 @@
+//! TLS configuration of the Postgres connection.
+//!
+//! Certificates are always verified against trusted roots and the server host name
+//! (`verify-full` semantics). The roots come from the CA bundle of the database provider when
+//! one is configured, otherwise from the system store and the Mozilla root program.
+
+use rustls::{ClientConfig, RootCertStore, crypto::aws_lc_rs};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("could not read CA bundle {path}: {source}")]
+    ReadCaBundle { path: PathBuf, source: io::Error },
+    #[error("CA bundle {0} does not contain any certificate")]
+    EmptyCaBundle(PathBuf),
+    #[error("invalid certificate in CA bundle {path}: {source}")]
+    InvalidCertificate {
+        path: PathBuf,
+        source: rustls::Error,
+    },
+    #[error("no trusted root certificate available")]
+    NoRootCertificates,
+    #[error("TLS configuration error: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Builds a connector that verifies the server certificate chain and host name.
+pub fn make_tls_connector(ca_bundle: Option<&Path>) -> Result<MakeRustlsConnect, TlsError> {
+    let roots: RootCertStore = match ca_bundle {
+        Some(path) => load_ca_bundle(path)?,
+        None => load_default_roots()?,
+    };
+
+    let config = ClientConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    Ok(MakeRustlsConnect::new(config))
+}
+
+/// Trusts only the certificates of a PEM bundle, e.g. the Amazon RDS or Supabase CA.
+fn load_ca_bundle(path: &Path) -> Result<RootCertStore, TlsError> {
+    let file = File::open(path).map_err(|source| TlsError::ReadCaBundle {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut BufReader::new(file)) {
+        let cert = cert.map_err(|source| TlsError::ReadCaBundle {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        roots
+            .add(cert)
+            .map_err(|source| TlsError::InvalidCertificate {
+                path: path.to_path_buf(),
+                source,
+            })?;
+    }
+
+    if roots.is_empty() {
+        return Err(TlsError::EmptyCaBundle(path.to_path_buf()));
+    }
+
+    tracing::info!(
+        "Loaded {} CA certificate(s) from {}",
+        roots.len(),
+        path.display()
+    );
+    Ok(roots)
+}
+
+/// Trusts the system certificate store along with the bundled Mozilla roots.
+fn load_default_roots() -> Result<RootCertStore, TlsError> {
+    let mut roots = RootCertStore::empty();
+
+    let native = rustls_native_certs::load_native_certs();
+    for error in &native.errors {
+        tracing::warn!("Could not load a system certificate: {}", error);
+    }
+    let (added, ignored) = roots.add_parsable_certificates(native.certs);
+    tracing::debug!(
+        "Loaded {} system CA certificate(s), ignored {}",
+        added,
+        ignored
+    );
+
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+    if roots.is_empty() {
+        return Err(TlsError::NoRootCertificates);
+    }
+
+    Ok(roots)
+}
+
+/// Finds the certificate verification failure behind a connection error, if any.
+pub fn certificate_error(error: &(dyn std::error::Error + 'static)) -> Option<String> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(err) = source {
+        if let Some(rustls::Error::InvalidCertificate(reason)) = err.downcast_ref::<rustls::Error>()
+        {
+            return Some(format!("{reason:?}"));
+        }
+        if let Some(io_err) = err.downcast_ref::<io::Error>()
+            && let Some(rustls::Error::InvalidCertificate(reason)) = io_err
+                .get_ref()
+                .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+        {
+            return Some(format!("{reason:?}"));
+        }
+        source = err.source();
+    }
+    None
+}
@@ -0,0 +1,209 @@
+//! Periodic jobs, run by an in-process scheduler.
+//!
+//! Every instance of the backend runs the scheduler, but a Postgres advisory lock ensures that
+//! only one of them runs the jobs at a time. A job is due once the next occurrence of its
+//! schedule after its last run has passed, so occurrences missed while no instance was running
+//! are caught up on the next tick. Every run is recorded in the `job_run` table.
+
+use crate::api::app::AppState;
+use crate::api::wrappers::{check_in, waitlist};
+use crate::db::models::JobRun;
+use crate::db::queries::job_run::{
+    delete_old_job_runs, finish_job_run, get_last_job_run, start_job_run,
+};
+use crate::db::queries::{email_outbox, login_attempt, password_reset_token, session};
+use crate::mail;
+use crate::reminders;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use deadpool_postgres::{Client, Object};
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Interval between two checks for due jobs.
+const TICK: Duration = Duration::from_secs(60);
+
+/// Days during which job runs are kept.
+const JOB_RUN_RETENTION_DAYS: i32 = 30;
+
+/// Days during which sent emails are kept in the outbox.
+const SENT_EMAIL_RETENTION_DAYS: i32 = 30;
+
+/// Arbitrary key of the advisory lock held by the instance running the jobs.
+const SCHEDULER_LOCK_KEY: i64 = 0x6265_6163_685f_6a62;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Job {
+    CleanupExpiredTokens,
+    CleanupExpiredSessions,
+    CleanupOldLoginAttempts,
+    CleanupOldJobRuns,
+    CleanupSentEmails,
+    ExpireWaitlistEntries,
+    MarkNoShows,
+    DeliverEmails,
+    SendReminders,
+}
+
+pub const JOBS: &[Job] = &[
+    Job::CleanupExpiredTokens,
+    Job::CleanupExpiredSessions,
+    Job::CleanupOldLoginAttempts,
+    Job::CleanupOldJobRuns,
+    Job::CleanupSentEmails,
+    Job::ExpireWaitlistEntries,
+    Job::MarkNoShows,
+    Job::DeliverEmails,
+    Job::SendReminders,
+];
+
+impl Job {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Job::CleanupExpiredTokens => "cleanup_expired_tokens",
+            Job::CleanupExpiredSessions => "cleanup_expired_sessions",
+            Job::CleanupOldLoginAttempts => "cleanup_old_login_attempts",
+            Job::CleanupOldJobRuns => "cleanup_old_job_runs",
+            Job::CleanupSentEmails => "cleanup_sent_emails",
+            Job::ExpireWaitlistEntries => "expire_waitlist_entries",
+            Job::MarkNoShows => "mark_no_shows",
+            Job::DeliverEmails => "deliver_emails",
+            Job::SendReminders => "send_reminders",
+        }
+    }
+
+    /// Cron expression (with seconds) of the job, evaluated in UTC.
+    fn expression(&self) -> &'static str {
+        match self {
+            // Every Saturday at midnight
+            Job::CleanupExpiredTokens
+            | Job::CleanupExpiredSessions
+            | Job::CleanupOldLoginAttempts
+            | Job::CleanupOldJobRuns
+            | Job::CleanupSentEmails => "0 0 0 * * Sat",
+            // Every five minutes, claim links being short-lived
+            Job::ExpireWaitlistEntries => "0 */5 * * * *",
+            // Every quarter of an hour, reservations ending on the half hour
+            Job::MarkNoShows => "0 */15 * * * *",
+            // Every minute, on every tick of the scheduler
+            Job::DeliverEmails => "0 * * * * *",
+            // Every five minutes, reminders being sent a while before the start
+            Job::SendReminders => "0 */5 * * * *",
+        }
+    }
+
+    pub fn schedule(&self) -> Schedule {
+        Schedule::from_str(self.expression()).expect("Invalid cron expression.")
+    }
+
+    /// Whether the job should run at `now`, given its last run.
+    pub fn is_due(&self, last_run: Option<&JobRun>, now: DateTime<Utc>) -> bool {
+        match last_run {
+            None => true,
+            Some(run) => self
+                .schedule()
+                .after(&run.started_at)
+                .next()
+                .is_some_and(|next| next <= now),
+        }
+    }
+
+    /// Runs the job and returns the number of affected rows.
+    async fn run(&self, state: &AppState, client: &mut Client) -> Result<u64, JobError> {
+        let rows: u64 = match self {
+            Job::CleanupExpiredTokens => {
+                password_reset_token::delete_expired_tokens(client).await?
+            }
+            Job::CleanupExpiredSessions => session::delete_expired_sessions(client).await?,
+            Job::CleanupOldLoginAttempts => login_attempt::delete_old_attempts(client).await?,
+            Job::CleanupOldJobRuns => delete_old_job_runs(client, JOB_RUN_RETENTION_DAYS).await?,
+            Job::CleanupSentEmails => {
+                email_outbox::delete_sent_emails(client, SENT_EMAIL_RETENTION_DAYS).await?
+            }
+            Job::ExpireWaitlistEntries => waitlist::expire_entries(state, client).await?,
+            Job::MarkNoShows => check_in::mark_no_shows(client, state.config.club_now()).await?,
+            Job::DeliverEmails => mail::deliver_outbox(client, state.mailer.as_ref()).await?,
+            Job::SendReminders => {
+                reminders::send_reminders(state, client, state.config.club_now()).await?
+            }
+        };
+        Ok(rows)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JobError {
+    #[error("database error: {0}")]
+    Db(#[from] tokio_postgres::Error),
+    #[error("could not get a database connection: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+}
+
+/// Runs the jobs that are due at `now` and returns their names. Nothing is run if another
+/// instance holds the scheduler lock.
+pub async fn run_due_jobs(
+    state: &AppState,
+    now: DateTime<Utc>,
+) -> Result<Vec<&'static str>, JobError> {
+    let mut client: Object = state.pool.get().await?;
+
+    let locked: bool = client
+        .query_one("SELECT pg_try_advisory_lock($1)", &[&SCHEDULER_LOCK_KEY])
+        .await?
+        .try_get(0)?;
+    if !locked {
+        return Ok(Vec::new());
+    }
+
+    let result = run_jobs(state, &mut client, now).await;
+
+    client
+        .execute("SELECT pg_advisory_unlock($1)", &[&SCHEDULER_LOCK_KEY])
+        .await?;
+
+    result
+}
+
+async fn run_jobs(
+    state: &AppState,
+    client: &mut Client,
+    now: DateTime<Utc>,
+) -> Result<Vec<&'static str>, JobError> {
+    let mut ran: Vec<&'static str> = Vec::new();
+
+    for job in JOBS {
+        let last_run: Option<JobRun> = get_last_job_run(client, job.name()).await?;
+        if !job.is_due(last_run.as_ref(), now) {
+            continue;
+        }
+
+        let id: i64 = start_job_run(client, job.name()).await?;
+        match job.run(state, client).await {
+            Ok(rows) => {
+                tracing::info!("Job {} affected {} row(s)", job.name(), rows);
+                finish_job_run(client, id, Some(rows as i64), None).await?;
+            }
+            Err(e) => {
+                tracing::error!("Job {} failed: {}", job.name(), e);
+                finish_job_run(client, id, None, Some(&e.to_string())).await?;
+            }
+        }
+        ran.push(job.name());
+    }
+
+    Ok(ran)
+}
+
+/// Checks for due jobs forever.
+pub async fn run_scheduler(state: AppState) {
+    let mut interval = tokio::time::interval(TICK);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+        if let Err(e) = run_due_jobs(&state, Utc::now()).await {
+            tracing::error!("Could not run the scheduled jobs: {}", e);
+        }
+    }
+}
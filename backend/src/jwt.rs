@@ -1,12 +1,12 @@
+use crate::config::JwtConfig;
 use chrono::{Duration, Utc};
-use dotenvy::dotenv;
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
-use std::env;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
+    pub sid: String,
     exp: usize,
     iat: usize,
     pub phone: String,
@@ -14,24 +14,23 @@ pub struct Claims {
     pub is_admin: bool,
 }
 
-fn get_secret() -> String {
-    dotenv().ok();
-    env::var("JWT_SECRET").expect("Undefined JWT_SECRET environment variable")
-}
-
+/// Creates an access token. Its lifetime is short, longer sessions are kept alive with refresh tokens.
 pub fn create_jwt(
+    config: &JwtConfig,
     id: &str,
+    session_id: &str,
     phone: &str,
     is_profile_complete: bool,
     is_admin: bool,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(24))
+        .checked_add_signed(Duration::minutes(config.access_token_ttl_minutes))
         .expect("valid timestamp")
         .timestamp() as usize;
 
     let claims = Claims {
         sub: id.to_string(),
+        sid: session_id.to_string(),
         exp: expiration,
         iat: Utc::now().timestamp() as usize,
         phone: phone.to_string(),
@@ -42,14 +41,14 @@ pub fn create_jwt(
     encode(
         &Header::default(),
         &claims,
-        &EncodingKey::from_secret(get_secret().as_ref()),
+        &EncodingKey::from_secret(config.secret.as_ref()),
     )
 }
 
-pub fn verify_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+pub fn verify_jwt(config: &JwtConfig, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
     decode::<Claims>(
         token,
-        &DecodingKey::from_secret(get_secret().as_ref()),
+        &DecodingKey::from_secret(config.secret.as_ref()),
         &Validation::default(),
     )
     .map(|data| data.claims)
@@ -1,21 +1,54 @@
+pub mod calendar;
+pub mod config;
+pub mod jobs;
 pub mod jwt;
+pub mod mail;
+pub mod planning;
+pub mod reminders;
+pub mod rules;
+pub mod sms;
 pub mod utils;
 
 pub mod api {
     pub mod app;
     pub mod auth;
     pub mod email;
+    pub mod login_guard;
     pub mod wrappers {
+        pub mod availability;
+        pub mod booking_rules;
+        pub mod check_in;
+        pub mod court;
+        pub mod court_block;
+        pub mod email_outbox;
         pub mod member;
+        pub mod opening_hours;
+        pub mod participant;
         pub mod reservation;
+        pub mod reservation_series;
+        pub mod waitlist;
     }
 }
 
 pub mod db {
+    pub mod migrations;
     pub mod models;
     pub mod queries {
+        pub mod booking_rules;
+        pub mod court;
+        pub mod court_block;
+        pub mod email_outbox;
+        pub mod job_run;
+        pub mod login_attempt;
         pub mod member;
+        pub mod opening_hours;
+        pub mod participant;
         pub mod password_reset_token;
+        pub mod reminder;
         pub mod reservation;
+        pub mod reservation_series;
+        pub mod session;
+        pub mod waitlist;
     }
+    pub mod tls;
 }
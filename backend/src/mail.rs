@@ -0,0 +1,221 @@
+use crate::config::{MailTransport, SmtpConfig};
+use crate::db::models::OutboxEmail;
+use crate::db::queries::email_outbox;
+use async_trait::async_trait;
+use lettre::message::MultiPart;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio_postgres::Client;
+use uuid::Uuid;
+
+pub const MAX_ATTEMPTS: i32 = 5;
+// Doubled after every failed attempt
+const FIRST_RETRY_SECONDS: i64 = 60;
+const BATCH_SIZE: i64 = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub html_body: String,
+    pub text_body: String,
+    pub attachment: Option<Attachment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Attachment {
+    pub filename: String,
+    pub content_type: String,
+    pub content: String,
+}
+
+#[derive(Debug, Error)]
+pub enum MailError {
+    #[error("invalid email: {0}")]
+    Build(String),
+    #[error("could not send the email over SMTP: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("could not write the email: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+pub fn build_mailer(transport: &MailTransport, smtp: &SmtpConfig) -> Arc<dyn Mailer> {
+    match transport {
+        MailTransport::Smtp => Arc::new(SmtpMailer::new(smtp)),
+        MailTransport::File(dir) => Arc::new(FileMailer::new(dir.clone(), &smtp.sender)),
+        MailTransport::Memory => Arc::new(MemoryMailer::default()),
+    }
+}
+
+fn to_message(sender: &str, email: &Email) -> Result<Message, MailError> {
+    let body = MultiPart::alternative_plain_html(email.text_body.clone(), email.html_body.clone());
+    let body: MultiPart = match &email.attachment {
+        None => body,
+        Some(attachment) => MultiPart::mixed().multipart(body).singlepart(
+            lettre::message::Attachment::new(attachment.filename.clone()).body(
+                attachment.content.clone(),
+                ContentType::parse(&attachment.content_type)
+                    .map_err(|e| MailError::Build(e.to_string()))?,
+            ),
+        ),
+    };
+
+    Message::builder()
+        .from(
+            sender
+                .parse()
+                .map_err(|e| MailError::Build(format!("{e}")))?,
+        )
+        .to(email
+            .to
+            .parse()
+            .map_err(|e| MailError::Build(format!("{e}")))?)
+        .subject(&email.subject)
+        .multipart(body)
+        .map_err(|e| MailError::Build(e.to_string()))
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    sender: String,
+}
+
+impl SmtpMailer {
+    pub fn new(smtp: &SmtpConfig) -> Self {
+        let creds = Credentials::new(smtp.user.clone(), smtp.password.clone());
+
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.server)
+            .expect("Invalid SMTP server.")
+            .credentials(creds)
+            .build();
+
+        Self {
+            transport,
+            sender: smtp.sender.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        self.transport
+            .send(to_message(&self.sender, email)?)
+            .await?;
+        Ok(())
+    }
+}
+
+// Writes every email to its own .eml file, for local development
+pub struct FileMailer {
+    dir: PathBuf,
+    sender: String,
+}
+
+impl FileMailer {
+    pub fn new(dir: PathBuf, sender: &str) -> Self {
+        Self {
+            dir,
+            sender: sender.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path: PathBuf = self.dir.join(format!("{}.eml", Uuid::new_v4()));
+        tokio::fs::write(&path, to_message(&self.sender, email)?.formatted()).await?;
+        tracing::info!("Email to {} written to {}", email.to, path.display());
+        Ok(())
+    }
+}
+
+// Keeps the sent emails for the tests
+#[derive(Default)]
+pub struct MemoryMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl MemoryMailer {
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().expect("Mailer lock poisoned.").clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for MemoryMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        self.sent
+            .lock()
+            .expect("Mailer lock poisoned.")
+            .push(email.clone());
+        Ok(())
+    }
+}
+
+impl From<OutboxEmail> for Email {
+    fn from(email: OutboxEmail) -> Self {
+        Self {
+            to: email.recipient,
+            subject: email.subject,
+            html_body: email.html_body,
+            text_body: email.text_body,
+            attachment: email.attachment,
+        }
+    }
+}
+
+pub fn retry_delay_seconds(attempts: i32) -> Option<i64> {
+    (attempts < MAX_ATTEMPTS).then(|| FIRST_RETRY_SECONDS << (attempts - 1).clamp(0, 16))
+}
+
+// Emails are queued in the outbox by the actions sending them, and delivered by a job
+pub async fn deliver_outbox(
+    client: &Client,
+    mailer: &dyn Mailer,
+) -> Result<u64, tokio_postgres::Error> {
+    let mut sent: u64 = 0;
+
+    for queued in email_outbox::claim_due_emails(client, BATCH_SIZE).await? {
+        let (id, attempts): (i64, i32) = (queued.id, queued.attempts);
+        match mailer.send(&queued.into()).await {
+            Ok(()) => {
+                email_outbox::mark_sent(client, id).await?;
+                sent += 1;
+            }
+            Err(e) => {
+                let retry_in: Option<i64> = retry_delay_seconds(attempts);
+                match retry_in {
+                    Some(seconds) => tracing::warn!(
+                        "Attempt {} to deliver email {} failed, retrying in {}s: {}",
+                        attempts,
+                        id,
+                        seconds,
+                        e
+                    ),
+                    None => tracing::error!(
+                        "Giving up on email {} after {} attempts: {}",
+                        id,
+                        attempts,
+                        e
+                    ),
+                }
+                email_outbox::mark_attempt_failed(client, id, &e.to_string(), retry_in).await?;
+            }
+        }
+    }
+
+    Ok(sent)
+}
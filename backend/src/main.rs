@@ -1,12 +1,12 @@
 use backend::api::app::{AppState, build_state, router};
+use backend::config::Config;
+use backend::jobs::{run_due_jobs, run_scheduler};
+use chrono::Utc;
 use lambda_http::Error;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[cfg(feature = "local")]
-use {
-    dotenvy::dotenv,
-    std::{env, net::SocketAddr},
-};
+use std::net::SocketAddr;
 
 #[cfg(not(feature = "local"))]
 use lambda_http::run;
@@ -35,22 +35,45 @@ async fn main() -> Result<(), Error> {
             .init();
     }
 
+    let mut config: Config =
+        Config::load().inspect_err(|e| tracing::error!("Invalid configuration: {}", e))?;
+
+    // `backend migrate` applies the pending migrations and exits
+    if std::env::args().nth(1).as_deref() == Some("migrate") {
+        config.run_migrations = true;
+        build_state(config)
+            .await
+            .inspect_err(|e| tracing::error!("Migration failed: {}", e))?;
+        return Ok(());
+    }
+
+    // `backend run-jobs` runs the jobs that are due and exits
+    if std::env::args().nth(1).as_deref() == Some("run-jobs") {
+        let app_state: AppState = build_state(config)
+            .await
+            .inspect_err(|e| tracing::error!("Startup failed: {}", e))?;
+        let ran: Vec<&str> = run_due_jobs(&app_state, Utc::now())
+            .await
+            .inspect_err(|e| tracing::error!("Could not run the scheduled jobs: {}", e))?;
+        tracing::info!("Ran {} job(s): {:?}", ran.len(), ran);
+        return Ok(());
+    }
+
     #[cfg(feature = "local")]
     {
-        dotenv().ok();
-
         tracing::info!("Starting backend in local mode");
 
-        let api_ip: &str = &env::var("API_IP").expect("Undefined API_IP environment variable");
-        let api_port: u16 =
-            str::parse(&env::var("API_PORT").expect("Undefined API_PORT environment variable"))
-                .unwrap();
-
-        let app_state: AppState = build_state().await;
-        let app = router(app_state).await;
-        let addr: SocketAddr = format!("{api_ip}:{api_port}")
+        let addr: SocketAddr = format!("{}:{}", config.api_ip, config.api_port)
             .parse()
             .expect("Invalid address");
+
+        let app_state: AppState = build_state(config)
+            .await
+            .inspect_err(|e| tracing::error!("Startup failed: {}", e))?;
+        if app_state.config.scheduler_enabled {
+            tokio::spawn(run_scheduler(app_state.clone()));
+        }
+        let app = router(app_state).await;
         let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
 
         tracing::info!("Listening on {}", addr);
@@ -62,7 +85,12 @@ async fn main() -> Result<(), Error> {
     {
         tracing::info!("Starting backend in production mode");
 
-        let app_state: AppState = build_state().await;
+        let app_state: AppState = build_state(config)
+            .await
+            .inspect_err(|e| tracing::error!("Startup failed: {}", e))?;
+        if app_state.config.scheduler_enabled {
+            tokio::spawn(run_scheduler(app_state.clone()));
+        }
         let app = router(app_state).await;
         run(app).await?;
     }
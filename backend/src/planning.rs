@@ -0,0 +1,302 @@
+//! Opening hours of the club and availability of its courts.
+
+use crate::db::models::{Court, CourtBlock, OpeningHours, Reservation, ReservationSeries};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Maximum number of days covered by a single availability request.
+pub const MAX_AVAILABILITY_DAYS: i64 = 31;
+
+/// Allowed durations of a reservation, in minutes.
+pub const DURATIONS: [i16; 4] = [30, 60, 90, 120];
+
+/// Maximum number of members and guests playing with the member who booked a court.
+pub const MAX_PARTICIPANTS: usize = 3;
+
+/// Maximum number of occurrences of a reservation series.
+pub const MAX_OCCURRENCES: usize = 52;
+
+/// A bookable slot of a court.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Slot {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl OpeningHours {
+    fn applies_to(&self, date: NaiveDate, court_number: i16) -> bool {
+        self.weekday as u32 == date.weekday().number_from_monday()
+            && self
+                .court_number
+                .is_none_or(|number| number == court_number)
+            && self.valid_from.is_none_or(|from| from <= date)
+            && self.valid_until.is_none_or(|until| date <= until)
+    }
+
+    fn is_seasonal(&self) -> bool {
+        self.valid_from.is_some() || self.valid_until.is_some()
+    }
+
+    fn slots(&self) -> Vec<Slot> {
+        let (Some(opens_at), Some(closes_at)) = (self.opens_at, self.closes_at) else {
+            return Vec::new();
+        };
+        if self.is_closed || self.slot_minutes <= 0 {
+            return Vec::new();
+        }
+
+        let length = Duration::minutes(self.slot_minutes.into());
+        let mut slots: Vec<Slot> = Vec::new();
+        let mut start: NaiveTime = opens_at;
+        loop {
+            let (end, wrapped) = start.overflowing_add_signed(length);
+            if wrapped != 0 || end > closes_at {
+                break;
+            }
+            slots.push(Slot { start, end });
+            start = end;
+        }
+        slots
+    }
+}
+
+/// Bookable slots of `court_number` on `date`, empty when the court is closed.
+///
+/// Only the most specific opening hours apply: seasonal hours take precedence over permanent
+/// ones, then hours of the court over those of the whole club.
+pub fn slots(date: NaiveDate, court_number: i16, opening_hours: &[OpeningHours]) -> Vec<Slot> {
+    let applicable: Vec<&OpeningHours> = opening_hours
+        .iter()
+        .filter(|hours| hours.applies_to(date, court_number))
+        .collect();
+
+    let precedence = |hours: &OpeningHours| (hours.is_seasonal(), hours.court_number.is_some());
+    let Some(highest) = applicable.iter().map(|hours| precedence(hours)).max() else {
+        return Vec::new();
+    };
+
+    let mut slots: Vec<Slot> = applicable
+        .into_iter()
+        .filter(|hours| precedence(hours) == highest)
+        .flat_map(OpeningHours::slots)
+        .collect();
+    slots.sort();
+    slots.dedup();
+    slots
+}
+
+impl Reservation {
+    /// End of the reservation, `None` if it would end after midnight.
+    pub fn end_time(&self) -> Option<NaiveTime> {
+        let (end, wrapped) = self
+            .start_time
+            .overflowing_add_signed(Duration::minutes(self.duration_minutes.into()));
+        (wrapped == 0).then_some(end)
+    }
+
+    fn overlaps(&self, date: NaiveDate, court_number: i16, slot: Slot) -> bool {
+        self.reservation_date == date
+            && self.court_number == court_number
+            && self.start_time < slot.end
+            && self.end_time().is_none_or(|end| slot.start < end)
+    }
+}
+
+/// Whether the reservation starts at the start of a slot of its court and ends before the
+/// court closes, possibly spanning consecutive slots.
+pub fn fits_opening_hours(reservation: &Reservation, opening_hours: &[OpeningHours]) -> bool {
+    let Some(end) = reservation.end_time() else {
+        return false;
+    };
+
+    let mut covered_until: Option<NaiveTime> = None;
+    for slot in slots(
+        reservation.reservation_date,
+        reservation.court_number,
+        opening_hours,
+    ) {
+        covered_until = match covered_until {
+            None if slot.start == reservation.start_time => Some(slot.end),
+            Some(until) if slot.start == until => Some(slot.end),
+            other => other,
+        };
+        if covered_until.is_some_and(|until| end <= until) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Whether the court of `candidate` is free for its whole duration, given the other
+/// reservations of the day.
+pub fn is_free(candidate: &Reservation, reservations: &[Reservation]) -> bool {
+    let Some(end) = candidate.end_time() else {
+        return false;
+    };
+    let slot = Slot {
+        start: candidate.start_time,
+        end,
+    };
+    !reservations.iter().any(|r| {
+        r.id != candidate.id && r.overlaps(candidate.reservation_date, candidate.court_number, slot)
+    })
+}
+
+impl CourtBlock {
+    fn applies_to(&self, court_number: i16) -> bool {
+        self.court_numbers
+            .as_ref()
+            .is_none_or(|numbers| numbers.contains(&court_number))
+    }
+
+    /// Start and end of every occurrence of the block.
+    pub fn periods(&self) -> impl Iterator<Item = (NaiveDateTime, NaiveDateTime)> + '_ {
+        let length: Duration = self.ends_at - self.starts_at;
+        let step: Option<Duration> = self
+            .interval_weeks
+            .map(|weeks| Duration::weeks(weeks.into()));
+        std::iter::successors(Some(self.starts_at), move |start| {
+            start.checked_add_signed(step?)
+        })
+        .take_while(|start| self.until_date.is_none_or(|until| start.date() <= until))
+        .map(move |start| (start, start + length))
+    }
+
+    /// Whether the block covers part of `court_number` between `start` and `end`.
+    pub fn overlaps(&self, court_number: i16, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+        self.applies_to(court_number)
+            && self
+                .periods()
+                .take_while(|(block_start, _)| *block_start < end)
+                .any(|(_, block_end)| start < block_end)
+    }
+}
+
+/// The first of `blocks` covering part of `reservation`, if any.
+pub fn blocking<'a>(reservation: &Reservation, blocks: &'a [CourtBlock]) -> Option<&'a CourtBlock> {
+    let start: NaiveDateTime = reservation
+        .reservation_date
+        .and_time(reservation.start_time);
+    let end: NaiveDateTime = start + Duration::minutes(reservation.duration_minutes.into());
+    blocks
+        .iter()
+        .find(|block| block.overlaps(reservation.court_number, start, end))
+}
+
+/// Dates of the occurrences of a series, `None` if it has none or more than
+/// [`MAX_OCCURRENCES`].
+pub fn occurrence_dates(series: &ReservationSeries) -> Option<Vec<NaiveDate>> {
+    let step = Duration::weeks(series.interval_weeks.into());
+    let all_dates = std::iter::successors(Some(series.first_date), |date| {
+        date.checked_add_signed(step)
+    });
+
+    let dates: Vec<NaiveDate> = match (series.until_date, series.occurrence_count) {
+        (Some(until), None) => all_dates
+            .take_while(|date| *date <= until)
+            .take(MAX_OCCURRENCES + 1)
+            .collect(),
+        (None, Some(count)) if count > 0 => all_dates.take(count as usize).collect(),
+        _ => return None,
+    };
+
+    (!dates.is_empty() && dates.len() <= MAX_OCCURRENCES).then_some(dates)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlotStatus {
+    Free,
+    Booked,
+    /// The slot cannot be booked, because it has already started or the court is closed or
+    /// blocked.
+    Blocked,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CourtSlot {
+    pub court_number: i16,
+    pub status: SlotStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeSlots {
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    /// Courts open during this slot.
+    pub courts: Vec<CourtSlot>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayAvailability {
+    pub date: NaiveDate,
+    pub slots: Vec<TimeSlots>,
+}
+
+/// Computes the status of every slot of `courts` between `from` and `to` included, `now` being
+/// the current time of the club.
+pub fn availability(
+    from: NaiveDate,
+    to: NaiveDate,
+    courts: &[Court],
+    opening_hours: &[OpeningHours],
+    reservations: &[Reservation],
+    blocks: &[CourtBlock],
+    now: NaiveDateTime,
+) -> Vec<DayAvailability> {
+    from.iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| {
+            let mut by_slot: BTreeMap<Slot, Vec<CourtSlot>> = BTreeMap::new();
+            for court in courts {
+                for slot in slots(date, court.number, opening_hours) {
+                    by_slot.entry(slot).or_default().push(CourtSlot {
+                        court_number: court.number,
+                        status: slot_status(date, slot, court, reservations, blocks, now),
+                    });
+                }
+            }
+
+            DayAvailability {
+                date,
+                slots: by_slot
+                    .into_iter()
+                    .map(|(slot, courts)| TimeSlots {
+                        start_time: slot.start,
+                        end_time: slot.end,
+                        courts,
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn slot_status(
+    date: NaiveDate,
+    slot: Slot,
+    court: &Court,
+    reservations: &[Reservation],
+    blocks: &[CourtBlock],
+    now: NaiveDateTime,
+) -> SlotStatus {
+    let booked: bool = reservations
+        .iter()
+        .any(|r| r.overlaps(date, court.number, slot));
+    let blocked: bool = blocks.iter().any(|block| {
+        block.overlaps(
+            court.number,
+            date.and_time(slot.start),
+            date.and_time(slot.end),
+        )
+    });
+
+    if booked {
+        SlotStatus::Booked
+    } else if !court.is_active || blocked || date.and_time(slot.start) <= now {
+        SlotStatus::Blocked
+    } else {
+        SlotStatus::Free
+    }
+}
@@ -0,0 +1,102 @@
+use crate::api::app::AppState;
+use crate::api::email::{self, EmailService};
+use crate::db::models::{Member, ReminderChannel, Reservation};
+use crate::db::queries::{member, reminder};
+use crate::sms::{Sms, SmsGateway};
+use chrono::{Duration, NaiveDateTime};
+use deadpool_postgres::{Client, Transaction};
+use tokio_postgres::Error;
+
+fn starts_at(reservation: &Reservation) -> NaiveDateTime {
+    reservation
+        .reservation_date
+        .and_time(reservation.start_time)
+}
+
+// Reminders are recorded in the transaction sending them, so that reruns do not send them twice
+pub async fn send_reminders(
+    state: &AppState,
+    client: &mut Client,
+    now: NaiveDateTime,
+) -> Result<u64, Error> {
+    let until: NaiveDateTime = now + Duration::minutes(state.config.reminder_lead_minutes);
+    let mut sent: u64 = send_email_reminders(state, client, &now, &until).await?;
+    if let Some(gateway) = &state.sms {
+        sent += send_sms_reminders(gateway.as_ref(), client, &now, &until).await?;
+    }
+    Ok(sent)
+}
+
+async fn send_email_reminders(
+    state: &AppState,
+    client: &mut Client,
+    from: &NaiveDateTime,
+    to: &NaiveDateTime,
+) -> Result<u64, Error> {
+    let mut sent: u64 = 0;
+
+    for reservation in reminder::get_due_reminders(client, ReminderChannel::Email, from, to).await?
+    {
+        let transaction: Transaction = client.transaction().await?;
+        if reminder::record_reminder(
+            &transaction,
+            &reservation.id,
+            ReminderChannel::Email,
+            &starts_at(&reservation),
+        )
+        .await?
+        {
+            let member: Member = member::get_member(&transaction, &reservation.member_id).await?;
+            EmailService::new(&transaction, &state.config)
+                .send_reminder_email(&member, &reservation)
+                .await?;
+            sent += 1;
+        }
+        transaction.commit().await?;
+    }
+
+    Ok(sent)
+}
+
+async fn send_sms_reminders(
+    gateway: &dyn SmsGateway,
+    client: &mut Client,
+    from: &NaiveDateTime,
+    to: &NaiveDateTime,
+) -> Result<u64, Error> {
+    let mut sent: u64 = 0;
+
+    for reservation in reminder::get_due_reminders(client, ReminderChannel::Sms, from, to).await? {
+        let transaction: Transaction = client.transaction().await?;
+        if !reminder::record_reminder(
+            &transaction,
+            &reservation.id,
+            ReminderChannel::Sms,
+            &starts_at(&reservation),
+        )
+        .await?
+        {
+            continue;
+        }
+
+        let member: Member = member::get_member(&transaction, &reservation.member_id).await?;
+        let sms = Sms {
+            to: member.phone.clone(),
+            body: email::reminder_sms(&member, &reservation),
+        };
+        // Not recorded if the gateway fails, so that the next run retries it
+        match gateway.send(&sms).await {
+            Ok(()) => {
+                transaction.commit().await?;
+                sent += 1;
+            }
+            Err(e) => tracing::warn!(
+                "Could not send the reminder of reservation {} by text message: {}",
+                reservation.id,
+                e
+            ),
+        }
+    }
+
+    Ok(sent)
+}
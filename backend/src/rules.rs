@@ -0,0 +1,200 @@
+//! Booking rules limiting the reservations of members, administrators being exempt.
+
+use crate::db::models::{BookingRules, Reservation};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Serialize, Serializer, ser::SerializeStruct};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleViolation {
+    MaxPerDay {
+        limit: i16,
+    },
+    MaxPerWeek {
+        limit: i16,
+    },
+    MaxDaysInAdvance {
+        limit: i16,
+    },
+    MinNotice {
+        minutes: i32,
+    },
+    MaxPeakPerWeek {
+        limit: i16,
+        starts_at: NaiveTime,
+        ends_at: NaiveTime,
+    },
+}
+
+impl RuleViolation {
+    /// Name of the rule, as in the `booking_rules` table.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            RuleViolation::MaxPerDay { .. } => "max_per_day",
+            RuleViolation::MaxPerWeek { .. } => "max_per_week",
+            RuleViolation::MaxDaysInAdvance { .. } => "max_days_in_advance",
+            RuleViolation::MinNotice { .. } => "min_notice_minutes",
+            RuleViolation::MaxPeakPerWeek { .. } => "max_peak_per_week",
+        }
+    }
+
+    /// Whether the rule limits the number of reservations, the only rules participants are
+    /// held to.
+    pub fn is_quota(&self) -> bool {
+        matches!(
+            self,
+            RuleViolation::MaxPerDay { .. }
+                | RuleViolation::MaxPerWeek { .. }
+                | RuleViolation::MaxPeakPerWeek { .. }
+        )
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            RuleViolation::MaxPerDay { limit } => {
+                format!("You can book at most {limit} reservation(s) per day")
+            }
+            RuleViolation::MaxPerWeek { limit } => {
+                format!("You can book at most {limit} reservation(s) per week")
+            }
+            RuleViolation::MaxDaysInAdvance { limit } => {
+                format!("You can book at most {limit} day(s) in advance")
+            }
+            RuleViolation::MinNotice { minutes } => {
+                format!("You must book at least {minutes} minute(s) before the start")
+            }
+            RuleViolation::MaxPeakPerWeek {
+                limit,
+                starts_at,
+                ends_at,
+            } => format!(
+                "You can book at most {limit} reservation(s) per week between {} and {}",
+                starts_at.format("%H:%M"),
+                ends_at.format("%H:%M")
+            ),
+        }
+    }
+}
+
+impl Serialize for RuleViolation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("RuleViolation", 2)?;
+        state.serialize_field("rule", self.rule())?;
+        state.serialize_field("message", &self.message())?;
+        state.end()
+    }
+}
+
+/// Monday and Sunday of the ISO week of `date`.
+pub fn week_of(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let monday: NaiveDate = date - Duration::days(date.weekday().num_days_from_monday().into());
+    (monday, monday + Duration::days(6))
+}
+
+impl BookingRules {
+    fn minutes_before_start(reservation: &Reservation, now: NaiveDateTime) -> i64 {
+        (reservation
+            .reservation_date
+            .and_time(reservation.start_time)
+            - now)
+            .num_minutes()
+    }
+
+    /// Whether members can still cancel `reservation` themselves at `now`.
+    pub fn can_cancel(&self, reservation: &Reservation, now: NaiveDateTime) -> bool {
+        self.cancellation_cutoff_minutes
+            .is_none_or(|cutoff| Self::minutes_before_start(reservation, now) >= cutoff.into())
+    }
+
+    /// Whether cancelling `reservation` at `now` is late, which is also the case past the
+    /// cancellation cutoff, when an administrator cancels on behalf of the member.
+    pub fn is_late_cancellation(&self, reservation: &Reservation, now: NaiveDateTime) -> bool {
+        let before: i64 = Self::minutes_before_start(reservation, now);
+        [
+            self.late_cancellation_minutes,
+            self.cancellation_cutoff_minutes,
+        ]
+        .into_iter()
+        .flatten()
+        .any(|minutes| before < minutes.into())
+    }
+
+    /// Whether members can check in for `reservation` themselves at `now`.
+    pub fn can_check_in(&self, reservation: &Reservation, now: NaiveDateTime) -> bool {
+        let before: i64 = Self::minutes_before_start(reservation, now);
+        self.check_in_opens_minutes
+            .is_none_or(|opens| before <= opens.into())
+            && self
+                .check_in_closes_minutes
+                .is_none_or(|closes| -before <= closes.into())
+    }
+
+    fn is_peak(&self, reservation: &Reservation) -> bool {
+        let (Some(starts_at), Some(ends_at)) = (self.peak_starts_at, self.peak_ends_at) else {
+            return false;
+        };
+        reservation.start_time < ends_at && reservation.end_time().is_none_or(|end| starts_at < end)
+    }
+
+    /// Rules broken by `reservation`, given the other reservations of its member during its
+    /// week and the current time of the club.
+    pub fn check(
+        &self,
+        reservation: &Reservation,
+        week_reservations: &[Reservation],
+        now: NaiveDateTime,
+    ) -> Vec<RuleViolation> {
+        let (monday, sunday) = week_of(reservation.reservation_date);
+        let others: Vec<&Reservation> = week_reservations
+            .iter()
+            .filter(|r| r.id != reservation.id && (monday..=sunday).contains(&r.reservation_date))
+            .collect();
+        let mut violations: Vec<RuleViolation> = Vec::new();
+
+        if let Some(limit) = self.max_per_day {
+            let same_day: usize = others
+                .iter()
+                .filter(|r| r.reservation_date == reservation.reservation_date)
+                .count();
+            if same_day >= limit as usize {
+                violations.push(RuleViolation::MaxPerDay { limit });
+            }
+        }
+
+        if let Some(limit) = self.max_per_week
+            && others.len() >= limit as usize
+        {
+            violations.push(RuleViolation::MaxPerWeek { limit });
+        }
+
+        if let Some(limit) = self.max_days_in_advance
+            && reservation.reservation_date > now.date() + Duration::days(limit.into())
+        {
+            violations.push(RuleViolation::MaxDaysInAdvance { limit });
+        }
+
+        if let Some(minutes) = self.min_notice_minutes
+            && reservation
+                .reservation_date
+                .and_time(reservation.start_time)
+                < now + Duration::minutes(minutes.into())
+        {
+            violations.push(RuleViolation::MinNotice { minutes });
+        }
+
+        if let (Some(limit), Some(starts_at), Some(ends_at)) = (
+            self.max_peak_per_week,
+            self.peak_starts_at,
+            self.peak_ends_at,
+        ) && self.is_peak(reservation)
+            && others.iter().filter(|r| self.is_peak(r)).count() >= limit as usize
+        {
+            violations.push(RuleViolation::MaxPeakPerWeek {
+                limit,
+                starts_at,
+                ends_at,
+            });
+        }
+
+        violations
+    }
+}
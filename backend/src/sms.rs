@@ -0,0 +1,65 @@
+use crate::config::SmsTransport;
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sms {
+    pub to: String,
+    pub body: String,
+}
+
+#[derive(Debug, Error)]
+pub enum SmsError {
+    #[error("the gateway rejected the text message: {0}")]
+    Rejected(String),
+    #[error("could not reach the gateway: {0}")]
+    Unreachable(String),
+}
+
+// Unlike emails, text messages are not queued: reminders are pointless once late
+#[async_trait]
+pub trait SmsGateway: Send + Sync {
+    async fn send(&self, sms: &Sms) -> Result<(), SmsError>;
+}
+
+pub fn build_sms_gateway(transport: &SmsTransport) -> Option<Arc<dyn SmsGateway>> {
+    match transport {
+        SmsTransport::Disabled => None,
+        SmsTransport::Log => Some(Arc::new(LogSmsGateway)),
+        SmsTransport::Memory => Some(Arc::new(MemorySmsGateway::default())),
+    }
+}
+
+pub struct LogSmsGateway;
+
+#[async_trait]
+impl SmsGateway for LogSmsGateway {
+    async fn send(&self, sms: &Sms) -> Result<(), SmsError> {
+        tracing::info!("Text message to {}: {}", sms.to, sms.body);
+        Ok(())
+    }
+}
+
+// Keeps the sent text messages for the tests
+#[derive(Default)]
+pub struct MemorySmsGateway {
+    sent: Mutex<Vec<Sms>>,
+}
+
+impl MemorySmsGateway {
+    pub fn sent(&self) -> Vec<Sms> {
+        self.sent.lock().expect("Gateway lock poisoned.").clone()
+    }
+}
+
+#[async_trait]
+impl SmsGateway for MemorySmsGateway {
+    async fn send(&self, sms: &Sms) -> Result<(), SmsError> {
+        self.sent
+            .lock()
+            .expect("Gateway lock poisoned.")
+            .push(sms.clone());
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ use argon2::{
 use rand::distr::uniform;
 use rand::seq::SliceRandom;
 use rand::{Rng, distr::Uniform, prelude::Distribution, rng};
+use sha2::{Digest, Sha256};
 
 /// Generates an 8-character random string containing at least one digit and one uppercase letter.
 pub fn gen_id() -> Result<String, uniform::Error> {
@@ -49,6 +50,27 @@ pub fn gen_otp() -> Result<String, uniform::Error> {
     Ok(otp.iter().map(|&b| b as char).collect())
 }
 
+/// Generates an opaque refresh token made of 32 random bytes, hex encoded.
+pub fn gen_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    rng().fill(&mut bytes);
+    to_hex(&bytes)
+}
+
+/// Hashes a refresh token with SHA-256 so that only its digest is stored in the database.
+pub fn hash_token(token: &str) -> String {
+    sha256_hex(token.as_bytes())
+}
+
+/// Returns the SHA-256 digest of `data`, hex encoded.
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
 pub fn hash_password(password: &String) -> Result<String, argon2::Error> {
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
@@ -69,10 +91,10 @@ pub fn is_valid_argon2id(hash: &str) -> bool {
         return false;
     }
 
-    if let Some(v) = parsed.version {
-        if v.to_string() != "19" {
-            return false;
-        }
+    if let Some(v) = parsed.version
+        && v.to_string() != "19"
+    {
+        return false;
     }
 
     let has_m = parsed.params.iter().any(|(id, _)| id.as_str() == "m");
@@ -0,0 +1,334 @@
+mod common;
+
+use axum_test::{TestResponse, TestServer};
+use backend::db::queries;
+use backend::mail::{Email, MemoryMailer, deliver_outbox};
+use common::{add_member_request, admin_token, create_test_server, create_test_server_with_mailer};
+use deadpool_postgres::{Client, Pool, Transaction};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use testcontainers::{ContainerAsync, GenericImage};
+
+#[derive(Deserialize)]
+struct AddMemberResponse {
+    otp: String,
+}
+
+#[derive(Deserialize)]
+struct TokensResponse {
+    token: String,
+    refresh_token: String,
+}
+
+async fn add_member_and_get_otp(server: &TestServer) -> Result<String, anyhow::Error> {
+    let add_member_res: TestResponse = add_member_request(server).await?;
+    let response_body: AddMemberResponse = add_member_res.json();
+    Ok(response_body.otp)
+}
+
+async fn login_request(server: &TestServer, password: &str) -> TestResponse {
+    server
+        .post("/login")
+        .json(&json!({ "phone": "0123456789", "password": password }))
+        .await
+}
+
+async fn refresh_request(server: &TestServer, refresh_token: &str) -> TestResponse {
+    server
+        .post("/refresh-jwt")
+        .json(&json!({ "refresh_token": refresh_token }))
+        .await
+}
+
+#[tokio::test]
+async fn refresh_token_rotation() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let otp: String = add_member_and_get_otp(&server).await?;
+    let login_res: TestResponse = login_request(&server, &otp).await;
+    login_res.assert_status_ok();
+    let tokens: TokensResponse = login_res.json();
+
+    // Rotating the refresh token extends the session by its TTL
+    let client = pool.get().await?;
+    client
+        .execute(
+            "UPDATE session SET expires_at = NOW() + INTERVAL '1 day'",
+            &[],
+        )
+        .await?;
+
+    let refresh_res: TestResponse = refresh_request(&server, &tokens.refresh_token).await;
+    refresh_res.assert_status_ok();
+    let new_tokens: TokensResponse = refresh_res.json();
+    assert_ne!(new_tokens.refresh_token, tokens.refresh_token);
+    let extended: bool = client
+        .query_one(
+            "SELECT expires_at > NOW() + INTERVAL '29 days' FROM session",
+            &[],
+        )
+        .await?
+        .get(0);
+    assert!(extended);
+
+    let verify_res: TestResponse = server
+        .get("/verify-token")
+        .authorization_bearer(&new_tokens.token)
+        .await;
+    verify_res.assert_status_ok();
+
+    // A rotated refresh token cannot be used twice, and its reuse revokes the session
+    let reuse_res: TestResponse = refresh_request(&server, &tokens.refresh_token).await;
+    reuse_res.assert_status_unauthorized();
+    refresh_request(&server, &new_tokens.refresh_token)
+        .await
+        .assert_status_unauthorized();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn logout_revokes_session() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let otp: String = add_member_and_get_otp(&server).await?;
+    let tokens: TokensResponse = login_request(&server, &otp).await.json();
+
+    let logout_res: TestResponse = server
+        .post("/logout")
+        .authorization_bearer(&tokens.token)
+        .await;
+    logout_res.assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    let refresh_res: TestResponse = refresh_request(&server, &tokens.refresh_token).await;
+    refresh_res.assert_status_unauthorized();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn logout_all_revokes_every_session() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let otp: String = add_member_and_get_otp(&server).await?;
+    let phone_tokens: TokensResponse = login_request(&server, &otp).await.json();
+    let laptop_tokens: TokensResponse = login_request(&server, &otp).await.json();
+
+    let logout_all_res: TestResponse = server
+        .post("/logout-all")
+        .authorization_bearer(&phone_tokens.token)
+        .await;
+    logout_all_res.assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    refresh_request(&server, &phone_tokens.refresh_token)
+        .await
+        .assert_status_unauthorized();
+    refresh_request(&server, &laptop_tokens.refresh_token)
+        .await
+        .assert_status_unauthorized();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn password_change_revokes_other_sessions() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let otp: String = add_member_and_get_otp(&server).await?;
+    let phone_tokens: TokensResponse = login_request(&server, &otp).await.json();
+    let laptop_tokens: TokensResponse = login_request(&server, &otp).await.json();
+
+    let update_password_res: TestResponse = server
+        .patch("/password")
+        .authorization_bearer(&phone_tokens.token)
+        .json(&json!({
+            "id": "AB1234",
+            "current_password": otp,
+            "new_password": "new-password",
+        }))
+        .await;
+    update_password_res.assert_status_ok();
+
+    refresh_request(&server, &laptop_tokens.refresh_token)
+        .await
+        .assert_status_unauthorized();
+    refresh_request(&server, &phone_tokens.refresh_token)
+        .await
+        .assert_status_ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn repeated_failures_lock_account() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    let client = pool.get().await?;
+
+    let otp: String = add_member_and_get_otp(&server).await?;
+
+    for failures in 0..5 {
+        // After two failures, attempts made too soon are rejected without being checked
+        if failures >= 2 {
+            let throttled_res: TestResponse = login_request(&server, &otp).await;
+            throttled_res.assert_status(axum::http::StatusCode::TOO_MANY_REQUESTS);
+            assert!(throttled_res.maybe_header("Retry-After").is_some());
+            client
+                .execute(
+                    "UPDATE login_attempt SET attempted_at = attempted_at - INTERVAL '5 seconds'",
+                    &[],
+                )
+                .await?;
+        }
+        login_request(&server, "wrong-password")
+            .await
+            .assert_status_not_found();
+    }
+
+    // Even the right password is rejected while the account is locked
+    let login_res: TestResponse = login_request(&server, &otp).await;
+    login_res.assert_status(axum::http::StatusCode::LOCKED);
+    assert!(login_res.maybe_header("Retry-After").is_some());
+
+    let unlock_res: TestResponse = server
+        .post("/member/AB1234/unlock")
+        .authorization_bearer(admin_token())
+        .await;
+    unlock_res.assert_status_ok();
+
+    login_request(&server, &otp).await.assert_status_ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn concurrent_attempts_respect_the_lockout() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    let otp: String = add_member_and_get_otp(&server).await?;
+
+    // Failed attempts in progress, committed while the next one is checked
+    let mut client: Client = pool.get().await?;
+    let transaction: Transaction = client.transaction().await?;
+    queries::login_attempt::lock_attempts(&transaction, "0123456789").await?;
+    for _ in 0..5 {
+        queries::login_attempt::add_login_attempt(&transaction, "0123456789", None, false).await?;
+    }
+    queries::login_attempt::lock_phone(&transaction, "0123456789", 15, 4).await?;
+    let (login_res, committed): (TestResponse, Result<(), tokio_postgres::Error>) =
+        tokio::join!(login_request(&server, &otp), async {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            transaction.commit().await
+        });
+    committed?;
+    login_res.assert_status(axum::http::StatusCode::LOCKED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ip_limit_uses_the_address_appended_by_the_proxy() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let login_from = |phone: String, forwarded_for: String| {
+        server
+            .post("/login")
+            .add_header("X-Forwarded-For", forwarded_for)
+            .json(&json!({ "phone": phone, "password": "wrong-password" }))
+    };
+
+    // Entries set by the client do not spread the failures over several addresses
+    for i in 0..20 {
+        login_from(
+            format!("06000000{i:02}"),
+            format!("10.0.0.{i}, 203.0.113.7"),
+        )
+        .await
+        .assert_status_not_found();
+    }
+    login_from(
+        "0600000099".to_string(),
+        "10.0.0.99, 203.0.113.7".to_string(),
+    )
+    .await
+    .assert_status(axum::http::StatusCode::TOO_MANY_REQUESTS);
+
+    // Other clients are not affected, nor are those whose address is unknown
+    login_from("0600000099".to_string(), "203.0.113.8".to_string())
+        .await
+        .assert_status_not_found();
+    server
+        .post("/login")
+        .json(&json!({ "phone": "0600000098", "password": "wrong-password" }))
+        .await
+        .assert_status_not_found();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn password_forgotten_sends_reset_link() -> Result<(), anyhow::Error> {
+    let (server, pool, _container, mailer): (
+        TestServer,
+        Pool,
+        ContainerAsync<GenericImage>,
+        Arc<MemoryMailer>,
+    ) = create_test_server_with_mailer().await?;
+    add_member_and_get_otp(&server).await?;
+    let client = pool.get().await?;
+
+    // Unknown addresses are not revealed
+    server
+        .post("/password-forgotten")
+        .json(&json!({ "email": "unknown@email.com" }))
+        .await
+        .assert_status_ok();
+    assert!(mailer.sent().is_empty());
+
+    server
+        .post("/password-forgotten")
+        .json(&json!({ "email": "john.doe@email.com" }))
+        .await
+        .assert_status_ok();
+    // The email is queued until the outbox is delivered
+    assert!(mailer.sent().is_empty());
+    assert_eq!(deliver_outbox(&client, mailer.as_ref()).await?, 1);
+    let sent: Vec<Email> = mailer.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, "john.doe@email.com");
+    assert_eq!(sent[0].subject, "Beach Garden SXM - Mot de passe oublié");
+    assert!(sent[0].html_body.contains("Bonjour John,"));
+    // The reset link is not kept in the outbox
+    let row = client
+        .query_one("SELECT html_body, text_body FROM email_outbox", &[])
+        .await?;
+    assert_eq!(row.get::<_, String>("html_body"), "");
+    assert_eq!(row.get::<_, String>("text_body"), "");
+
+    let link_start: usize = sent[0]
+        .text_body
+        .find("/password-reset?token=")
+        .expect("The email should contain the reset link.");
+    let token: &str = &sent[0].text_body[link_start + "/password-reset?token=".len()..][..36];
+
+    server
+        .patch("/password-reset")
+        .json(&json!({
+            "token": token,
+            "email": "john.doe@email.com",
+            "new_password": "new-password"
+        }))
+        .await
+        .assert_status_ok();
+    login_request(&server, "new-password")
+        .await
+        .assert_status_ok();
+
+    Ok(())
+}
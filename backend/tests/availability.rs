@@ -0,0 +1,115 @@
+mod common;
+
+use axum_test::{TestResponse, TestServer};
+use chrono::{Duration, NaiveDate};
+use common::{add_member_request, create_test_server, member_token, next_tuesday};
+use deadpool_postgres::Pool;
+use serde_json::{Value, json};
+use testcontainers::{ContainerAsync, GenericImage};
+
+fn status(day: &Value, start_time: &str, court_number: i64) -> String {
+    day["slots"]
+        .as_array()
+        .expect("Slots should be an array.")
+        .iter()
+        .find(|slot| slot["start_time"] == start_time)
+        .and_then(|slot| {
+            slot["courts"]
+                .as_array()?
+                .iter()
+                .find(|court| court["court_number"] == court_number)
+        })
+        .map(|court| court["status"].as_str().unwrap_or_default().to_string())
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn availability_over_a_range() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+    server
+        .post("/reservation")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&json!({
+            "id": "CD5678",
+            "member_id": "AB1234",
+            "court_number": 2,
+            "reservation_date": tuesday,
+            "start_time": "17:00:00",
+            "duration_minutes": 60
+        }))
+        .await
+        .assert_status_ok();
+
+    let wednesday: NaiveDate = tuesday + Duration::days(1);
+    let availability_res: TestResponse = server
+        .get(&format!("/availability?from={tuesday}&to={wednesday}"))
+        .await;
+    availability_res.assert_status_ok();
+    let availability: Value = availability_res.json();
+
+    let days = availability["days"]
+        .as_array()
+        .expect("Days should be an array.");
+    assert_eq!(days.len(), 2);
+    assert_eq!(days[0]["slots"].as_array().map(Vec::len), Some(5));
+    assert_eq!(status(&days[0], "17:00:00", 2), "booked");
+    assert_eq!(status(&days[0], "17:00:00", 1), "free");
+    assert_eq!(status(&days[0], "16:00:00", 2), "free");
+    // The club is closed on Wednesdays
+    assert_eq!(days[1]["slots"].as_array().map(Vec::len), Some(0));
+
+    // Slots of past days cannot be booked
+    let past_tuesday: NaiveDate = tuesday - Duration::days(14);
+    let past_res: TestResponse = server
+        .get(&format!(
+            "/availability?from={past_tuesday}&to={past_tuesday}&court=3"
+        ))
+        .await;
+    past_res.assert_status_ok();
+    let past: Value = past_res.json();
+    assert_eq!(
+        past["days"][0]["slots"][0]["courts"]
+            .as_array()
+            .map(Vec::len),
+        Some(1)
+    );
+    assert_eq!(status(&past["days"][0], "16:00:00", 3), "blocked");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn availability_rejects_invalid_ranges() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let tuesday: NaiveDate = next_tuesday();
+    let backwards_res: TestResponse = server
+        .get(&format!(
+            "/availability?from={tuesday}&to={}",
+            tuesday - Duration::days(1)
+        ))
+        .await;
+    backwards_res.assert_status_bad_request();
+
+    let too_long_res: TestResponse = server
+        .get(&format!(
+            "/availability?from={tuesday}&to={}",
+            tuesday + Duration::days(31)
+        ))
+        .await;
+    too_long_res.assert_status_bad_request();
+
+    let unknown_court_res: TestResponse = server
+        .get(&format!(
+            "/availability?from={tuesday}&to={tuesday}&court=9"
+        ))
+        .await;
+    unknown_court_res.assert_status_not_found();
+
+    Ok(())
+}
@@ -0,0 +1,211 @@
+mod common;
+use crate::common::{
+    add_member_request, admin_token, member_token, next_tuesday, reservation_request,
+};
+use axum_test::{TestResponse, TestServer};
+use backend::db::{models::BookingRules, queries};
+use chrono::{Duration, NaiveDate};
+use common::create_test_server;
+use deadpool_postgres::{Client, Pool, Transaction};
+use serde_json::{Value, json};
+use std::future::IntoFuture;
+use testcontainers::{ContainerAsync, GenericImage};
+
+fn violated_rules(res: &TestResponse) -> Vec<String> {
+    let body: Value = res.json();
+    body["violations"]
+        .as_array()
+        .expect("Violations should be listed.")
+        .iter()
+        .map(|violation| violation["rule"].as_str().unwrap_or_default().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn one_reservation_per_day_by_default() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+
+    let rules: BookingRules = server.get("/booking-rules").await.json();
+    assert_eq!(rules.max_per_day, Some(1));
+    assert_eq!(rules.max_per_week, None);
+
+    reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        tuesday,
+        "16:00:00",
+    )
+    .await
+    .assert_status_ok();
+
+    let second_res: TestResponse = reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        2,
+        tuesday,
+        "18:00:00",
+    )
+    .await;
+    second_res.assert_status_unprocessable_entity();
+    assert_eq!(violated_rules(&second_res), vec!["max_per_day"]);
+
+    // Administrators are exempt
+    reservation_request(&server, admin_token(), "AB1234", 2, tuesday, "18:00:00")
+        .await
+        .assert_status_ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn concurrent_bookings_respect_the_quotas() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+
+    // A booking of the member in progress, committed while the second one is checked
+    let mut client: Client = pool.get().await?;
+    let transaction: Transaction = client.transaction().await?;
+    queries::member::lock_member(&transaction, "AB1234").await?;
+    transaction
+        .execute(
+            "INSERT INTO reservation (id, member_id, court_number, reservation_date, start_time, duration_minutes) VALUES ('XY0000', 'AB1234', 1, $1, '16:00:00', 60)",
+            &[&tuesday],
+        )
+        .await?;
+    let (second_res, committed): (TestResponse, Result<(), tokio_postgres::Error>) = tokio::join!(
+        reservation_request(
+            &server,
+            member_token("AB1234"),
+            "AB1234",
+            2,
+            tuesday,
+            "18:00:00"
+        )
+        .into_future(),
+        async {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            transaction.commit().await
+        }
+    );
+    committed?;
+    second_res.assert_status_unprocessable_entity();
+    assert_eq!(violated_rules(&second_res), vec!["max_per_day"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_configured_rules() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+    let thursday: NaiveDate = tuesday + Duration::days(2);
+
+    let rules = json!({
+        "max_per_day": 1,
+        "max_per_week": 1,
+        "max_days_in_advance": 30,
+        "min_notice_minutes": 60,
+        "peak_starts_at": "19:00:00",
+        "peak_ends_at": "21:00:00",
+        "max_peak_per_week": 1
+    });
+    server
+        .put("/booking-rules")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&rules)
+        .await
+        .assert_status_forbidden();
+    server
+        .put("/booking-rules")
+        .authorization_bearer(admin_token())
+        .json(&rules)
+        .await
+        .assert_status_ok();
+
+    let too_early_res: TestResponse = reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        next_tuesday() + Duration::weeks(5),
+        "16:00:00",
+    )
+    .await;
+    assert_eq!(violated_rules(&too_early_res), vec!["max_days_in_advance"]);
+
+    reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        tuesday,
+        "19:00:00",
+    )
+    .await
+    .assert_status_ok();
+
+    let same_week_res: TestResponse = reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        thursday,
+        "20:00:00",
+    )
+    .await;
+    same_week_res.assert_status_unprocessable_entity();
+    assert_eq!(
+        violated_rules(&same_week_res),
+        vec!["max_per_week", "max_peak_per_week"]
+    );
+
+    // Rules are disabled when unset
+    server
+        .put("/booking-rules")
+        .authorization_bearer(admin_token())
+        .json(&json!({}))
+        .await
+        .assert_status_ok();
+    reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        thursday,
+        "20:00:00",
+    )
+    .await
+    .assert_status_ok();
+
+    server
+        .put("/booking-rules")
+        .authorization_bearer(admin_token())
+        .json(&json!({ "min_notice_minutes": 30 * 24 * 60 }))
+        .await
+        .assert_status_ok();
+    let short_notice_res: TestResponse = reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        next_tuesday() + Duration::weeks(1),
+        "16:00:00",
+    )
+    .await;
+    assert_eq!(
+        violated_rules(&short_notice_res),
+        vec!["min_notice_minutes"]
+    );
+
+    Ok(())
+}
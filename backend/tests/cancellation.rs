@@ -0,0 +1,139 @@
+mod common;
+use crate::common::{add_member_request, admin_token, member_token, next_tuesday, reserve};
+use axum_test::{TestResponse, TestServer};
+use backend::db::{
+    models::{Member, Reservation},
+    queries,
+};
+use chrono::NaiveDate;
+use common::create_test_server;
+use deadpool_postgres::{Client, Pool};
+use serde_json::{Value, json};
+use testcontainers::{ContainerAsync, GenericImage};
+
+#[tokio::test]
+async fn cancellation_cutoff_and_late_cancellations() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+
+    // Reservations of next week are past the cutoff, within the late cancellation delay
+    server
+        .put("/booking-rules")
+        .authorization_bearer(admin_token())
+        .json(&json!({
+            "max_per_day": 1,
+            "cancellation_cutoff_minutes": 30 * 24 * 60,
+            "late_cancellation_minutes": 60 * 24 * 60
+        }))
+        .await
+        .assert_status_ok();
+    let reservation_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+
+    let member_res: TestResponse = server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .authorization_bearer(member_token("AB1234"))
+        .await;
+    member_res.assert_status_forbidden();
+    let body: Value = member_res.json();
+    assert!(body["error"].as_str().is_some_and(|e| e.contains("43200")));
+
+    server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .add_query_param("reason", "Called the club")
+        .authorization_bearer(admin_token())
+        .await
+        .assert_status_ok();
+
+    let client: Client = pool.get().await?;
+    let cancelled: Reservation =
+        queries::reservation::get_reservation(&client, &reservation_id).await?;
+    let cancellation = cancelled
+        .cancellation
+        .expect("Reservation should be cancelled.");
+    assert_eq!(cancellation.cancelled_by.as_deref(), Some("ADM001"));
+    assert!(cancellation.is_late);
+
+    let member: Member = server
+        .get("/member/AB1234")
+        .authorization_bearer(admin_token())
+        .await
+        .json();
+    assert_eq!(member.late_cancellations, 1);
+
+    // The cancelled reservation neither blocks the court nor counts in the booking rules
+    let rebooked_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+
+    // Cancelling earlier than the late cancellation delay is not counted
+    server
+        .put("/booking-rules")
+        .authorization_bearer(admin_token())
+        .json(&json!({ "max_per_day": 1, "late_cancellation_minutes": 60 }))
+        .await
+        .assert_status_ok();
+    server
+        .delete(&format!("/reservation/{rebooked_id}"))
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_ok();
+    let member: Member = queries::member::get_member(&client, &"AB1234".to_string()).await?;
+    assert_eq!(member.late_cancellations, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn moves_are_limited_like_cancellations() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+    server
+        .put("/booking-rules")
+        .authorization_bearer(admin_token())
+        .json(&json!({
+            "cancellation_cutoff_minutes": 30 * 24 * 60,
+            "late_cancellation_minutes": 60 * 24 * 60
+        }))
+        .await
+        .assert_status_ok();
+    let reservation_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+    let update = |court_number: i16, duration_minutes: i16| {
+        server
+            .patch("/reservation")
+            .authorization_bearer(member_token("AB1234"))
+            .json(&json!({
+                "id": reservation_id,
+                "member_id": "AB1234",
+                "court_number": court_number,
+                "reservation_date": tuesday,
+                "start_time": "17:00:00",
+                "duration_minutes": duration_minutes
+            }))
+    };
+
+    // Past the cutoff, the reservation can be extended but not moved
+    let moved_res: TestResponse = update(2, 60).await;
+    moved_res.assert_status_forbidden();
+    let body: Value = moved_res.json();
+    assert!(body["error"].as_str().is_some_and(|e| e.contains("43200")));
+    update(1, 90).await.assert_status_ok();
+
+    // Moving within the late cancellation delay is counted as a late cancellation
+    server
+        .put("/booking-rules")
+        .authorization_bearer(admin_token())
+        .json(&json!({ "late_cancellation_minutes": 60 * 24 * 60 }))
+        .await
+        .assert_status_ok();
+    update(2, 90).await.assert_status_ok();
+    let member: Member = server
+        .get("/member/AB1234")
+        .authorization_bearer(admin_token())
+        .await
+        .json();
+    assert_eq!(member.late_cancellations, 1);
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+mod common;
+use crate::common::{add_member_request, admin_token, member_token, next_tuesday, reserve};
+use axum_test::TestServer;
+use backend::api::wrappers::check_in::mark_no_shows;
+use backend::db::{
+    models::{Member, Reservation},
+    queries,
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use common::create_test_server;
+use deadpool_postgres::{Client, Pool};
+use serde_json::json;
+use testcontainers::{ContainerAsync, GenericImage};
+
+fn check_in(server: &TestServer, token: String, id: &str) -> axum_test::TestRequest {
+    server
+        .post(&format!("/reservation/{id}/check-in"))
+        .authorization_bearer(token)
+}
+
+#[tokio::test]
+async fn check_in_window() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+    let reservation_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+
+    // Members check in from 15 minutes before the start by default
+    check_in(&server, member_token("AB1234"), &reservation_id)
+        .await
+        .assert_status_unprocessable_entity();
+
+    check_in(&server, admin_token(), &reservation_id)
+        .await
+        .assert_status_ok();
+    let mut client: Client = pool.get().await?;
+    let checked_in: Reservation =
+        queries::reservation::get_reservation(&client, &reservation_id).await?;
+    assert_eq!(
+        checked_in
+            .check_in
+            .expect("Member should be checked in.")
+            .checked_in_by
+            .as_deref(),
+        Some("ADM001")
+    );
+
+    // Checked-in reservations are not no-shows
+    let ended: NaiveDateTime = tuesday.and_time(NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+    assert_eq!(mark_no_shows(&mut client, ended).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn no_shows_suspend_bookings() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+
+    server
+        .put("/booking-rules")
+        .authorization_bearer(admin_token())
+        .json(&json!({
+            "no_show_suspension_threshold": 1,
+            "no_show_suspension_days": 7
+        }))
+        .await
+        .assert_status_ok();
+    let reservation_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+
+    let mut client: Client = pool.get().await?;
+    let during: NaiveDateTime = tuesday.and_time(NaiveTime::from_hms_opt(17, 30, 0).unwrap());
+    assert_eq!(mark_no_shows(&mut client, during).await?, 0);
+    let ended: NaiveDateTime = tuesday.and_time(NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+    assert_eq!(mark_no_shows(&mut client, ended).await?, 1);
+    assert_eq!(mark_no_shows(&mut client, ended).await?, 0);
+
+    let member: Member = queries::member::get_member(&client, &"AB1234".to_string()).await?;
+    assert_eq!(member.no_shows, 1);
+    assert_eq!(member.suspended_until, Some(tuesday + Duration::days(7)));
+
+    reserve(&server, "AB1234", 1, tuesday + Duration::days(7))
+        .await
+        .assert_status_forbidden();
+    check_in(&server, member_token("AB1234"), &reservation_id)
+        .await
+        .assert_status_unprocessable_entity();
+
+    // Admins can check in a member afterwards, clearing the no-show and the suspension
+    check_in(&server, admin_token(), &reservation_id)
+        .await
+        .assert_status_ok();
+    let checked_in: Reservation =
+        queries::reservation::get_reservation(&client, &reservation_id).await?;
+    assert!(!checked_in.is_no_show);
+    let member: Member = queries::member::get_member(&client, &"AB1234".to_string()).await?;
+    assert_eq!(member.no_shows, 0);
+    assert_eq!(member.suspended_until, None);
+    reserve(&server, "AB1234", 1, tuesday + Duration::days(7))
+        .await
+        .assert_status_ok();
+
+    queries::member::set_suspended_until(&client, "AB1234", Some(&tuesday)).await?;
+    server
+        .delete("/member/AB1234/suspension")
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_forbidden();
+    server
+        .delete("/member/AB1234/suspension")
+        .authorization_bearer(admin_token())
+        .await
+        .assert_status_ok();
+    reserve(&server, "AB1234", 2, tuesday + Duration::days(7))
+        .await
+        .assert_status_ok();
+
+    Ok(())
+}
@@ -1,17 +1,63 @@
+// Shared by every integration test crate, each of which only uses part of it
+#![allow(dead_code)]
+
 use anyhow::Error;
 use axum::Router;
-use axum_test::{TestResponse, TestServer};
-use backend::api::app::{AppState, router};
-use deadpool_postgres::{Config, ManagerConfig, Pool, RecyclingMethod, Runtime};
+use axum_test::{TestRequest, TestResponse, TestServer};
+use backend::api::app::{AppState, migrate, router};
+use backend::config::Config;
+use backend::jwt::create_jwt;
+use backend::mail::MemoryMailer;
+use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday};
+use deadpool_postgres::{ManagerConfig, Pool, RecyclingMethod, Runtime};
 use serde_json::json;
+use std::sync::Arc;
 use testcontainers::GenericImage;
 use testcontainers::core::{IntoContainerPort, WaitFor};
 use testcontainers_modules::testcontainers::{ContainerAsync, runners::AsyncRunner};
 use tokio_postgres::NoTls;
 
+pub fn test_config() -> Config {
+    Config::from_values([
+        ("POSTGRES_USER", "postgres"),
+        ("POSTGRES_PASSWORD", "postgres"),
+        ("POSTGRES_DB", "postgres"),
+        ("POSTGRES_HOST", "localhost"),
+        ("JWT_SECRET", "test-secret"),
+        ("MAIL_TRANSPORT", "memory"),
+        ("SMTP_SENDER", "noreply@beachgarden.test"),
+        ("CUSTOM_DOMAIN_URL", "https://beachgarden.test"),
+    ])
+    .expect("Invalid test configuration.")
+}
+
+pub fn admin_token() -> String {
+    create_jwt(&test_config().jwt, "ADM001", "", "0600000000", true, true)
+        .expect("Could not create JWT.")
+}
+
+pub fn member_token(member_id: &str) -> String {
+    create_jwt(&test_config().jwt, member_id, "", "0123456789", true, false)
+        .expect("Could not create JWT.")
+}
+
 pub async fn create_test_server() -> Result<(TestServer, Pool, ContainerAsync<GenericImage>), Error>
 {
-    let container = GenericImage::new("postgres-with-pgcron", "latest")
+    let (server, pool, container, _mailer) = create_test_server_with_mailer().await?;
+    Ok((server, pool, container))
+}
+
+/// Test server whose emails are kept by the returned mailer.
+pub async fn create_test_server_with_mailer() -> Result<
+    (
+        TestServer,
+        Pool,
+        ContainerAsync<GenericImage>,
+        Arc<MemoryMailer>,
+    ),
+    Error,
+> {
+    let container = GenericImage::new("postgres", "16-bookworm")
         .with_exposed_port(5432.tcp())
         .with_wait_for(WaitFor::message_on_stderr(
             "database system is ready to accept connections",
@@ -21,7 +67,7 @@ pub async fn create_test_server() -> Result<(TestServer, Pool, ContainerAsync<Ge
 
     let container_port = container.get_host_port_ipv4(5432).await?;
 
-    let mut cfg = Config::new();
+    let mut cfg = deadpool_postgres::Config::new();
     cfg.host = Some("localhost".into());
     cfg.user = Some("postgres".into());
     cfg.dbname = Some("postgres".into());
@@ -32,12 +78,19 @@ pub async fn create_test_server() -> Result<(TestServer, Pool, ContainerAsync<Ge
     });
 
     let pool = cfg.create_pool(Some(Runtime::Tokio1), NoTls)?;
-    let app_state = AppState { pool: pool.clone() };
+    migrate(&pool).await?;
+    let mailer: Arc<MemoryMailer> = Arc::new(MemoryMailer::default());
+    let app_state = AppState {
+        pool: pool.clone(),
+        config: Arc::new(test_config()),
+        mailer: mailer.clone(),
+        sms: None,
+    };
     let app: Router = router(app_state).await;
 
     let server = TestServer::new(app)?;
 
-    Ok((server, pool, container))
+    Ok((server, pool, container, mailer))
 }
 
 pub async fn add_member_request(server: &TestServer) -> Result<TestResponse, anyhow::Error> {
@@ -49,5 +102,78 @@ pub async fn add_member_request(server: &TestServer) -> Result<TestResponse, any
         "first_name": "John",
         "last_name": "Doe",
     });
-    Ok(server.post("/member").json(&new_member).await)
+    Ok(server
+        .post("/member")
+        .authorization_bearer(admin_token())
+        .json(&new_member)
+        .await)
+}
+
+// First `weekday` at least a week ahead, so that none of its slots has started
+pub fn next_weekday(weekday: Weekday) -> NaiveDate {
+    let mut date: NaiveDate = Utc::now().date_naive() + Duration::days(7);
+    while date.weekday() != weekday {
+        date = date.succ_opt().expect("Could not get next date.");
+    }
+    date
+}
+
+// The club is open from 16:00 to 21:00 on Tuesdays
+pub fn next_tuesday() -> NaiveDate {
+    next_weekday(Weekday::Tue)
+}
+
+pub async fn add_other_member_request(server: &TestServer, id: &str, phone: &str) -> TestResponse {
+    server
+        .post("/member")
+        .authorization_bearer(admin_token())
+        .json(&json!({
+            "id": id,
+            "phone": phone,
+            "password": "",
+            "email": format!("{id}@email.com"),
+            "first_name": "Jane",
+            "last_name": "Doe",
+        }))
+        .await
+}
+
+// Hour-long reservation of `member_id`, requested with `token`
+pub fn reservation_request(
+    server: &TestServer,
+    token: String,
+    member_id: &str,
+    court_number: i16,
+    date: NaiveDate,
+    start_time: &str,
+) -> TestRequest {
+    server
+        .post("/reservation")
+        .authorization_bearer(token)
+        .json(&json!({
+            "id": "",
+            "member_id": member_id,
+            "court_number": court_number,
+            "reservation_date": date,
+            "start_time": start_time,
+            "duration_minutes": 60
+        }))
+}
+
+// Books `court_number` for `member_id` on `date`, from 17:00 to 18:00
+pub async fn reserve(
+    server: &TestServer,
+    member_id: &str,
+    court_number: i16,
+    date: NaiveDate,
+) -> TestResponse {
+    reservation_request(
+        server,
+        member_token(member_id),
+        member_id,
+        court_number,
+        date,
+        "17:00:00",
+    )
+    .await
 }
@@ -0,0 +1,102 @@
+use backend::config::{Config, ConfigError, MailTransport, SmsTransport};
+
+#[test]
+fn missing_keys_are_reported_together() {
+    let error: ConfigError =
+        Config::from_values([("POSTGRES_USER", "postgres"), ("SMTP_SERVER", "")])
+            .expect_err("Configuration should be incomplete.");
+
+    match error {
+        ConfigError::Invalid { missing, invalid } => {
+            assert!(invalid.is_empty());
+            for key in [
+                "POSTGRES_PASSWORD",
+                "POSTGRES_DB",
+                "POSTGRES_HOST",
+                "JWT_SECRET",
+                "SMTP_SERVER",
+                "SMTP_USER",
+                "SMTP_PASSWORD",
+                "SMTP_SENDER",
+            ] {
+                assert!(
+                    missing.contains(&key.to_string()),
+                    "{key} should be missing"
+                );
+            }
+            assert!(!missing.contains(&"POSTGRES_USER".to_string()));
+        }
+        other => panic!("Unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn defaults_and_invalid_values() {
+    let values = [
+        ("POSTGRES_USER", "postgres"),
+        ("POSTGRES_PASSWORD", "postgres"),
+        ("POSTGRES_DB", "postgres"),
+        ("POSTGRES_HOST", "localhost"),
+        ("JWT_SECRET", "secret"),
+        ("SMTP_SERVER", "localhost"),
+        ("SMTP_USER", "user"),
+        ("SMTP_PASSWORD", "password"),
+        ("SMTP_SENDER", "noreply@beachgarden.test"),
+        ("CUSTOM_DOMAIN_URL", "https://beachgarden.test"),
+        ("FRONTEND_IP", "localhost"),
+    ];
+
+    let config: Config = Config::from_values(values).expect("Configuration should be valid.");
+    assert_eq!(config.postgres.port, 5432);
+    assert_eq!(config.postgres.ca_bundle, None);
+    assert_eq!(config.jwt.access_token_ttl_minutes, 15);
+    assert_eq!(config.jwt.refresh_token_ttl_days, 30);
+    assert_eq!(config.club_utc_offset.local_minus_utc(), -4 * 3600);
+    assert_eq!(config.waitlist_claim_minutes, 30);
+    assert_eq!(config.mail_transport, MailTransport::Smtp);
+    assert_eq!(config.reminder_lead_minutes, 120);
+    assert_eq!(config.sms_transport, SmsTransport::Disabled);
+
+    let error: ConfigError =
+        Config::from_values(values.into_iter().chain([("POSTGRES_PORT", "not-a-port")]))
+            .expect_err("POSTGRES_PORT should be invalid.");
+    assert!(error.to_string().contains("POSTGRES_PORT"));
+
+    let config: Config =
+        Config::from_values(values.into_iter().chain([("CLUB_UTC_OFFSET", "+01:00")]))
+            .expect("CLUB_UTC_OFFSET should be valid.");
+    assert_eq!(config.club_utc_offset.local_minus_utc(), 3600);
+}
+
+#[test]
+fn smtp_is_only_required_for_the_smtp_transport() {
+    let values = [
+        ("POSTGRES_USER", "postgres"),
+        ("POSTGRES_PASSWORD", "postgres"),
+        ("POSTGRES_DB", "postgres"),
+        ("POSTGRES_HOST", "localhost"),
+        ("JWT_SECRET", "secret"),
+        ("SMTP_SENDER", "noreply@beachgarden.test"),
+        ("CUSTOM_DOMAIN_URL", "https://beachgarden.test"),
+    ];
+
+    let config: Config = Config::from_values(
+        values
+            .into_iter()
+            .chain([("MAIL_TRANSPORT", "file"), ("MAIL_SPOOL_DIR", "/tmp/mail")]),
+    )
+    .expect("Configuration should be valid.");
+    assert_eq!(
+        config.mail_transport,
+        MailTransport::File("/tmp/mail".into())
+    );
+
+    let error: ConfigError =
+        Config::from_values(values).expect_err("SMTP settings should be required by default.");
+    assert!(error.to_string().contains("SMTP_SERVER"));
+
+    let error: ConfigError =
+        Config::from_values(values.into_iter().chain([("MAIL_TRANSPORT", "pigeon")]))
+            .expect_err("MAIL_TRANSPORT should be invalid.");
+    assert!(error.to_string().contains("MAIL_TRANSPORT"));
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use axum_test::{TestResponse, TestServer};
+use backend::db::models::Court;
+use chrono::{Duration, NaiveDate, Utc};
+use common::{
+    add_member_request, admin_token, create_test_server, member_token, next_tuesday, reserve,
+};
+use deadpool_postgres::Pool;
+use serde_json::{Value, json};
+use testcontainers::{ContainerAsync, GenericImage};
+
+fn fifth_court(is_active: bool) -> Value {
+    json!({
+        "number": 5,
+        "name": "Terrain 5",
+        "surface": "sand",
+        "is_lit": false,
+        "is_active": is_active,
+        "display_order": 5
+    })
+}
+
+#[tokio::test]
+async fn courts_are_seeded() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let courts_res: TestResponse = server.get("/courts").await;
+    courts_res.assert_status_ok();
+    let courts: Vec<Court> = courts_res.json();
+
+    let numbers: Vec<i16> = courts.iter().map(|court| court.number).collect();
+    assert_eq!(numbers, vec![1, 2, 3, 4]);
+    assert!(courts.iter().all(|court| court.is_active));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admins_manage_courts() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+
+    server
+        .post("/court")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&fifth_court(true))
+        .await
+        .assert_status_forbidden();
+
+    server
+        .post("/court")
+        .authorization_bearer(admin_token())
+        .json(&fifth_court(true))
+        .await
+        .assert_status_ok();
+    reserve(&server, "AB1234", 5, next_tuesday())
+        .await
+        .assert_status_ok();
+
+    server
+        .patch("/court")
+        .authorization_bearer(admin_token())
+        .json(&fifth_court(false))
+        .await
+        .assert_status_ok();
+    let court: Court = server.get("/court/5").await.json();
+    assert!(!court.is_active);
+
+    // Booked courts can only be deactivated
+    server
+        .delete("/court/5")
+        .authorization_bearer(admin_token())
+        .await
+        .assert_status_bad_request();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn closed_courts_cannot_be_booked() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+
+    server
+        .post("/court")
+        .authorization_bearer(admin_token())
+        .json(&fifth_court(false))
+        .await
+        .assert_status_ok();
+
+    reserve(&server, "AB1234", 5, next_tuesday())
+        .await
+        .assert_status_unprocessable_entity();
+    reserve(&server, "AB1234", 9, next_tuesday())
+        .await
+        .assert_status_unprocessable_entity();
+
+    let today: NaiveDate = Utc::now().date_naive();
+    let availability: Value = server
+        .get(&format!(
+            "/availability?from={today}&to={}",
+            today + Duration::days(6)
+        ))
+        .await
+        .json();
+    let listed: bool = availability["days"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|day| day["slots"].as_array().into_iter().flatten())
+        .flat_map(|slot| slot["courts"].as_array().into_iter().flatten())
+        .any(|court| court["court_number"] == 5);
+    assert!(!listed);
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+mod common;
+use crate::common::{add_member_request, admin_token, member_token, next_tuesday, reserve};
+use axum_test::{TestResponse, TestServer};
+use backend::db::{
+    models::{CourtBlock, Reservation},
+    queries,
+};
+use chrono::{Duration, NaiveDate};
+use common::create_test_server;
+use deadpool_postgres::{Client, Pool, Transaction};
+use serde_json::{Value, json};
+use testcontainers::{ContainerAsync, GenericImage};
+
+#[tokio::test]
+async fn blocks_cancel_and_prevent_bookings() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+    let next_week: NaiveDate = tuesday + Duration::days(7);
+    let reservation_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+
+    let block = json!({
+        "court_numbers": [1],
+        "starts_at": format!("{tuesday}T16:00:00"),
+        "ends_at": format!("{tuesday}T18:00:00"),
+        "reason": "Resurfacing",
+        "frequency": "weekly",
+        "until": next_week,
+        "cancel_overlapping": true
+    });
+    server
+        .post("/court-blocks")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&block)
+        .await
+        .assert_status_forbidden();
+    let block_res: TestResponse = server
+        .post("/court-blocks")
+        .authorization_bearer(admin_token())
+        .json(&block)
+        .await;
+    block_res.assert_status_ok();
+    let body: Value = block_res.json();
+    assert_eq!(body["overlapping"], json!([reservation_id]));
+
+    let client: Client = pool.get().await?;
+    let cancelled: Reservation =
+        queries::reservation::get_reservation(&client, &reservation_id).await?;
+    assert_eq!(
+        cancelled
+            .cancellation
+            .expect("Reservation should be cancelled.")
+            .reason
+            .as_deref(),
+        Some("Resurfacing")
+    );
+
+    // The block repeats the following week, on court 1 only
+    let blocked_res: TestResponse = reserve(&server, "AB1234", 1, next_week).await;
+    blocked_res.assert_status_unprocessable_entity();
+    let error: Value = blocked_res.json();
+    assert_eq!(
+        error["error"],
+        "Court 1 is closed at that time: Resurfacing"
+    );
+    reserve(&server, "AB1234", 2, next_week)
+        .await
+        .assert_status_ok();
+
+    let availability: Value = server
+        .get(&format!("/availability?from={tuesday}&to={tuesday}"))
+        .await
+        .json();
+    let courts: &Value = &availability["days"][0]["slots"]
+        .as_array()
+        .expect("Slots should be an array.")
+        .iter()
+        .find(|slot| slot["start_time"] == "17:00:00")
+        .expect("17:00 slot should exist.")["courts"];
+    assert_eq!(courts[0]["court_number"], 1);
+    assert_eq!(courts[0]["status"], "blocked");
+    assert_eq!(courts[1]["status"], "free");
+
+    let blocks: Vec<Value> = server.get("/court-blocks").await.json();
+    assert_eq!(blocks.len(), 1);
+    let id: i64 = blocks[0]["id"].as_i64().expect("ID should be a number.");
+    server
+        .delete(&format!("/court-blocks/{id}"))
+        .authorization_bearer(admin_token())
+        .await
+        .assert_status_ok();
+    let blocks: Vec<Value> = server.get("/court-blocks").await.json();
+    assert!(blocks.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_blocks_are_rejected() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    let tuesday: NaiveDate = next_tuesday();
+
+    for block in [
+        json!({
+            "starts_at": format!("{tuesday}T18:00:00"),
+            "ends_at": format!("{tuesday}T16:00:00"),
+            "reason": "Storm"
+        }),
+        json!({
+            "starts_at": format!("{tuesday}T16:00:00"),
+            "ends_at": format!("{tuesday}T18:00:00"),
+            "reason": "Storm",
+            "frequency": "weekly"
+        }),
+    ] {
+        server
+            .post("/court-blocks")
+            .authorization_bearer(admin_token())
+            .json(&block)
+            .await
+            .assert_status_unprocessable_entity();
+    }
+
+    // Like the court endpoints, unknown courts are not found
+    server
+        .post("/court-blocks")
+        .authorization_bearer(admin_token())
+        .json(&json!({
+            "court_numbers": [9],
+            "starts_at": format!("{tuesday}T16:00:00"),
+            "ends_at": format!("{tuesday}T18:00:00"),
+            "reason": "Storm"
+        }))
+        .await
+        .assert_status_not_found();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn blocks_added_while_booking_are_checked() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+
+    // A block being added, committed once the booking checked the court
+    let mut client: Client = pool.get().await?;
+    let transaction: Transaction = client.transaction().await?;
+    queries::court_block::add_block(
+        &transaction,
+        &CourtBlock {
+            id: 0,
+            court_numbers: None,
+            starts_at: tuesday.and_hms_opt(16, 0, 0).unwrap(),
+            ends_at: tuesday.and_hms_opt(21, 0, 0).unwrap(),
+            reason: "Storm".to_string(),
+            interval_weeks: None,
+            until_date: None,
+            created_by: None,
+        },
+    )
+    .await?;
+    let (booking_res, committed): (TestResponse, Result<(), tokio_postgres::Error>) =
+        tokio::join!(reserve(&server, "AB1234", 1, tuesday), async {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            transaction.commit().await
+        });
+    committed?;
+    booking_res.assert_status_unprocessable_entity();
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+mod common;
+use crate::common::{add_member_request, admin_token, member_token, next_tuesday, reserve};
+use axum_test::TestServer;
+use backend::mail::{Attachment, Email, MemoryMailer, deliver_outbox};
+use chrono::NaiveDate;
+use common::create_test_server_with_mailer;
+use deadpool_postgres::{Client, Pool};
+use serde_json::json;
+use std::sync::Arc;
+use testcontainers::{ContainerAsync, GenericImage};
+
+async fn reset_email(server: &TestServer, client: &Client, mailer: &MemoryMailer) -> Email {
+    server
+        .post("/password-forgotten")
+        .json(&json!({ "email": "jo@email.com" }))
+        .await
+        .assert_status_ok();
+    deliver_outbox(client, mailer)
+        .await
+        .expect("Could not deliver the outbox.");
+    mailer
+        .sent()
+        .pop()
+        .expect("An email should have been sent.")
+}
+
+#[tokio::test]
+async fn emails_are_localized_and_escaped() -> Result<(), anyhow::Error> {
+    let (server, pool, _container, mailer): (
+        TestServer,
+        Pool,
+        ContainerAsync<GenericImage>,
+        Arc<MemoryMailer>,
+    ) = create_test_server_with_mailer().await?;
+    let client: Client = pool.get().await?;
+    server
+        .post("/member")
+        .authorization_bearer(admin_token())
+        .json(&json!({
+            "id": "JO1234",
+            "phone": "0612345678",
+            "password": "",
+            "email": "jo@email.com",
+            "first_name": "<b>Jo</b>",
+            "last_name": "Doe",
+            "locale": "en"
+        }))
+        .await
+        .assert_status_ok();
+
+    let email: Email = reset_email(&server, &client, &mailer).await;
+    assert_eq!(email.subject, "Beach Garden SXM - Forgotten password");
+    assert!(email.html_body.contains("<h2>Forgotten password</h2>"));
+    assert!(
+        email
+            .html_body
+            .contains("Hello &lt;b&gt;Jo&lt;&#x2f;b&gt;,")
+    );
+    assert!(!email.html_body.contains("<b>Jo</b>"));
+    assert!(email.text_body.starts_with("Forgotten password\n"));
+    assert!(email.text_body.contains("Hello <b>Jo</b>,"));
+    assert!(email.text_body.contains("/password-reset?token="));
+    assert!(email.text_body.contains("&email=jo%40email.com"));
+
+    // Members choose their language with their profile
+    server
+        .patch("/member")
+        .authorization_bearer(member_token("JO1234"))
+        .json(&json!({
+            "id": "JO1234",
+            "phone": "0612345678",
+            "email": "jo@email.com",
+            "first_name": "Jo",
+            "last_name": "Doe",
+            "locale": "nl"
+        }))
+        .await
+        .assert_status_ok();
+    let email: Email = reset_email(&server, &client, &mailer).await;
+    assert_eq!(email.subject, "Beach Garden SXM - Wachtwoord vergeten");
+    assert!(email.text_body.contains("Hallo Jo,"));
+
+    // Kept when not given
+    server
+        .patch("/member")
+        .authorization_bearer(member_token("JO1234"))
+        .json(&json!({
+            "id": "JO1234",
+            "phone": "0612345678",
+            "email": "jo@email.com",
+            "first_name": "Jo",
+            "last_name": "Doe"
+        }))
+        .await
+        .assert_status_ok();
+    let email: Email = reset_email(&server, &client, &mailer).await;
+    assert_eq!(email.subject, "Beach Garden SXM - Wachtwoord vergeten");
+
+    Ok(())
+}
+
+async fn delivered(client: &Client, mailer: &MemoryMailer) -> Email {
+    assert_eq!(deliver_outbox(client, mailer).await.ok(), Some(1));
+    mailer
+        .sent()
+        .pop()
+        .expect("An email should have been sent.")
+}
+
+#[tokio::test]
+async fn reservation_emails_carry_calendar_events() -> Result<(), anyhow::Error> {
+    let (server, pool, _container, mailer): (
+        TestServer,
+        Pool,
+        ContainerAsync<GenericImage>,
+        Arc<MemoryMailer>,
+    ) = create_test_server_with_mailer().await?;
+    let client: Client = pool.get().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+
+    let reservation_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+    let uid: String = format!("UID:reservation-{reservation_id}@beachgardensxm");
+
+    let confirmation: Email = delivered(&client, &mailer).await;
+    assert_eq!(confirmation.to, "john.doe@email.com");
+    assert_eq!(
+        confirmation.subject,
+        "Beach Garden SXM - Réservation confirmée"
+    );
+    let invitation: Attachment = confirmation
+        .attachment
+        .expect("The confirmation should have a calendar event.");
+    assert_eq!(
+        invitation.content_type,
+        "text/calendar; charset=UTF-8; method=REQUEST"
+    );
+    assert!(invitation.content.contains("METHOD:REQUEST\r\n"));
+    assert!(invitation.content.contains(&uid));
+    assert!(
+        invitation
+            .content
+            .contains("ORGANIZER:mailto:noreply@beachgarden.test\r\n")
+    );
+    assert!(
+        invitation
+            .content
+            .contains("ATTENDEE:mailto:john.doe@email.com\r\n")
+    );
+    // Times of the club are four hours behind UTC
+    let date: String = tuesday.format("%Y%m%d").to_string();
+    assert!(
+        invitation
+            .content
+            .contains(&format!("DTSTART:{date}T210000Z"))
+    );
+    assert!(
+        invitation
+            .content
+            .contains(&format!("DTEND:{date}T220000Z"))
+    );
+    assert!(
+        invitation
+            .content
+            .contains("SUMMARY:Beach Garden SXM - terrain 1")
+    );
+
+    server
+        .patch("/reservation")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&json!({
+            "id": reservation_id,
+            "member_id": "AB1234",
+            "court_number": 2,
+            "reservation_date": tuesday,
+            "start_time": "18:00:00",
+            "duration_minutes": 90
+        }))
+        .await
+        .assert_status_ok();
+    let modification: Email = delivered(&client, &mailer).await;
+    assert_eq!(
+        modification.subject,
+        "Beach Garden SXM - Réservation modifiée"
+    );
+    let update: Attachment = modification
+        .attachment
+        .expect("The modification should have a calendar event.");
+    assert!(update.content.contains(&uid));
+    assert!(update.content.contains(&format!("DTEND:{date}T233000Z")));
+
+    server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .add_query_param("reason", "Tournoi")
+        .authorization_bearer(admin_token())
+        .await
+        .assert_status_ok();
+    let cancellation: Email = delivered(&client, &mailer).await;
+    assert_eq!(
+        cancellation.subject,
+        "Beach Garden SXM - Réservation annulée"
+    );
+    assert!(cancellation.text_body.contains("Motif : Tournoi"));
+    let cancel: Attachment = cancellation
+        .attachment
+        .expect("The cancellation should have a calendar event.");
+    assert!(cancel.content.contains("METHOD:CANCEL\r\n"));
+    assert!(cancel.content.contains("STATUS:CANCELLED"));
+    assert!(cancel.content.contains(&uid));
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+mod common;
+use crate::common::{add_member_request, admin_token, member_token};
+use async_trait::async_trait;
+use axum_test::TestServer;
+use backend::db::models::{OutboxEmailSummary, OutboxStatus};
+use backend::mail::{Email, MAX_ATTEMPTS, MailError, Mailer, MemoryMailer, deliver_outbox};
+use chrono::{Duration, Utc};
+use common::create_test_server;
+use deadpool_postgres::{Client, Pool};
+use serde_json::{Value, json};
+use testcontainers::{ContainerAsync, GenericImage};
+
+struct FailingMailer;
+
+#[async_trait]
+impl Mailer for FailingMailer {
+    async fn send(&self, _email: &Email) -> Result<(), MailError> {
+        Err(MailError::Build("server unreachable".to_string()))
+    }
+}
+
+async fn outbox(server: &TestServer, query: &str) -> Vec<OutboxEmailSummary> {
+    server
+        .get(&format!("/email-outbox{query}"))
+        .authorization_bearer(admin_token())
+        .await
+        .json()
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried_then_given_up() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    let client: Client = pool.get().await?;
+
+    server
+        .post("/password-forgotten")
+        .json(&json!({ "email": "john.doe@email.com" }))
+        .await
+        .assert_status_ok();
+
+    server
+        .get("/email-outbox")
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_forbidden();
+
+    assert_eq!(deliver_outbox(&client, &FailingMailer).await?, 0);
+    let emails: Vec<OutboxEmailSummary> = outbox(&server, "").await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].status, OutboxStatus::Pending);
+    assert_eq!(emails[0].attempts, 1);
+    assert_eq!(
+        emails[0].last_error.as_deref(),
+        Some("invalid email: server unreachable")
+    );
+    assert!(emails[0].next_attempt_at > Utc::now() + Duration::seconds(30));
+
+    // The content, holding the reset link, is not listed
+    let listed: Value = server
+        .get("/email-outbox")
+        .authorization_bearer(admin_token())
+        .await
+        .json();
+    assert!(listed[0].get("html_body").is_none());
+    assert!(listed[0].get("text_body").is_none());
+
+    // Not retried before its backoff is over
+    assert_eq!(deliver_outbox(&client, &FailingMailer).await?, 0);
+    assert_eq!(outbox(&server, "").await[0].attempts, 1);
+
+    for _ in 1..MAX_ATTEMPTS {
+        client
+            .execute("UPDATE email_outbox SET next_attempt_at = NOW()", &[])
+            .await?;
+        deliver_outbox(&client, &FailingMailer).await?;
+    }
+    let failed: Vec<OutboxEmailSummary> = outbox(&server, "?status=failed").await;
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].attempts, MAX_ATTEMPTS);
+    assert!(outbox(&server, "?status=pending").await.is_empty());
+
+    // Admins queue it again once the cause is fixed
+    server
+        .post(&format!("/email-outbox/{}/retry", failed[0].id))
+        .authorization_bearer(admin_token())
+        .await
+        .assert_status_ok();
+    let mailer = MemoryMailer::default();
+    assert_eq!(deliver_outbox(&client, &mailer).await?, 1);
+    assert_eq!(mailer.sent()[0].to, "john.doe@email.com");
+
+    let sent: Vec<OutboxEmailSummary> = outbox(&server, "?status=sent").await;
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].sent_at.is_some());
+    server
+        .post(&format!("/email-outbox/{}/retry", sent[0].id))
+        .authorization_bearer(admin_token())
+        .await
+        .assert_status_not_found();
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use anyhow::Error;
+use backend::api::app::AppState;
+use backend::jobs::{JOBS, run_due_jobs};
+use backend::mail::MemoryMailer;
+use chrono::{Duration, Utc};
+use common::{add_member_request, create_test_server, test_config};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn due_jobs_run_once_per_occurrence() -> Result<(), Error> {
+    let (server, pool, _container) = create_test_server().await?;
+    let state = AppState {
+        pool: pool.clone(),
+        config: Arc::new(test_config()),
+        mailer: Arc::new(MemoryMailer::default()),
+        sms: None,
+    };
+
+    add_member_request(&server).await?.assert_status_ok();
+    let client = pool.get().await?;
+    client
+        .execute(
+            "INSERT INTO password_reset_token (member_id, expires_at) VALUES ('AB1234', NOW() - INTERVAL '1 hour')",
+            &[],
+        )
+        .await?;
+
+    client
+        .execute(
+            "INSERT INTO job_run (job_name, started_at) VALUES ('former_job', NOW() - INTERVAL '40 days'), ('former_job', NOW() - INTERVAL '35 days')",
+            &[],
+        )
+        .await?;
+
+    client
+        .execute(
+            "INSERT INTO email_outbox (recipient, subject, html_body, text_body, status, sent_at) VALUES ('john.doe@email.com', 'Old', '', '', 'sent', NOW() - INTERVAL '40 days'), ('john.doe@email.com', 'Recent', '', '', 'sent', NOW() - INTERVAL '1 day')",
+            &[],
+        )
+        .await?;
+
+    // Jobs which never ran are due
+    let ran: Vec<&str> = run_due_jobs(&state, Utc::now()).await?;
+    assert_eq!(ran.len(), JOBS.len());
+
+    // Old runs are deleted, except the last one of every job
+    let former_runs: i64 = client
+        .query_one(
+            "SELECT COUNT(*) FROM job_run WHERE job_name = 'former_job'",
+            &[],
+        )
+        .await?
+        .get(0);
+    assert_eq!(former_runs, 1);
+
+    // Sent emails are deleted once past their retention
+    let sent_emails: i64 = client
+        .query_one("SELECT COUNT(*) FROM email_outbox", &[])
+        .await?
+        .get(0);
+    assert_eq!(sent_emails, 1);
+
+    let tokens: i64 = client
+        .query_one("SELECT COUNT(*) FROM password_reset_token", &[])
+        .await?
+        .get(0);
+    assert_eq!(tokens, 0);
+
+    let ran: Vec<&str> = run_due_jobs(&state, Utc::now()).await?;
+    assert!(ran.is_empty());
+
+    // The weekly jobs are due again after their next occurrence
+    let ran: Vec<&str> = run_due_jobs(&state, Utc::now() + Duration::days(8)).await?;
+    assert_eq!(ran.len(), JOBS.len());
+
+    let succeeded_runs: i64 = client
+        .query_one("SELECT COUNT(*) FROM job_run WHERE succeeded", &[])
+        .await?
+        .get(0);
+    assert_eq!(succeeded_runs, 2 * JOBS.len() as i64);
+
+    Ok(())
+}
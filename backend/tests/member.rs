@@ -5,7 +5,7 @@ use backend::{
     db::{models::Member, queries},
     utils::{hash_password, is_valid_argon2id},
 };
-use common::{add_member_request, create_test_server};
+use common::{add_member_request, admin_token, create_test_server, member_token};
 use deadpool_postgres::{Client, Pool};
 use serde::Deserialize;
 use serde_json::json;
@@ -51,7 +51,10 @@ async fn get_member() -> Result<(), anyhow::Error> {
     let response_body: AddMemberResponse = add_member_res.json();
     let member_id: String = response_body.member_id;
 
-    let get_member_res: TestResponse = server.get(&format!("/member/{member_id}")).await;
+    let get_member_res: TestResponse = server
+        .get(&format!("/member/{member_id}"))
+        .authorization_bearer(member_token(&member_id))
+        .await;
     get_member_res.assert_status_ok();
     let member_from_server: Member = get_member_res.json();
 
@@ -91,6 +94,7 @@ async fn update_member() -> Result<(), anyhow::Error> {
 
     let update_member_res = server
         .patch("/member-with-password")
+        .authorization_bearer(member_token(&member_id))
         .json(&updated_member)
         .await;
     update_member_res.assert_status_ok();
@@ -121,10 +125,16 @@ async fn delete_member() -> Result<(), anyhow::Error> {
     let response_body: AddMemberResponse = add_member_res.json();
     let member_id: String = response_body.member_id;
 
-    let delete_member_res: TestResponse = server.delete(&format!("/member/{member_id}")).await;
+    let delete_member_res: TestResponse = server
+        .delete(&format!("/member/{member_id}"))
+        .authorization_bearer(admin_token())
+        .await;
     delete_member_res.assert_status_ok();
 
-    let get_member_res: TestResponse = server.get(&format!("/member/{member_id}")).await;
+    let get_member_res: TestResponse = server
+        .get(&format!("/member/{member_id}"))
+        .authorization_bearer(admin_token())
+        .await;
     get_member_res.assert_status_not_found();
 
     let client: Client = pool.get().await?;
@@ -135,3 +145,91 @@ async fn delete_member() -> Result<(), anyhow::Error> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn get_all_members_requires_token() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let get_members_res: TestResponse = server.get("/members").await;
+    get_members_res.assert_status_unauthorized();
+
+    let get_members_res: TestResponse = server
+        .get("/members")
+        .authorization_bearer("not-a-jwt")
+        .await;
+    get_members_res.assert_status_unauthorized();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_routes_reject_members() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let add_member_res: TestResponse = add_member_request(&server).await?;
+    let response_body: AddMemberResponse = add_member_res.json();
+    let member_id: String = response_body.member_id;
+    let token: String = member_token(&member_id);
+
+    let get_members_res: TestResponse = server.get("/members").authorization_bearer(&token).await;
+    get_members_res.assert_status_forbidden();
+
+    let delete_member_res: TestResponse = server
+        .delete(&format!("/member/{member_id}"))
+        .authorization_bearer(&token)
+        .await;
+    delete_member_res.assert_status_forbidden();
+
+    let get_members_res: TestResponse = server
+        .get("/members")
+        .authorization_bearer(admin_token())
+        .await;
+    get_members_res.assert_status_ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn members_cannot_touch_other_profiles() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let add_member_res: TestResponse = add_member_request(&server).await?;
+    let response_body: AddMemberResponse = add_member_res.json();
+    let member_id: String = response_body.member_id;
+    let token: String = member_token("ZZ9999");
+
+    let get_member_res: TestResponse = server
+        .get(&format!("/member/{member_id}"))
+        .authorization_bearer(&token)
+        .await;
+    get_member_res.assert_status_forbidden();
+
+    let update_member_res: TestResponse = server
+        .patch("/member")
+        .authorization_bearer(&token)
+        .json(&json!({
+            "id": member_id,
+            "phone": "9876543210",
+            "email": "",
+            "first_name": "",
+            "last_name": "",
+        }))
+        .await;
+    update_member_res.assert_status_forbidden();
+
+    let update_password_res: TestResponse = server
+        .patch("/password")
+        .authorization_bearer(&token)
+        .json(&json!({
+            "id": member_id,
+            "current_password": response_body.otp,
+            "new_password": "new-password",
+        }))
+        .await;
+    update_password_res.assert_status_forbidden();
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use anyhow::Error;
+use backend::db::migrations::{self, MIGRATIONS, MigrationError};
+use common::create_test_server;
+
+#[tokio::test]
+async fn migrations_are_applied_once() -> Result<(), Error> {
+    let (_server, pool, _container) = create_test_server().await?;
+    let mut client = pool.get().await?;
+
+    // The test server has already migrated the database
+    let applied: Vec<i32> = migrations::run(&mut client).await?;
+    assert!(applied.is_empty());
+
+    let count: i64 = client
+        .query_one("SELECT COUNT(*) FROM schema_migrations", &[])
+        .await?
+        .get(0);
+    assert_eq!(count, MIGRATIONS.len() as i64);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn modified_migration_is_rejected() -> Result<(), Error> {
+    let (_server, pool, _container) = create_test_server().await?;
+    let mut client = pool.get().await?;
+
+    client
+        .execute(
+            "UPDATE schema_migrations SET checksum = repeat('0', 64) WHERE version = 1",
+            &[],
+        )
+        .await?;
+
+    let result = migrations::run(&mut client).await;
+    assert!(matches!(
+        result,
+        Err(MigrationError::ChecksumMismatch { version: 1, .. })
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use axum_test::{TestResponse, TestServer};
+use chrono::{Duration, NaiveDate, Weekday};
+use common::{
+    add_member_request, admin_token, create_test_server, member_token, next_weekday,
+    reservation_request,
+};
+use deadpool_postgres::Pool;
+use serde_json::{Value, json};
+use testcontainers::{ContainerAsync, GenericImage};
+
+async fn day_slots(server: &TestServer, date: NaiveDate) -> Vec<Value> {
+    let availability: Value = server
+        .get(&format!("/availability?from={date}&to={date}"))
+        .await
+        .json();
+    availability["days"][0]["slots"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn bookings_outside_opening_hours_are_rejected() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+
+    let night_res: TestResponse = reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        next_weekday(Weekday::Tue),
+        "03:00:00",
+    )
+    .await;
+    night_res.assert_status_unprocessable_entity();
+    let error: Value = night_res.json();
+    assert!(
+        error["error"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("16:00, 17:00, 18:00, 19:00, 20:00")
+    );
+
+    let closed_res: TestResponse = reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        next_weekday(Weekday::Wed),
+        "17:00:00",
+    )
+    .await;
+    closed_res.assert_status_unprocessable_entity();
+    let error: Value = closed_res.json();
+    assert!(
+        error["error"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("closed")
+    );
+
+    reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        next_weekday(Weekday::Tue),
+        "20:00:00",
+    )
+    .await
+    .assert_status_ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn seasonal_and_court_hours_take_precedence() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+
+    let tuesday: NaiveDate = next_weekday(Weekday::Tue);
+    let saturday: NaiveDate = next_weekday(Weekday::Sat);
+    let closure = json!({
+        "court_number": null,
+        "weekday": 2,
+        "opens_at": null,
+        "closes_at": null,
+        "slot_minutes": 60,
+        "valid_from": tuesday,
+        "valid_until": tuesday,
+        "is_closed": true
+    });
+    let morning = json!({
+        "court_number": 1,
+        "weekday": 6,
+        "opens_at": "09:00:00",
+        "closes_at": "10:30:00",
+        "slot_minutes": 30,
+        "valid_from": null,
+        "valid_until": null,
+        "is_closed": false
+    });
+
+    server
+        .post("/opening-hours")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&closure)
+        .await
+        .assert_status_forbidden();
+    for hours in [&closure, &morning] {
+        server
+            .post("/opening-hours")
+            .authorization_bearer(admin_token())
+            .json(hours)
+            .await
+            .assert_status_ok();
+    }
+
+    assert!(day_slots(&server, tuesday).await.is_empty());
+    assert_eq!(
+        day_slots(&server, tuesday + Duration::days(7)).await.len(),
+        5
+    );
+    reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        tuesday,
+        "17:00:00",
+    )
+    .await
+    .assert_status_unprocessable_entity();
+
+    let saturday_slots: Vec<Value> = day_slots(&server, saturday).await;
+    let starts: Vec<&str> = saturday_slots
+        .iter()
+        .filter_map(|slot| slot["start_time"].as_str())
+        .collect();
+    assert_eq!(starts, vec!["09:00:00", "09:30:00", "10:00:00"]);
+    assert_eq!(
+        saturday_slots[0]["courts"].as_array().map(Vec::len),
+        Some(1)
+    );
+
+    reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        1,
+        saturday,
+        "09:00:00",
+    )
+    .await
+    .assert_status_ok();
+    reservation_request(
+        &server,
+        member_token("AB1234"),
+        "AB1234",
+        2,
+        saturday,
+        "09:00:00",
+    )
+    .await
+    .assert_status_unprocessable_entity();
+
+    Ok(())
+}
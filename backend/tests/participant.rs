@@ -0,0 +1,181 @@
+mod common;
+use crate::common::{
+    add_member_request, add_other_member_request, admin_token, member_token, next_tuesday, reserve,
+};
+use axum_test::{TestResponse, TestServer};
+use backend::db::queries;
+use chrono::NaiveDate;
+use common::create_test_server;
+use deadpool_postgres::{Client, Pool};
+use serde_json::{Value, json};
+use testcontainers::{ContainerAsync, GenericImage};
+
+async fn set_participants(server: &TestServer, reservation_id: &str, body: Value) -> TestResponse {
+    server
+        .put(&format!("/reservation/{reservation_id}/participants"))
+        .authorization_bearer(member_token("AB1234"))
+        .json(&body)
+        .await
+}
+
+#[tokio::test]
+async fn invite_members_and_guests() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    add_other_member_request(&server, "CD5678", "0223456789")
+        .await
+        .assert_status_ok();
+    let tuesday: NaiveDate = next_tuesday();
+    let reservation_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+
+    for invalid in [
+        json!({ "member_ids": ["AB1234"] }),
+        json!({ "guest_names": ["A", "B", "C", "D"] }),
+        json!({ "guest_names": [" "] }),
+        json!({ "member_ids": ["ZZ9999"] }),
+    ] {
+        set_participants(&server, &reservation_id, invalid)
+            .await
+            .assert_status_unprocessable_entity();
+    }
+
+    let participants_res: TestResponse = set_participants(
+        &server,
+        &reservation_id,
+        json!({ "member_ids": ["CD5678"], "guest_names": ["Max Guest"] }),
+    )
+    .await;
+    participants_res.assert_status_ok();
+    let participants: Vec<Value> = participants_res.json();
+    assert_eq!(participants.len(), 2);
+    assert_eq!(participants[0]["status"], "invited");
+    assert_eq!(participants[1]["status"], "accepted");
+
+    let answer = json!({ "member_id": "CD5678", "accept": true });
+    server
+        .patch(&format!("/reservation/{reservation_id}/participation"))
+        .authorization_bearer(member_token("AB1234"))
+        .json(&answer)
+        .await
+        .assert_status_forbidden();
+    server
+        .patch(&format!("/reservation/{reservation_id}/participation"))
+        .authorization_bearer(member_token("CD5678"))
+        .json(&answer)
+        .await
+        .assert_status_ok();
+
+    let reservations: Vec<Value> = server
+        .get(&format!("/reservations/{tuesday}"))
+        .authorization_bearer(member_token("CD5678"))
+        .await
+        .json();
+    let participants: &Value = &reservations[0]["participants"];
+    assert_eq!(participants[0]["member_first_name"], "Jane");
+    assert_eq!(participants[0]["status"], "accepted");
+    assert_eq!(participants[1]["guest_name"], "Max Guest");
+
+    // Replacing the guests keeps the answer of the members still invited
+    set_participants(
+        &server,
+        &reservation_id,
+        json!({ "member_ids": ["CD5678"] }),
+    )
+    .await
+    .assert_status_ok();
+    let reservations: Vec<Value> = server
+        .get(&format!("/reservations/{tuesday}"))
+        .authorization_bearer(member_token("CD5678"))
+        .await
+        .json();
+    assert_eq!(
+        reservations[0]["participants"],
+        json!([{
+            "id": participants[0]["id"],
+            "reservation_id": reservation_id,
+            "member_id": "CD5678",
+            "guest_name": null,
+            "status": "accepted",
+            "member_first_name": "Jane",
+            "member_last_name": "Doe"
+        }])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn participations_count_towards_quotas() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    add_other_member_request(&server, "CD5678", "0223456789")
+        .await
+        .assert_status_ok();
+    let tuesday: NaiveDate = next_tuesday();
+    let client: Client = pool.get().await?;
+
+    let set_rules = |rules: Value| {
+        server
+            .put("/booking-rules")
+            .authorization_bearer(admin_token())
+            .json(&rules)
+    };
+    set_rules(json!({ "max_per_day": 1, "count_participants": true }))
+        .await
+        .assert_status_ok();
+
+    // Pending invitations do not count, so the quota is only checked on acceptance
+    let reservation_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+    let answer = |accept: bool| {
+        server
+            .patch(&format!("/reservation/{reservation_id}/participation"))
+            .authorization_bearer(member_token("CD5678"))
+            .json(&json!({ "member_id": "CD5678", "accept": accept }))
+    };
+    set_participants(
+        &server,
+        &reservation_id,
+        json!({ "member_ids": ["CD5678"] }),
+    )
+    .await
+    .assert_status_ok();
+    let own_id: String = reserve(&server, "CD5678", 2, tuesday).await.json();
+    let accept_res: TestResponse = answer(true).await;
+    accept_res.assert_status_unprocessable_entity();
+    let body: Value = accept_res.json();
+    assert_eq!(body["violations"][0]["rule"], "max_per_day");
+
+    // Suspended members cannot join, and the rules other than quotas do not apply
+    server
+        .delete(&format!("/reservation/{own_id}"))
+        .authorization_bearer(member_token("CD5678"))
+        .await
+        .assert_status_ok();
+    queries::member::set_suspended_until(&client, "CD5678", Some(&tuesday)).await?;
+    answer(true).await.assert_status_forbidden();
+    queries::member::set_suspended_until(&client, "CD5678", None).await?;
+    set_rules(json!({
+        "max_per_day": 1,
+        "max_days_in_advance": 1,
+        "count_participants": true
+    }))
+    .await
+    .assert_status_ok();
+    answer(true).await.assert_status_ok();
+
+    // Accepted invitations count, declined ones do not
+    set_rules(json!({ "max_per_day": 1, "count_participants": true }))
+        .await
+        .assert_status_ok();
+    reserve(&server, "CD5678", 2, tuesday)
+        .await
+        .assert_status_unprocessable_entity();
+    answer(false).await.assert_status_ok();
+    reserve(&server, "CD5678", 2, tuesday)
+        .await
+        .assert_status_ok();
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+mod common;
+use crate::common::{add_member_request, member_token, next_tuesday, reserve, test_config};
+use axum_test::TestServer;
+use backend::api::app::AppState;
+use backend::mail::{Email, MemoryMailer, deliver_outbox};
+use backend::reminders::send_reminders;
+use backend::sms::{MemorySmsGateway, Sms};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use common::create_test_server_with_mailer;
+use deadpool_postgres::{Client, Pool};
+use serde_json::json;
+use std::sync::Arc;
+use testcontainers::{ContainerAsync, GenericImage};
+
+fn at(date: NaiveDate, hour: u32, minute: u32) -> NaiveDateTime {
+    date.and_time(NaiveTime::from_hms_opt(hour, minute, 0).expect("Invalid time."))
+}
+
+#[tokio::test]
+async fn reminders_are_sent_once_on_the_chosen_channels() -> Result<(), anyhow::Error> {
+    let (server, pool, _container, mailer): (
+        TestServer,
+        Pool,
+        ContainerAsync<GenericImage>,
+        Arc<MemoryMailer>,
+    ) = create_test_server_with_mailer().await?;
+    let gateway: Arc<MemorySmsGateway> = Arc::new(MemorySmsGateway::default());
+    let state = AppState {
+        pool: pool.clone(),
+        config: Arc::new(test_config()),
+        mailer: mailer.clone(),
+        sms: Some(gateway.clone()),
+    };
+    let mut client: Client = pool.get().await?;
+    add_member_request(&server).await?;
+    let tuesday: NaiveDate = next_tuesday();
+
+    // Text messages are opted in to, unlike emails
+    server
+        .patch("/member")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&json!({
+            "id": "AB1234",
+            "phone": "0123456789",
+            "email": "john.doe@email.com",
+            "first_name": "John",
+            "last_name": "Doe",
+            "sms_reminders": true
+        }))
+        .await
+        .assert_status_ok();
+
+    let reservation_id: String = reserve(&server, "AB1234", 1, tuesday).await.json();
+    // Confirmation
+    assert_eq!(deliver_outbox(&client, mailer.as_ref()).await?, 1);
+
+    // Members are reminded two hours before the start by default
+    assert_eq!(
+        send_reminders(&state, &mut client, at(tuesday, 14, 55)).await?,
+        0
+    );
+    assert_eq!(
+        send_reminders(&state, &mut client, at(tuesday, 15, 0)).await?,
+        2
+    );
+    assert_eq!(deliver_outbox(&client, mailer.as_ref()).await?, 1);
+    let email: Email = mailer.sent().pop().expect("A reminder should be emailed.");
+    assert_eq!(email.to, "john.doe@email.com");
+    assert_eq!(email.subject, "Beach Garden SXM - Rappel de réservation");
+    assert!(email.text_body.contains("terrain 1"));
+    assert_eq!(
+        gateway.sent(),
+        vec![Sms {
+            to: "0123456789".to_string(),
+            body: format!(
+                "Beach Garden SXM : rappel de votre réservation du terrain 1 le {} à 17:00.",
+                tuesday.format("%d/%m/%Y")
+            ),
+        }]
+    );
+
+    // Reruns do not remind again
+    assert_eq!(
+        send_reminders(&state, &mut client, at(tuesday, 15, 0)).await?,
+        0
+    );
+    assert_eq!(
+        send_reminders(&state, &mut client, at(tuesday, 15, 5)).await?,
+        0
+    );
+
+    // Moved reservations are reminded anew, on the channels the member did not opt out of
+    server
+        .patch("/member")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&json!({
+            "id": "AB1234",
+            "phone": "0123456789",
+            "email": "john.doe@email.com",
+            "first_name": "John",
+            "last_name": "Doe",
+            "email_reminders": false
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .patch("/reservation")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&json!({
+            "id": reservation_id,
+            "member_id": "AB1234",
+            "court_number": 1,
+            "reservation_date": tuesday,
+            "start_time": "18:00:00",
+            "duration_minutes": 60
+        }))
+        .await
+        .assert_status_ok();
+    // Modification
+    assert_eq!(deliver_outbox(&client, mailer.as_ref()).await?, 1);
+
+    assert_eq!(
+        send_reminders(&state, &mut client, at(tuesday, 16, 0)).await?,
+        1
+    );
+    assert_eq!(deliver_outbox(&client, mailer.as_ref()).await?, 0);
+    assert_eq!(gateway.sent().len(), 2);
+    assert!(gateway.sent()[1].body.contains("18:00"));
+
+    // Cancelled reservations are not reminded
+    server
+        .patch("/member")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&json!({
+            "id": "AB1234",
+            "phone": "0123456789",
+            "email": "john.doe@email.com",
+            "first_name": "John",
+            "last_name": "Doe",
+            "email_reminders": true
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_ok();
+    assert_eq!(
+        send_reminders(&state, &mut client, at(tuesday, 16, 5)).await?,
+        0
+    );
+
+    Ok(())
+}
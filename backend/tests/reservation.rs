@@ -1,8 +1,11 @@
 mod common;
-use crate::common::add_member_request;
+use crate::common::{add_member_request, admin_token, member_token};
 use axum_test::{TestResponse, TestServer};
-use backend::db::{models::Reservation, queries};
-use chrono::NaiveDate;
+use backend::db::{
+    models::{Reservation, ReservationWithNames},
+    queries,
+};
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
 use common::create_test_server;
 use deadpool_postgres::{Client, Pool};
 use serde_json::json;
@@ -16,9 +19,14 @@ async fn add_reservation_request(server: &TestServer) -> Result<TestResponse, an
         "member_id": "AB1234",
         "court_number": 1,
         "reservation_date": "2025-08-07",
-        "reservation_time": 17
+        "start_time": "17:00:00",
+        "duration_minutes": 60
     });
-    Ok(server.post("/reservation").json(&new_reservation).await)
+    Ok(server
+        .post("/reservation")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&new_reservation)
+        .await)
 }
 
 #[tokio::test]
@@ -41,7 +49,11 @@ async fn add_reservation() -> Result<(), anyhow::Error> {
         reservation_from_db.reservation_date,
         NaiveDate::from_ymd_opt(2025, 8, 7).expect("Could not create date.")
     );
-    assert_eq!(reservation_from_db.reservation_time, 17);
+    assert_eq!(
+        reservation_from_db.start_time,
+        NaiveTime::from_hms_opt(17, 0, 0).expect("Could not create time.")
+    );
+    assert_eq!(reservation_from_db.duration_minutes, 60);
 
     Ok(())
 }
@@ -54,8 +66,10 @@ async fn get_reservation() -> Result<(), anyhow::Error> {
     let add_reservation_res: TestResponse = add_reservation_request(&server).await?;
     let reservation_id: String = add_reservation_res.json();
 
-    let get_reservation_res: TestResponse =
-        server.get(&format!("/reservation/{reservation_id}")).await;
+    let get_reservation_res: TestResponse = server
+        .get(&format!("/reservation/{reservation_id}"))
+        .authorization_bearer(member_token("AB1234"))
+        .await;
     get_reservation_res.assert_status_ok();
     let reservation_from_server: Reservation = get_reservation_res.json();
 
@@ -66,33 +80,322 @@ async fn get_reservation() -> Result<(), anyhow::Error> {
         reservation_from_server.reservation_date,
         NaiveDate::from_ymd_opt(2025, 8, 7).expect("Could not create date.")
     );
-    assert_eq!(reservation_from_server.reservation_time, 17);
+    assert_eq!(
+        reservation_from_server.start_time,
+        NaiveTime::from_hms_opt(17, 0, 0).expect("Could not create time.")
+    );
+    assert_eq!(reservation_from_server.duration_minutes, 60);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancel_reservation() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let add_reservation_res: TestResponse = add_reservation_request(&server).await?;
+    let reservation_id: String = add_reservation_res.json();
+
+    let cancel_reservation_res: TestResponse = server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .add_query_param("reason", "Injured")
+        .authorization_bearer(member_token("AB1234"))
+        .await;
+    cancel_reservation_res.assert_status_ok();
+
+    // The reservation is kept with its cancellation, but no longer listed
+    let client: Client = pool.get().await?;
+    let reservation_from_db: Reservation =
+        queries::reservation::get_reservation(&client, &reservation_id).await?;
+    let cancellation = reservation_from_db
+        .cancellation
+        .expect("Reservation should be cancelled.");
+    assert_eq!(cancellation.cancelled_by.as_deref(), Some("AB1234"));
+    assert_eq!(cancellation.reason.as_deref(), Some("Injured"));
+    assert!(!cancellation.is_late);
+
+    let reservations: Vec<ReservationWithNames> = server
+        .get("/reservations/2025-08-07")
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .json();
+    assert!(reservations.is_empty());
+
+    server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_not_found();
 
     Ok(())
 }
 
 #[tokio::test]
-async fn delete_reservation() -> Result<(), anyhow::Error> {
+async fn reservation_routes_require_token() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let add_reservation_res: TestResponse = server
+        .post("/reservation")
+        .json(&json!({
+            "id": "CD5678",
+            "member_id": "AB1234",
+            "court_number": 1,
+            "reservation_date": "2025-08-07",
+            "start_time": "17:00:00",
+            "duration_minutes": 60
+        }))
+        .await;
+    add_reservation_res.assert_status_unauthorized();
+
+    let get_reservations_res: TestResponse = server.get("/reservations/2025-08-07").await;
+    get_reservations_res.assert_status_unauthorized();
+
+    let delete_reservation_res: TestResponse = server
+        .delete("/reservation/CD5678")
+        .authorization_bearer("not-a-jwt")
+        .await;
+    delete_reservation_res.assert_status_unauthorized();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_reservation() -> Result<(), anyhow::Error> {
     let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
         create_test_server().await?;
 
     let add_reservation_res: TestResponse = add_reservation_request(&server).await?;
     let reservation_id: String = add_reservation_res.json();
 
+    let updated_reservation = json!({
+        "id": reservation_id,
+        "member_id": "AB1234",
+        "court_number": 3,
+        "reservation_date": "2025-08-07",
+        "start_time": "18:00:00",
+        "duration_minutes": 60
+    });
+    let update_reservation_res: TestResponse = server
+        .patch("/reservation")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&updated_reservation)
+        .await;
+    update_reservation_res.assert_status_ok();
+
+    let client: Client = pool.get().await?;
+    let reservation_from_db: Reservation =
+        queries::reservation::get_reservation(&client, &reservation_id).await?;
+
+    assert_eq!(reservation_from_db.court_number, 3);
+    assert_eq!(
+        reservation_from_db.start_time,
+        NaiveTime::from_hms_opt(18, 0, 0).expect("Could not create time.")
+    );
+    assert_eq!(reservation_from_db.duration_minutes, 60);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn members_cannot_touch_other_reservations() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+
+    let add_reservation_res: TestResponse = add_reservation_request(&server).await?;
+    let reservation_id: String = add_reservation_res.json();
+
+    let update_reservation_res: TestResponse = server
+        .patch("/reservation")
+        .authorization_bearer(member_token("ZZ9999"))
+        .json(&json!({
+            "id": reservation_id,
+            "member_id": "ZZ9999",
+            "court_number": 2,
+            "reservation_date": "2025-08-07",
+            "start_time": "17:00:00",
+            "duration_minutes": 60
+        }))
+        .await;
+    update_reservation_res.assert_status_forbidden();
+
     let delete_reservation_res: TestResponse = server
         .delete(&format!("/reservation/{reservation_id}"))
+        .authorization_bearer(member_token("ZZ9999"))
+        .await;
+    delete_reservation_res.assert_status_forbidden();
+
+    let add_reservation_res: TestResponse = server
+        .post("/reservation")
+        .authorization_bearer(member_token("ZZ9999"))
+        .json(&json!({
+            "id": "",
+            "member_id": "AB1234",
+            "court_number": 2,
+            "reservation_date": "2025-08-08",
+            "start_time": "17:00:00",
+            "duration_minutes": 60
+        }))
+        .await;
+    add_reservation_res.assert_status_forbidden();
+
+    let delete_reservation_res: TestResponse = server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .authorization_bearer(admin_token())
         .await;
     delete_reservation_res.assert_status_ok();
 
-    let get_reservation_res: TestResponse =
-        server.get(&format!("/reservation/{reservation_id}")).await;
-    get_reservation_res.assert_status_not_found();
+    Ok(())
+}
+
+#[tokio::test]
+async fn variable_length_reservations() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    server
+        .post("/member")
+        .authorization_bearer(admin_token())
+        .json(&json!({
+            "id": "EF9012",
+            "phone": "0987654321",
+            "password": "",
+            "email": "jane.doe@email.com",
+            "first_name": "Jane",
+            "last_name": "Doe",
+        }))
+        .await
+        .assert_status_ok();
 
-    let client: Client = pool.get().await?;
-    let reservation_from_db: Result<Reservation, tokio_postgres::Error> =
-        queries::reservation::get_reservation(&client, &reservation_id).await;
+    let mut tuesday: NaiveDate = Utc::now().date_naive() + Duration::days(7);
+    while tuesday.weekday() != Weekday::Tue {
+        tuesday = tuesday.succ_opt().expect("Could not get next date.");
+    }
+    let reserve = |member_id: &'static str, start_time: &'static str, duration_minutes: i16| {
+        server
+            .post("/reservation")
+            .authorization_bearer(member_token(member_id))
+            .json(&json!({
+                "id": "",
+                "member_id": member_id,
+                "court_number": 1,
+                "reservation_date": tuesday,
+                "start_time": start_time,
+                "duration_minutes": duration_minutes
+            }))
+    };
+
+    reserve("AB1234", "16:00:00", 90).await.assert_status_ok();
+
+    // Overlapping reservations of the court are rejected by the database
+    reserve("EF9012", "17:00:00", 60)
+        .await
+        .assert_status_conflict();
+    // Reservations start at the start of a slot and end before closing time
+    reserve("EF9012", "17:30:00", 30)
+        .await
+        .assert_status_unprocessable_entity();
+    reserve("EF9012", "20:00:00", 120)
+        .await
+        .assert_status_unprocessable_entity();
+    reserve("EF9012", "18:00:00", 45)
+        .await
+        .assert_status_unprocessable_entity();
+    reserve("EF9012", "18:00:00", 120).await.assert_status_ok();
+
+    let reservations: Vec<ReservationWithNames> = server
+        .get(&format!("/reservations/{tuesday}"))
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .json();
+    let ends: Vec<NaiveTime> = reservations.iter().map(|r| r.end_time).collect();
+    assert_eq!(
+        ends,
+        vec![
+            NaiveTime::from_hms_opt(17, 30, 0).expect("Could not create time."),
+            NaiveTime::from_hms_opt(20, 0, 0).expect("Could not create time."),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn my_reservations() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_reservation_request(&server).await?.assert_status_ok();
+
+    let mut tuesday: NaiveDate = Utc::now().date_naive() + Duration::days(7);
+    while tuesday.weekday() != Weekday::Tue {
+        tuesday = tuesday.succ_opt().expect("Could not get next date.");
+    }
+    let mut upcoming_ids: Vec<String> = Vec::new();
+    for date in [tuesday + Duration::days(7), tuesday] {
+        let res: TestResponse = server
+            .post("/reservation")
+            .authorization_bearer(member_token("AB1234"))
+            .json(&json!({
+                "id": "",
+                "member_id": "AB1234",
+                "court_number": 1,
+                "reservation_date": date,
+                "start_time": "17:00:00",
+                "duration_minutes": 60
+            }))
+            .await;
+        res.assert_status_ok();
+        upcoming_ids.push(res.json());
+    }
+    // Cancelled reservations are left out of the upcoming ones
+    server
+        .delete(&format!("/reservation/{}", upcoming_ids[0]))
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_ok();
+
+    let upcoming: serde_json::Value = server
+        .get("/me/reservations")
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .json();
+    assert_eq!(upcoming["total"], 1);
+    assert_eq!(upcoming["reservations"][0]["id"], upcoming_ids[1]);
+
+    let past: serde_json::Value = server
+        .get("/me/reservations?status=past&page=1")
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .json();
+    assert_eq!(past["total"], 1);
+    assert_eq!(past["reservations"][0]["id"], "CD5678");
+
+    let next_page: serde_json::Value = server
+        .get("/me/reservations?status=past&page=2")
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .json();
+    assert_eq!(next_page["page"], 2);
+    assert_eq!(next_page["reservations"], json!([]));
+    assert_eq!(next_page["total"], 1);
+    server
+        .get(&format!("/me/reservations?page={}", i64::MAX))
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_bad_request();
+
+    let others: serde_json::Value = server
+        .get("/me/reservations")
+        .authorization_bearer(member_token("EF9012"))
+        .await
+        .json();
+    assert_eq!(others["total"], 0);
 
-    assert!(reservation_from_db.is_err());
+    server
+        .get("/me/reservations")
+        .await
+        .assert_status_unauthorized();
 
     Ok(())
 }
@@ -0,0 +1,256 @@
+mod common;
+use crate::common::{add_member_request, admin_token, member_token};
+use axum_test::{TestResponse, TestServer};
+use backend::db::{
+    models::{Reservation, ReservationSeries},
+    queries,
+};
+use chrono::{NaiveDate, NaiveTime};
+use common::create_test_server;
+use deadpool_postgres::{Client, Pool};
+use serde_json::{Value, json};
+use testcontainers::{ContainerAsync, GenericImage};
+
+// A Tuesday, the club being open from 16:00 to 21:00 on Tuesdays
+fn first_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2030, 1, 1).expect("Could not create date.")
+}
+
+fn weekly_series(count: i16) -> Value {
+    json!({
+        "member_id": "AB1234",
+        "court_number": 1,
+        "start_time": "17:00:00",
+        "duration_minutes": 60,
+        "first_date": first_date(),
+        "frequency": "weekly",
+        "count": count
+    })
+}
+
+/// Emails queued for AB1234 with a calendar event, one per booked, moved or cancelled
+/// occurrence.
+async fn calendar_emails(pool: &Pool) -> anyhow::Result<i64> {
+    let client: Client = pool.get().await?;
+    Ok(client
+        .query_one(
+            "SELECT COUNT(*) FROM email_outbox WHERE recipient = 'john.doe@email.com' AND attachment_filename IS NOT NULL",
+            &[],
+        )
+        .await?
+        .get(0))
+}
+
+async fn series_reservations(pool: &Pool, series_id: &str) -> anyhow::Result<Vec<Reservation>> {
+    let client: Client = pool.get().await?;
+    Ok(
+        queries::reservation::get_series_reservations_from(&client, series_id, &first_date())
+            .await?,
+    )
+}
+
+#[tokio::test]
+async fn add_weekly_series() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+
+    let add_series_res: TestResponse = server
+        .post("/reservation-series")
+        .authorization_bearer(admin_token())
+        .json(&weekly_series(4))
+        .await;
+    add_series_res.assert_status_ok();
+    let body: Value = add_series_res.json();
+    let series_id: &str = body["id"].as_str().expect("Series ID should be a string.");
+    assert_eq!(body["reservations"].as_array().map(Vec::len), Some(4));
+    assert_eq!(body["conflicts"], json!([]));
+
+    let reservations: Vec<Reservation> = series_reservations(&pool, series_id).await?;
+    let dates: Vec<NaiveDate> = reservations.iter().map(|r| r.reservation_date).collect();
+    assert_eq!(
+        dates,
+        (0..4)
+            .map(|week| first_date() + chrono::Duration::weeks(week))
+            .collect::<Vec<NaiveDate>>()
+    );
+    assert!(
+        reservations
+            .iter()
+            .all(|r| r.series_id.as_deref() == Some(series_id))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn series_requires_admin_and_valid_recurrence() -> Result<(), anyhow::Error> {
+    let (server, _pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+
+    let member_res: TestResponse = server
+        .post("/reservation-series")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&weekly_series(4))
+        .await;
+    member_res.assert_status_forbidden();
+
+    let too_long_res: TestResponse = server
+        .post("/reservation-series")
+        .authorization_bearer(admin_token())
+        .json(&weekly_series(53))
+        .await;
+    too_long_res.assert_status_unprocessable_entity();
+
+    let mut both_ends: Value = weekly_series(4);
+    both_ends["until"] = json!("2030-03-01");
+    let both_ends_res: TestResponse = server
+        .post("/reservation-series")
+        .authorization_bearer(admin_token())
+        .json(&both_ends)
+        .await;
+    both_ends_res.assert_status_unprocessable_entity();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn series_conflicts() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+
+    // Another booking of the court overlapping the third occurrence
+    server
+        .post("/reservation")
+        .authorization_bearer(admin_token())
+        .json(&json!({
+            "id": "ZZ0000",
+            "member_id": "AB1234",
+            "court_number": 1,
+            "reservation_date": "2030-01-15",
+            "start_time": "17:00:00",
+            "duration_minutes": 30
+        }))
+        .await
+        .assert_status_ok();
+
+    let conflict_res: TestResponse = server
+        .post("/reservation-series")
+        .authorization_bearer(admin_token())
+        .json(&weekly_series(4))
+        .await;
+    conflict_res.assert_status_conflict();
+    let body: Value = conflict_res.json();
+    assert_eq!(body["conflicts"].as_array().map(Vec::len), Some(1));
+    assert_eq!(body["conflicts"][0]["date"], json!("2030-01-15"));
+
+    // Nothing was created
+    let client: Client = pool.get().await?;
+    let booked: Vec<Reservation> = queries::reservation::get_reservations_between(
+        &client,
+        &first_date(),
+        &NaiveDate::from_ymd_opt(2030, 2, 1).expect("Could not create date."),
+        None,
+    )
+    .await?;
+    assert_eq!(booked.len(), 1);
+
+    let mut skipping: Value = weekly_series(4);
+    skipping["skip_conflicts"] = json!(true);
+    let skip_res: TestResponse = server
+        .post("/reservation-series")
+        .authorization_bearer(admin_token())
+        .json(&skipping)
+        .await;
+    skip_res.assert_status_ok();
+    let body: Value = skip_res.json();
+    assert_eq!(body["reservations"].as_array().map(Vec::len), Some(3));
+    assert_eq!(body["conflicts"][0]["date"], json!("2030-01-15"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_and_delete_following_occurrences() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+
+    let body: Value = server
+        .post("/reservation-series")
+        .authorization_bearer(admin_token())
+        .json(&weekly_series(4))
+        .await
+        .json();
+    let series_id: String = body["id"]
+        .as_str()
+        .expect("Series ID should be a string.")
+        .to_string();
+    let occurrences: Vec<Reservation> = series_reservations(&pool, &series_id).await?;
+    let second: &Reservation = &occurrences[1];
+    assert_eq!(calendar_emails(&pool).await?, 4);
+
+    let payload = json!({
+        "id": second.id,
+        "member_id": "AB1234",
+        "court_number": 2,
+        "reservation_date": second.reservation_date,
+        "start_time": "18:00:00",
+        "duration_minutes": 60
+    });
+
+    // Members can only change a single occurrence
+    server
+        .patch("/reservation")
+        .add_query_param("scope", "following")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&payload)
+        .await
+        .assert_status_forbidden();
+
+    server
+        .patch("/reservation")
+        .add_query_param("scope", "following")
+        .authorization_bearer(admin_token())
+        .json(&payload)
+        .await
+        .assert_status_ok();
+
+    // The series is split: the first occurrence is left as is
+    let original: Vec<Reservation> = series_reservations(&pool, &series_id).await?;
+    assert_eq!(original.len(), 1);
+    assert_eq!(original[0].court_number, 1);
+
+    let client: Client = pool.get().await?;
+    let moved: Reservation = queries::reservation::get_reservation(&client, &second.id).await?;
+    let new_series_id: String = moved.series_id.expect("Occurrence should be in a series.");
+    assert_ne!(new_series_id, series_id);
+    let following: Vec<Reservation> = series_reservations(&pool, &new_series_id).await?;
+    assert_eq!(following.len(), 3);
+    assert!(following.iter().all(|r| r.court_number == 2
+        && r.start_time == NaiveTime::from_hms_opt(18, 0, 0).expect("Could not create time.")));
+    assert_eq!(calendar_emails(&pool).await?, 4 + 3);
+
+    let series: ReservationSeries = queries::reservation_series::get_series(&client, &series_id)
+        .await?
+        .expect("Series should exist.");
+    assert_eq!(
+        series.until_date,
+        Some(second.reservation_date - chrono::Duration::days(1))
+    );
+
+    server
+        .delete(&format!("/reservation/{}", following[1].id))
+        .add_query_param("scope", "following")
+        .authorization_bearer(admin_token())
+        .await
+        .assert_status_ok();
+    let remaining: Vec<Reservation> = series_reservations(&pool, &new_series_id).await?;
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, second.id);
+    assert_eq!(calendar_emails(&pool).await?, 4 + 3 + 2);
+
+    Ok(())
+}
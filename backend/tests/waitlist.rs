@@ -0,0 +1,260 @@
+mod common;
+use crate::common::{
+    add_member_request, add_other_member_request, admin_token, member_token, test_config,
+};
+use axum_test::{TestResponse, TestServer};
+use backend::api::app::AppState;
+use backend::api::wrappers::waitlist::expire_entries;
+use backend::db::{
+    models::{Reservation, WaitlistEntry},
+    queries,
+};
+use backend::mail::MemoryMailer;
+use chrono::NaiveDate;
+use common::create_test_server;
+use deadpool_postgres::{Client, Pool};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use testcontainers::{ContainerAsync, GenericImage};
+use uuid::Uuid;
+
+// A Tuesday, the club being open from 16:00 to 21:00 on Tuesdays
+const DATE: &str = "2030-01-01";
+
+/// Books court 1 at 17:00 for AB1234 and returns the reservation ID.
+async fn book_court(server: &TestServer) -> Result<String, anyhow::Error> {
+    let res: TestResponse = server
+        .post("/reservation")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&json!({
+            "id": "",
+            "member_id": "AB1234",
+            "court_number": 1,
+            "reservation_date": DATE,
+            "start_time": "17:00:00",
+            "duration_minutes": 60
+        }))
+        .await;
+    res.assert_status_ok();
+    Ok(res.json())
+}
+
+async fn join_waitlist(server: &TestServer, member_id: &str, auto_book: bool) -> TestResponse {
+    server
+        .post("/waitlist")
+        .authorization_bearer(member_token(member_id))
+        .json(&json!({
+            "member_id": member_id,
+            "court_number": null,
+            "reservation_date": DATE,
+            "start_time": "17:00:00",
+            "duration_minutes": 60,
+            "auto_book": auto_book
+        }))
+        .await
+}
+
+async fn reservations_of(client: &Client, member_id: &str) -> anyhow::Result<Vec<Reservation>> {
+    let date: NaiveDate = DATE.parse()?;
+    Ok(
+        queries::reservation::get_reservations_between(client, &date, &date, None)
+            .await?
+            .into_iter()
+            .filter(|r| r.member_id == member_id)
+            .collect(),
+    )
+}
+
+#[tokio::test]
+async fn freed_court_is_auto_booked() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    add_other_member_request(&server, "CD5678", "0223456789")
+        .await
+        .assert_status_ok();
+    let reservation_id: String = book_court(&server).await?;
+
+    join_waitlist(&server, "CD5678", true)
+        .await
+        .assert_status_ok();
+
+    // Waiting members only see their own entries
+    let entries: Vec<Value> = server
+        .get("/waitlist")
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .json();
+    assert!(entries.is_empty());
+
+    server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_ok();
+
+    let client: Client = pool.get().await?;
+    let booked: Vec<Reservation> = reservations_of(&client, "CD5678").await?;
+    assert_eq!(booked.len(), 1);
+    assert_eq!(booked[0].court_number, 1);
+    assert!(
+        queries::waitlist::get_entries(&client, None)
+            .await?
+            .is_empty()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn court_freed_by_cancelled_series_is_auto_booked() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    add_other_member_request(&server, "CD5678", "0223456789")
+        .await
+        .assert_status_ok();
+    server
+        .post("/reservation-series")
+        .authorization_bearer(admin_token())
+        .json(&json!({
+            "member_id": "AB1234",
+            "court_number": 1,
+            "start_time": "17:00:00",
+            "duration_minutes": 60,
+            "first_date": DATE,
+            "frequency": "weekly",
+            "count": 2
+        }))
+        .await
+        .assert_status_ok();
+    let client: Client = pool.get().await?;
+    let occurrence: Reservation = reservations_of(&client, "AB1234").await?.remove(0);
+
+    join_waitlist(&server, "CD5678", true)
+        .await
+        .assert_status_ok();
+
+    server
+        .delete(&format!("/reservation/{}", occurrence.id))
+        .add_query_param("scope", "following")
+        .authorization_bearer(admin_token())
+        .await
+        .assert_status_ok();
+
+    let booked: Vec<Reservation> = reservations_of(&client, "CD5678").await?;
+    assert_eq!(booked.len(), 1);
+    assert_eq!(booked[0].court_number, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn freed_court_is_claimed() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    add_other_member_request(&server, "CD5678", "0223456789")
+        .await
+        .assert_status_ok();
+    let reservation_id: String = book_court(&server).await?;
+
+    join_waitlist(&server, "CD5678", false)
+        .await
+        .assert_status_ok();
+    server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_ok();
+
+    // The court is offered but not booked yet
+    let client: Client = pool.get().await?;
+    assert!(reservations_of(&client, "CD5678").await?.is_empty());
+    let entries: Vec<WaitlistEntry> = queries::waitlist::get_entries(&client, None).await?;
+    assert_eq!(entries[0].claim_court_number, Some(1));
+    let token: Uuid = entries[0]
+        .claim_token
+        .expect("A claim link should have been sent.");
+
+    server
+        .post("/waitlist/claim")
+        .authorization_bearer(member_token("AB1234"))
+        .json(&json!({ "token": token }))
+        .await
+        .assert_status_forbidden();
+
+    server
+        .post("/waitlist/claim")
+        .authorization_bearer(member_token("CD5678"))
+        .json(&json!({ "token": token }))
+        .await
+        .assert_status_ok();
+    assert_eq!(reservations_of(&client, "CD5678").await?.len(), 1);
+    let confirmations: i64 = client
+        .query_one(
+            "SELECT COUNT(*) FROM email_outbox WHERE recipient = 'CD5678@email.com' AND attachment_filename IS NOT NULL",
+            &[],
+        )
+        .await?
+        .get(0);
+    assert_eq!(confirmations, 1);
+
+    let reused_res: TestResponse = server
+        .post("/waitlist/claim")
+        .authorization_bearer(member_token("CD5678"))
+        .json(&json!({ "token": token }))
+        .await;
+    reused_res.assert_status(axum::http::StatusCode::GONE);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn expired_claim_goes_to_next_member() -> Result<(), anyhow::Error> {
+    let (server, pool, _container): (TestServer, Pool, ContainerAsync<GenericImage>) =
+        create_test_server().await?;
+    add_member_request(&server).await?;
+    add_other_member_request(&server, "CD5678", "0223456789")
+        .await
+        .assert_status_ok();
+    add_other_member_request(&server, "EF9012", "0323456789")
+        .await
+        .assert_status_ok();
+    let reservation_id: String = book_court(&server).await?;
+
+    join_waitlist(&server, "CD5678", false)
+        .await
+        .assert_status_ok();
+    join_waitlist(&server, "EF9012", false)
+        .await
+        .assert_status_ok();
+    server
+        .delete(&format!("/reservation/{reservation_id}"))
+        .authorization_bearer(member_token("AB1234"))
+        .await
+        .assert_status_ok();
+
+    let mut client: Client = pool.get().await?;
+    client
+        .execute(
+            "UPDATE waitlist_entry SET claim_expires_at = NOW() - INTERVAL '1 minute' WHERE member_id = 'CD5678'",
+            &[],
+        )
+        .await?;
+
+    let state = AppState {
+        pool: pool.clone(),
+        config: Arc::new(test_config()),
+        mailer: Arc::new(MemoryMailer::default()),
+        sms: None,
+    };
+    assert_eq!(expire_entries(&state, &mut client).await?, 1);
+
+    let entries: Vec<WaitlistEntry> = queries::waitlist::get_entries(&client, None).await?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].member_id, "EF9012");
+    assert!(entries[0].claim_token.is_some());
+
+    Ok(())
+}
@@ -4,7 +4,15 @@ import { Spinner } from "@heroui/react"
 import { Link } from "@heroui/link"
 import { button as buttonStyles } from "@heroui/theme"
 import { useState, useEffect } from "react"
-import { Form, Input, Button, addToast } from "@heroui/react"
+import {
+  Form,
+  Input,
+  Button,
+  Select,
+  SelectItem,
+  Switch,
+  addToast
+} from "@heroui/react"
 import * as EmailValidator from "email-validator"
 
 import PhoneInput from "@/components/phone-input"
@@ -14,6 +22,8 @@ import { useAuth, authenticatedFetch } from "@/hooks/useAuth"
 const API_HOST = process.env.NEXT_PUBLIC_API_HOST!
 const API_PORT = process.env.NEXT_PUBLIC_API_PORT
 const API_URL = API_PORT ? `${API_HOST}:${API_PORT}` : API_HOST
+// Only offered when the backend has a text message gateway
+const SMS_ENABLED = process.env.NEXT_PUBLIC_SMS_ENABLED === "true"
 
 type Member = {
   id: string
@@ -22,15 +32,29 @@ type Member = {
   email: string
   first_name: string
   last_name: string
+  locale: Locale
+  email_reminders: boolean
+  sms_reminders: boolean
 }
 
+type Locale = "fr" | "en" | "nl"
+
+const locales: { key: Locale; label: string }[] = [
+  { key: "fr", label: "Français" },
+  { key: "en", label: "English" },
+  { key: "nl", label: "Nederlands" }
+]
+
 const emptyMember: Member = {
   id: "",
   phone: "",
   password: "",
   email: "",
   first_name: "",
-  last_name: ""
+  last_name: "",
+  locale: "fr",
+  email_reminders: true,
+  sms_reminders: false
 }
 
 export default function AccountPage() {
@@ -112,7 +136,10 @@ export default function AccountPage() {
       member.phone !== originalMember.phone ||
       member.first_name !== originalMember.first_name ||
       member.last_name !== originalMember.last_name ||
-      member.email !== originalMember.email
+      member.email !== originalMember.email ||
+      member.locale !== originalMember.locale ||
+      member.email_reminders !== originalMember.email_reminders ||
+      member.sms_reminders !== originalMember.sms_reminders
     )
   }
 
@@ -140,7 +167,9 @@ export default function AccountPage() {
 
     const payload = {
       id: auth.userId,
-      ...data
+      ...data,
+      email_reminders: member.email_reminders,
+      sms_reminders: member.sms_reminders
     }
 
     try {
@@ -276,6 +305,47 @@ export default function AccountPage() {
             }))
           }}
         />
+        <Select
+          disallowEmptySelection
+          label="Langue des emails"
+          labelPlacement="outside"
+          name="locale"
+          selectedKeys={[member.locale]}
+          onSelectionChange={keys => {
+            setMember(prev => ({
+              ...prev!,
+              locale: Array.from(keys)[0] as Locale
+            }))
+          }}
+        >
+          {locales.map(locale => (
+            <SelectItem key={locale.key}>{locale.label}</SelectItem>
+          ))}
+        </Select>
+        <Switch
+          isSelected={member.email_reminders}
+          onValueChange={isSelected => {
+            setMember(prev => ({
+              ...prev!,
+              email_reminders: isSelected
+            }))
+          }}
+        >
+          Rappels de réservation par email
+        </Switch>
+        {SMS_ENABLED && (
+          <Switch
+            isSelected={member.sms_reminders}
+            onValueChange={isSelected => {
+              setMember(prev => ({
+                ...prev!,
+                sms_reminders: isSelected
+              }))
+            }}
+          >
+            Rappels de réservation par SMS
+          </Switch>
+        )}
         <Link
           className={
             buttonStyles({
@@ -5,7 +5,7 @@ import { Form, Input, Button } from "@heroui/react"
 import * as EmailValidator from "email-validator"
 
 import { title } from "@/components/primitives"
-import { useAuth, authenticatedFetch } from "@/hooks/useAuth"
+import { useAuth, authenticatedFetch, refreshAuth } from "@/hooks/useAuth"
 
 const API_HOST = process.env.NEXT_PUBLIC_API_HOST!
 const API_PORT = process.env.NEXT_PUBLIC_API_PORT
@@ -146,33 +146,10 @@ export default function FirstLoginPage() {
 
       if (!response.ok) throw new Error(`Erreur ${response.status}`)
       else {
-        const refreshJwtPayload = { member_id: auth.userId }
-        const refreshResponse = await authenticatedFetch(
-          `${API_URL}/refresh-jwt`,
-          {
-            method: "POST",
-            headers: { "Content-Type": "application/json" },
-            body: JSON.stringify(refreshJwtPayload)
-          }
-        )
-
-        if (refreshResponse.ok) {
-          // Get the new token from response
-          const { token } = await refreshResponse.json()
-
-          // Update the stored auth with new token
-          const storedAuth = JSON.parse(
-            localStorage.getItem("beach_garden_auth") || "{}"
-          )
-          localStorage.setItem(
-            "beach_garden_auth",
-            JSON.stringify({
-              ...storedAuth,
-              token: token,
-              isProfileComplete: true
-            })
-          )
+        // Get a new token reflecting the completed profile
+        const token = await refreshAuth()
 
+        if (token) {
           // Redirect to planning
           window.location.href = "/planning"
         } else {
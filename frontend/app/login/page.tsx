@@ -49,6 +49,16 @@ export default function LogInPage() {
         body: JSON.stringify({ phone: phoneNumber, password })
       })
 
+      // Too many failed attempts, or attempts too close to each other
+      if (response.status === 423 || response.status === 429) {
+        setIsSubmitting(false)
+        addToast({
+          title: `Trop de tentatives de connexion. Veuillez réessayer dans ${response.headers.get("Retry-After") ?? "quelques"} secondes.`,
+          color: "warning"
+        })
+        return
+      }
+
       if (!response.ok) {
         setIsInvalid(true)
         setIsSubmitting(false)
@@ -64,11 +74,12 @@ export default function LogInPage() {
       // Store auth data directly
       const authData = {
         token: data.token,
+        refreshToken: data.refresh_token,
         userId: data.id,
         phone: data.phone,
         isProfileComplete: data.is_profile_complete,
         isAdmin: data.is_admin,
-        expiresAt: Date.now() + 24 * 60 * 60 * 1000
+        expiresAt: Date.now() + data.refresh_token_expires_in * 1000
       }
 
       // Use a try-catch for localStorage to handle any mobile issues
@@ -20,10 +20,59 @@ type Reservation = {
   id: string
   member_id: string
   court_number: number
-  reservation_time: number
+  start_time: string
+  duration_minutes: number
+  end_time: string
   reservation_date: string
   member_first_name: string
   member_last_name: string
+  participants: Participant[]
+}
+
+type Participant = {
+  member_id: string | null
+  guest_name: string | null
+  status: "invited" | "accepted" | "declined"
+  member_first_name: string | null
+  member_last_name: string | null
+}
+
+const participantNames = (participants: Participant[]) =>
+  participants
+    .filter(p => p.status !== "declined")
+    .map(p => p.guest_name ?? `${p.member_first_name} ${p.member_last_name}`)
+    .join(", ")
+
+type Slot = {
+  start_time: string
+  end_time: string
+}
+
+type Court = {
+  number: number
+  name: string
+  is_active: boolean
+}
+
+// Times are "HH:MM:SS" strings, which compare in chronological order
+function overlaps(reservation: Reservation, slot: Slot): boolean {
+  return (
+    reservation.start_time < slot.end_time &&
+    slot.start_time < reservation.end_time
+  )
+}
+
+function toMinutes(time: string): number {
+  const [h, m] = time.split(":").map(Number)
+  return h * 60 + m
+}
+
+function slotMinutes(slot: Slot): number {
+  return toMinutes(slot.end_time) - toMinutes(slot.start_time)
+}
+
+function formatTime(time: string): string {
+  return time.slice(0, 5).replace(":", "h")
 }
 
 const API_HOST = process.env.NEXT_PUBLIC_API_HOST!
@@ -39,6 +88,8 @@ export default function PlanningDatePage() {
   })
   const { date } = useParams() as { date?: string }
   const [reservations, setReservations] = useState<Reservation[]>([])
+  const [courts, setCourts] = useState<Court[]>([])
+  const [slots, setSlots] = useState<Slot[]>([])
   const [displayDate, setDisplayDate] = useState("")
   const [currentUserId, setCurrentUserId] = useState<string | null>(null)
   const [currentUserHasReservation, setCurrentUserHasReservation] =
@@ -78,6 +129,23 @@ export default function PlanningDatePage() {
         if (res2.ok) {
           setReservations(await res2.json())
         }
+
+        const res3 = await fetch(`${API_URL}/courts`, { method: "GET" })
+        if (res3.ok) {
+          const allCourts: Court[] = await res3.json()
+          setCourts(allCourts.filter(c => c.is_active))
+        }
+
+        // Opening hours of the day, as configured by the admins
+        const res4 = await fetch(
+          `${API_URL}/availability?from=${date}&to=${date}`,
+          { method: "GET" }
+        )
+        if (res4.ok) {
+          const availability: { days: { slots: Slot[] }[] } =
+            await res4.json()
+          setSlots(availability.days[0]?.slots ?? [])
+        }
       } catch (error) {
         console.error("Error fetching reservations:", error)
         addToast({
@@ -158,7 +226,8 @@ export default function PlanningDatePage() {
               <span className="text-green-600">
                 Vous avez réservé le{" "}
                 <b>terrain {currentReservation.court_number}</b> à{" "}
-                <b>{currentReservation.reservation_time}h</b>.
+                <b>{formatTime(currentReservation.start_time)}</b> pour{" "}
+                <b>{currentReservation.duration_minutes} minutes</b>.
               </span>
             )
           }
@@ -167,10 +236,13 @@ export default function PlanningDatePage() {
       </div>
 
       <Accordion variant="splitted">
-        {[16, 17, 18, 19, 20].map(hour => {
-          const total = 4
+        {slots.map(slot => {
+          const label = formatTime(slot.start_time)
+          const total = courts.length
           const used = reservations.filter(
-            r => r.reservation_time === hour
+            r =>
+              overlaps(r, slot) &&
+              courts.some(c => c.number === r.court_number)
           ).length
           const free = total - used
           let subtitle = `${free}/${total} terrains libres`
@@ -179,37 +251,73 @@ export default function PlanningDatePage() {
 
           return (
             <AccordionItem
-              key={hour}
-              aria-label={`${hour}h`}
-              title={`${hour}h`}
+              key={slot.start_time}
+              aria-label={label}
+              title={label}
               subtitle={subtitle}
             >
               <div>
                 <span>Terrains</span>
-                {[1, 2, 3, 4].map(court => {
+                {courts.map(({ number: court, name }) => {
                   const res = reservations.find(
-                    r => r.reservation_time === hour && r.court_number === court
+                    r => overlaps(r, slot) && r.court_number === court
                   )
                   return (
                     <div
                       key={court}
                       className="w-full flex justify-between items-center mt-2 ml-2"
                     >
-                      <span>{court}</span>
+                      <span>{name}</span>
                       {res ? (
                         <div className="flex items-center gap-2">
+                          {currentUserId === res.member_id && (
+                            <Button
+                              color="success"
+                              size="sm"
+                              onClick={async () => {
+                                try {
+                                  const checkedIn = await authenticatedFetch(
+                                    `${API_URL}/reservation/${res.id}/check-in`,
+                                    { method: "POST" }
+                                  )
+                                  if (!checkedIn.ok) {
+                                    const { error } = await checkedIn.json()
+                                    addToast({ title: error, color: "danger" })
+                                    return
+                                  }
+                                  addToast({
+                                    title: "Votre arrivée a été enregistrée.",
+                                    color: "success"
+                                  })
+                                } catch (error) {
+                                  console.error("Error checking in:", error)
+                                  addToast({
+                                    title: "Erreur lors de l'enregistrement",
+                                    color: "danger"
+                                  })
+                                }
+                              }}
+                            >
+                              Arrivé
+                            </Button>
+                          )}
                           {currentUserId === res.member_id ? (
                             <Button
                               color="danger"
                               size="sm"
                               onClick={async () => {
                                 try {
-                                  await authenticatedFetch(
+                                  const cancelled = await authenticatedFetch(
                                     `${API_URL}/reservation/${res.id}`,
                                     {
                                       method: "DELETE"
                                     }
                                   )
+                                  if (!cancelled.ok) {
+                                    const { error } = await cancelled.json()
+                                    addToast({ title: error, color: "danger" })
+                                    return
+                                  }
                                   addToast({
                                     title: "Votre réservation a été annulée.",
                                     color: "success"
@@ -240,6 +348,8 @@ export default function PlanningDatePage() {
                             <span className="text-gray-500">
                               Réservé par {res.member_first_name}{" "}
                               {res.member_last_name}
+                              {participantNames(res.participants) &&
+                                ` avec ${participantNames(res.participants)}`}
                             </span>
                           )}
                         </div>
@@ -250,7 +360,7 @@ export default function PlanningDatePage() {
                             size="sm"
                             onClick={async () => {
                               try {
-                                await authenticatedFetch(
+                                const created = await authenticatedFetch(
                                   `${API_URL}/reservation`,
                                   {
                                     method: "POST",
@@ -262,10 +372,16 @@ export default function PlanningDatePage() {
                                       member_id: currentUserId,
                                       court_number: court,
                                       reservation_date: date,
-                                      reservation_time: hour
+                                      start_time: slot.start_time,
+                                      duration_minutes: slotMinutes(slot)
                                     })
                                   }
                                 )
+                                if (!created.ok) {
+                                  const { error } = await created.json()
+                                  addToast({ title: error, color: "danger" })
+                                  return
+                                }
                                 addToast({
                                   title: "Votre réservation a été enregistrée.",
                                   color: "success"
@@ -0,0 +1,90 @@
+"use client"
+import React, { Suspense } from "react"
+import { Button, addToast } from "@heroui/react"
+import { useSearchParams } from "next/navigation"
+import { useAuth, authenticatedFetch } from "@/hooks/useAuth"
+
+const API_HOST = process.env.NEXT_PUBLIC_API_HOST!
+const API_PORT = process.env.NEXT_PUBLIC_API_PORT
+const API_URL = API_PORT ? `${API_HOST}:${API_PORT}` : API_HOST
+
+// Separate the component that uses useSearchParams
+function WaitlistClaimForm() {
+  useAuth({ requireAuth: true })
+  const searchParams = useSearchParams()
+  const token: string | null = searchParams.get("token")
+
+  const onClaim = async () => {
+    try {
+      const claimResponse = await authenticatedFetch(
+        `${API_URL}/waitlist/claim`,
+        {
+          method: "POST",
+          headers: { "Content-Type": "application/json" },
+          body: JSON.stringify({ token })
+        }
+      )
+      switch (claimResponse.status) {
+        case 200:
+          addToast({
+            title: "Votre réservation a été enregistrée.",
+            color: "success"
+          })
+          location.replace("/planning")
+          break
+        case 409:
+          addToast({
+            title: "Ce créneau a déjà été réservé par un autre membre.",
+            color: "danger"
+          })
+          break
+        case 410:
+          addToast({
+            title:
+              "Le lien est invalide ou a expiré, le créneau a été proposé au membre suivant.",
+            color: "danger",
+            timeout: 9999
+          })
+          break
+        default:
+          console.error(await claimResponse.json())
+          addToast({
+            title: "Ce créneau ne peut pas être réservé.",
+            color: "danger"
+          })
+      }
+    } catch (err: any) {
+      console.error(err)
+      addToast({
+        title: "Une erreur est survenue. Veuillez réessayer plus tard.",
+        color: "danger"
+      })
+    }
+  }
+
+  return (
+    <div>
+      <p>Un terrain s'est libéré sur le créneau que vous attendiez.</p>
+      <Button
+        className="mt-4"
+        color="primary"
+        isDisabled={!token}
+        onPress={onClaim}
+      >
+        Réserver ce créneau
+      </Button>
+    </div>
+  )
+}
+
+// Main page component with Suspense boundary
+export default function WaitlistClaimPage() {
+  return (
+    <div>
+      <h1 className="font-bold text-xl my-4">Liste d'attente</h1>
+      <Suspense fallback={<div className="h-10 bg-gray-200 rounded w-32" />}>
+        <WaitlistClaimForm />
+      </Suspense>
+    </div>
+  )
+}
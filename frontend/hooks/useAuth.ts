@@ -24,6 +24,7 @@ interface UseAuthOptions {
 
 interface StoredAuth {
   token: string
+  refreshToken: string
   userId: string
   phone: string
   isProfileComplete: boolean
@@ -75,17 +76,83 @@ export const getAuthToken = (): string | null => {
   return auth?.token || null
 }
 
+// Refresh tokens can only be used once, and reusing one revokes the session: concurrent
+// refreshes share the same request, and tabs take turns through a lock
+const REFRESH_LOCK = "beach_garden_refresh"
+let pendingRefresh: Promise<string | null> | null = null
+
+// Exchange the stored refresh token for a new access token.
+// Returns the new access token, or null if the session is no longer valid.
+export const refreshAuth = (): Promise<string | null> => {
+  if (!pendingRefresh) {
+    pendingRefresh = refreshWithLock().finally(() => {
+      pendingRefresh = null
+    })
+  }
+  return pendingRefresh
+}
+
+const refreshWithLock = (): Promise<string | null> => {
+  const refreshToken = getStoredAuth()?.refreshToken
+  if (!refreshToken) return Promise.resolve(null)
+
+  const refresh = () => refreshStoredAuth(refreshToken)
+  return navigator.locks
+    ? navigator.locks.request(REFRESH_LOCK, refresh)
+    : refresh()
+}
+
+const refreshStoredAuth = async (
+  refreshToken: string
+): Promise<string | null> => {
+  const auth = getStoredAuth()
+  if (!auth?.refreshToken) return null
+
+  // Already refreshed by another tab while waiting for the lock
+  if (auth.refreshToken !== refreshToken) return auth.token
+
+  try {
+    const response = await fetch(`${API_URL}/refresh-jwt`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ refresh_token: auth.refreshToken })
+    })
+
+    if (!response.ok) {
+      clearStoredAuth()
+      return null
+    }
+
+    const data = await response.json()
+    setStoredAuth({
+      ...auth,
+      token: data.token,
+      refreshToken: data.refresh_token,
+      isProfileComplete: data.is_profile_complete,
+      isAdmin: data.is_admin,
+      expiresAt: Date.now() + data.refresh_token_expires_in * 1000
+    })
+
+    return data.token
+  } catch (error) {
+    console.error("Token refresh failed:", error)
+    return null
+  }
+}
+
 // Export logout function for use in other components
 export const logout = async (): Promise<void> => {
-  const token = getAuthToken()
+  const auth = getStoredAuth()
 
-  if (token) {
+  if (auth) {
     try {
       await fetch(`${API_URL}/logout`, {
         method: "POST",
         headers: {
-          Authorization: `Bearer ${token}`
-        }
+          Authorization: `Bearer ${auth.token}`,
+          "Content-Type": "application/json"
+        },
+        body: JSON.stringify({ refresh_token: auth.refreshToken })
       })
     } catch (error) {
       console.error("Logout API call failed:", error)
@@ -139,13 +206,20 @@ export function useAuth(options: UseAuthOptions = {}) {
           return
         }
 
-        // Verify token with backend
-        const response = await fetch(`${API_URL}/verify-token`, {
-          method: "GET",
-          headers: {
-            Authorization: `Bearer ${storedAuth.token}`
-          }
-        })
+        // Verify token with backend, refreshing it once if it has expired
+        const verify = (token: string) =>
+          fetch(`${API_URL}/verify-token`, {
+            method: "GET",
+            headers: {
+              Authorization: `Bearer ${token}`
+            }
+          })
+
+        let response = await verify(storedAuth.token)
+        if (!response.ok) {
+          const refreshedToken = await refreshAuth()
+          if (refreshedToken) response = await verify(refreshedToken)
+        }
 
         if (!isMounted.current) return
 
@@ -165,7 +239,7 @@ export function useAuth(options: UseAuthOptions = {}) {
 
           // Update stored auth with fresh data
           setStoredAuth({
-            ...storedAuth,
+            ...(getStoredAuth() ?? storedAuth),
             isProfileComplete: data.is_profile_complete,
             isAdmin: data.is_admin
           })
@@ -249,11 +323,12 @@ export const login = async (
     // Store auth data
     setStoredAuth({
       token: data.token,
+      refreshToken: data.refresh_token,
       userId: data.id,
       phone: data.phone,
       isProfileComplete: data.is_profile_complete,
       isAdmin: data.is_admin,
-      expiresAt: Date.now() + 24 * 60 * 60 * 1000 // 24 hours
+      expiresAt: Date.now() + data.refresh_token_expires_in * 1000
     })
 
     return {
@@ -277,11 +352,22 @@ export const authenticatedFetch = async (
     throw new Error("No authentication token")
   }
 
-  return fetch(url, {
-    ...options,
-    headers: {
-      ...options.headers,
-      Authorization: `Bearer ${token}`
-    }
-  })
+  const send = (accessToken: string) =>
+    fetch(url, {
+      ...options,
+      headers: {
+        ...options.headers,
+        Authorization: `Bearer ${accessToken}`
+      }
+    })
+
+  const response = await send(token)
+
+  // Access tokens are short-lived: refresh once and retry
+  if (response.status === 401 || response.status === 410) {
+    const refreshedToken = await refreshAuth()
+    if (refreshedToken) return send(refreshedToken)
+  }
+
+  return response
 }